@@ -35,6 +35,7 @@
 // www.navitia.io
 
 pub mod comparator_type;
+pub mod engine_type;
 pub mod input_data_type;
 pub mod launch_params;
 pub mod request_params;
@@ -42,6 +43,7 @@ pub mod request_params;
 use std::fmt::{Debug, Display};
 
 pub use comparator_type::ComparatorType;
+pub use engine_type::EngineType;
 pub use input_data_type::InputDataType;
 pub use launch_params::LaunchParams;
 use loki::tracing::warn;
@@ -34,6 +34,7 @@
 // https://groups.google.com/d/forum/navitia
 // www.navitia.io
 
+use loki::request::comparator::Comparator;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone)]
@@ -42,7 +43,22 @@ pub enum ComparatorType {
     Occupancy,
     Basic,
     Robustness,
+    Co2,
+    LeastWalking,
 }
+
+impl ComparatorType {
+    pub fn comparator(&self) -> Comparator {
+        match self {
+            ComparatorType::Occupancy => Comparator::occupancy(),
+            ComparatorType::Basic => Comparator::basic(),
+            ComparatorType::Robustness => Comparator::robustness(),
+            ComparatorType::Co2 => Comparator::co2(),
+            ComparatorType::LeastWalking => Comparator::least_walking(),
+        }
+    }
+}
+
 impl std::str::FromStr for ComparatorType {
     type Err = ComparatorTypeConfigError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -50,6 +66,8 @@ impl std::str::FromStr for ComparatorType {
             "occupancy" => ComparatorType::Occupancy,
             "basic" => ComparatorType::Basic,
             "robustness" => ComparatorType::Robustness,
+            "co2" => ComparatorType::Co2,
+            "least_walking" => ComparatorType::LeastWalking,
             _ => {
                 return Err(ComparatorTypeConfigError {
                     comparator_type_name: s.to_string(),
@@ -72,6 +90,8 @@ impl std::fmt::Display for ComparatorType {
             ComparatorType::Occupancy => write!(f, "occupancy"),
             ComparatorType::Basic => write!(f, "basic"),
             ComparatorType::Robustness => write!(f, "robustness"),
+            ComparatorType::Co2 => write!(f, "co2"),
+            ComparatorType::LeastWalking => write!(f, "least_walking"),
         }
     }
 }
@@ -0,0 +1,93 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use serde::{Deserialize, Serialize};
+
+/// The engine used to compute the journeys of a request.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineType {
+    /// Multicriteria search, with all criteria of the comparator.
+    #[default]
+    Raptor,
+    /// Earliest arrival search, that provides at most one journey.
+    /// Only departure after requests are handled, arrival before requests
+    /// are solved with `Raptor`.
+    ConnectionScan,
+    /// Earliest arrival search for each number of legs, that uses the trip transfers
+    /// precomputed when loading the data (see `LaunchParams::trip_transfers`).
+    /// Only departure after requests are handled, and `Raptor` is used
+    /// when the trip transfers have not been computed.
+    TripBased,
+}
+
+impl std::str::FromStr for EngineType {
+    type Err = EngineTypeConfigError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let engine_type = match s {
+            "raptor" => EngineType::Raptor,
+            "connection_scan" => EngineType::ConnectionScan,
+            "trip_based" => EngineType::TripBased,
+            _ => {
+                return Err(EngineTypeConfigError {
+                    engine_type_name: s.to_string(),
+                })
+            }
+        };
+        Ok(engine_type)
+    }
+}
+
+impl std::fmt::Display for EngineType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineType::Raptor => write!(f, "raptor"),
+            EngineType::ConnectionScan => write!(f, "connection_scan"),
+            EngineType::TripBased => write!(f, "trip_based"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct EngineTypeConfigError {
+    engine_type_name: String,
+}
+
+impl std::fmt::Display for EngineTypeConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Bad engine type : `{}`", self.engine_type_name)
+    }
+}
@@ -58,6 +58,10 @@ pub struct LaunchParams {
     /// the transfer duration between a stop point and itself
     #[serde(default = "default_transfer_duration")]
     pub default_transfer_duration: PositiveDuration,
+
+    /// precompute the trip-to-trip transfers used by the trip based engine
+    #[serde(default)]
+    pub trip_transfers: bool,
 }
 
 pub const DEFAULT_TRANSFER_DURATION: &str = "00:01:00";
@@ -74,6 +78,7 @@ impl LaunchParams {
             input_data_type: InputDataType::Ntfs,
             default_transfer_duration: default_transfer_duration(),
             occupancy_data_path: None,
+            trip_transfers: false,
         }
     }
 }
@@ -37,9 +37,9 @@
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
-use loki::{PositiveDuration, RealTimeLevel};
+use loki::{request::comparator::Comparator, PositiveDuration, RealTimeLevel};
 
-use super::parse_env_var;
+use super::{parse_env_var, ComparatorType};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct RequestParams {
@@ -59,6 +59,29 @@ pub struct RequestParams {
     #[serde(default = "default_max_journey_duration")]
     pub max_journey_duration: PositiveDuration,
 
+    /// maximum walking duration of a journey, fallbacks and transfers included
+    #[serde(default = "default_max_walking_duration")]
+    pub max_walking_duration: PositiveDuration,
+
+    /// maximum walking duration of each transfer of a journey
+    #[serde(default = "default_max_transfer_walking_duration")]
+    pub max_transfer_walking_duration: PositiveDuration,
+
+    /// walking speed of the traveler, relative to the one used
+    /// to compute the transfer durations of the data
+    #[serde(default = "default_walking_speed_factor")]
+    pub walking_speed_factor: f64,
+
+    /// maximum duration of a wait for a vehicle between two vehicle legs
+    #[serde(default = "default_max_waiting_duration")]
+    pub max_waiting_duration: PositiveDuration,
+
+    /// maximum duration between the requested datetime and the first vehicle
+    /// (the last vehicle for arrive before requests).
+    /// Not limited when not provided.
+    #[serde(default)]
+    pub max_initial_waiting_duration: Option<PositiveDuration>,
+
     /// discard any journey that arrive later than
     /// the arrival time of another journey + too_late_threshold
     #[serde(default = "default_too_late_threshold")]
@@ -69,14 +92,50 @@ pub struct RequestParams {
     /// real_time : includes the real time updates
     #[serde(default = "default_real_time_level")]
     pub real_time_level: RealTimeLevel,
+
+    /// Before the multicriteria search, compute lower bounds on the travel duration
+    /// between the request stops and every stop, and use them to discard
+    /// partial journeys that cannot improve the journeys already found.
+    #[serde(default = "default_lower_bound_pruning")]
+    pub lower_bound_pruning: bool,
+
+    /// keep a journey dominated by another one when it arrives
+    /// at most pareto_slack_duration later than the dominating journey
+    #[serde(default = "default_pareto_slack_duration")]
+    pub pareto_slack_duration: PositiveDuration,
+
+    /// keep a journey dominated by another one when it arrives
+    /// later than the dominating journey by at most pareto_slack_percentage%
+    /// of the duration of the dominating journey
+    #[serde(default = "default_pareto_slack_percentage")]
+    pub pareto_slack_percentage: u32,
+
+    /// After a departure after search, replace each journey by the one that departs
+    /// the latest while arriving at the same time, found by an arrival before search.
+    #[serde(default = "default_tighten_journeys")]
+    pub tighten_journeys: bool,
+
+    /// comma separated list of the criteria used to compare journeys,
+    /// among time, nb_of_legs, walking, walking_duration, occupancy, uncertainty and co2.
+    /// When provided, it replaces the criteria given by the comparator type.
+    #[serde(default)]
+    pub criteria: Option<Comparator>,
 }
 
 pub const DEFAULT_LEG_ARRIVAL_PENALTY: &str = "00:02:00";
 pub const DEFAULT_LEG_WALKING_PENALTY: &str = "00:02:00";
 pub const DEFAULT_MAX_NB_LEGS: &str = "10";
 pub const DEFAULT_MAX_JOURNEY_DURATION: &str = "24:00:00";
+pub const DEFAULT_MAX_WALKING_DURATION: &str = "24:00:00";
+pub const DEFAULT_MAX_TRANSFER_WALKING_DURATION: &str = "24:00:00";
+pub const DEFAULT_WALKING_SPEED_FACTOR: &str = "1.0";
+pub const DEFAULT_MAX_WAITING_DURATION: &str = "24:00:00";
 pub const DEFAULT_TOO_LATE_THRESHOLD: &str = "02:00:00";
 pub const DEFAULT_REAL_TIME_LEVEL: &str = "base";
+pub const DEFAULT_LOWER_BOUND_PRUNING: &str = "false";
+pub const DEFAULT_PARETO_SLACK_DURATION: &str = "00:00:00";
+pub const DEFAULT_PARETO_SLACK_PERCENTAGE: &str = "0";
+pub const DEFAULT_TIGHTEN_JOURNEYS: &str = "false";
 
 pub fn default_leg_arrival_penalty() -> PositiveDuration {
     PositiveDuration::from_str(DEFAULT_LEG_ARRIVAL_PENALTY).unwrap()
@@ -94,6 +153,22 @@ pub fn default_max_journey_duration() -> PositiveDuration {
     PositiveDuration::from_str(DEFAULT_MAX_JOURNEY_DURATION).unwrap()
 }
 
+pub fn default_max_walking_duration() -> PositiveDuration {
+    PositiveDuration::from_str(DEFAULT_MAX_WALKING_DURATION).unwrap()
+}
+
+pub fn default_max_transfer_walking_duration() -> PositiveDuration {
+    PositiveDuration::from_str(DEFAULT_MAX_TRANSFER_WALKING_DURATION).unwrap()
+}
+
+pub fn default_walking_speed_factor() -> f64 {
+    f64::from_str(DEFAULT_WALKING_SPEED_FACTOR).unwrap()
+}
+
+pub fn default_max_waiting_duration() -> PositiveDuration {
+    PositiveDuration::from_str(DEFAULT_MAX_WAITING_DURATION).unwrap()
+}
+
 pub fn default_too_late_threshold() -> PositiveDuration {
     PositiveDuration::from_str(DEFAULT_TOO_LATE_THRESHOLD).unwrap()
 }
@@ -102,6 +177,22 @@ pub fn default_real_time_level() -> RealTimeLevel {
     RealTimeLevel::Base
 }
 
+pub fn default_lower_bound_pruning() -> bool {
+    bool::from_str(DEFAULT_LOWER_BOUND_PRUNING).unwrap()
+}
+
+pub fn default_pareto_slack_duration() -> PositiveDuration {
+    PositiveDuration::from_str(DEFAULT_PARETO_SLACK_DURATION).unwrap()
+}
+
+pub fn default_pareto_slack_percentage() -> u32 {
+    u32::from_str(DEFAULT_PARETO_SLACK_PERCENTAGE).unwrap()
+}
+
+pub fn default_tighten_journeys() -> bool {
+    bool::from_str(DEFAULT_TIGHTEN_JOURNEYS).unwrap()
+}
+
 impl Default for RequestParams {
     fn default() -> Self {
         Self {
@@ -109,8 +200,18 @@ impl Default for RequestParams {
             leg_walking_penalty: default_leg_walking_penalty(),
             max_nb_of_legs: default_max_nb_of_legs(),
             max_journey_duration: default_max_journey_duration(),
+            max_walking_duration: default_max_walking_duration(),
+            max_transfer_walking_duration: default_max_transfer_walking_duration(),
+            walking_speed_factor: default_walking_speed_factor(),
+            max_waiting_duration: default_max_waiting_duration(),
+            max_initial_waiting_duration: None,
             too_late_threshold: default_too_late_threshold(),
             real_time_level: default_real_time_level(),
+            lower_bound_pruning: default_lower_bound_pruning(),
+            pareto_slack_duration: default_pareto_slack_duration(),
+            pareto_slack_percentage: default_pareto_slack_percentage(),
+            tighten_journeys: default_tighten_journeys(),
+            criteria: None,
         }
     }
 }
@@ -141,6 +242,35 @@ impl RequestParams {
             PositiveDuration::from_str,
         );
 
+        let max_walking_duration = parse_env_var(
+            "LOKI_MAX_WALKING_DURATION",
+            default_max_walking_duration(),
+            PositiveDuration::from_str,
+        );
+
+        let max_transfer_walking_duration = parse_env_var(
+            "LOKI_MAX_TRANSFER_WALKING_DURATION",
+            default_max_transfer_walking_duration(),
+            PositiveDuration::from_str,
+        );
+
+        let walking_speed_factor = parse_env_var(
+            "LOKI_WALKING_SPEED_FACTOR",
+            default_walking_speed_factor(),
+            f64::from_str,
+        );
+
+        let max_waiting_duration = parse_env_var(
+            "LOKI_MAX_WAITING_DURATION",
+            default_max_waiting_duration(),
+            PositiveDuration::from_str,
+        );
+
+        let max_initial_waiting_duration =
+            parse_env_var("LOKI_MAX_INITIAL_WAITING_DURATION", None, |s| {
+                PositiveDuration::from_str(s).map(Some)
+            });
+
         let too_late_threshold = parse_env_var(
             "LOKI_TOO_LATE_THRESHOLD",
             default_too_late_threshold(),
@@ -153,13 +283,57 @@ impl RequestParams {
             RealTimeLevel::from_str,
         );
 
+        let lower_bound_pruning = parse_env_var(
+            "LOKI_LOWER_BOUND_PRUNING",
+            default_lower_bound_pruning(),
+            bool::from_str,
+        );
+
+        let pareto_slack_duration = parse_env_var(
+            "LOKI_PARETO_SLACK_DURATION",
+            default_pareto_slack_duration(),
+            PositiveDuration::from_str,
+        );
+
+        let pareto_slack_percentage = parse_env_var(
+            "LOKI_PARETO_SLACK_PERCENTAGE",
+            default_pareto_slack_percentage(),
+            u32::from_str,
+        );
+
+        let tighten_journeys = parse_env_var(
+            "LOKI_TIGHTEN_JOURNEYS",
+            default_tighten_journeys(),
+            bool::from_str,
+        );
+
+        let criteria = parse_env_var("LOKI_CRITERIA", None, |s| Comparator::from_str(s).map(Some));
+
         Self {
             leg_arrival_penalty,
             leg_walking_penalty,
             max_nb_of_legs,
             max_journey_duration,
+            max_walking_duration,
+            max_transfer_walking_duration,
+            walking_speed_factor,
+            max_waiting_duration,
+            max_initial_waiting_duration,
             too_late_threshold,
             real_time_level,
+            lower_bound_pruning,
+            pareto_slack_duration,
+            pareto_slack_percentage,
+            tighten_journeys,
+            criteria,
+        }
+    }
+
+    /// Returns the comparator to use for a request with `comparator_type`.
+    pub fn comparator(&self, comparator_type: ComparatorType) -> Comparator {
+        match &self.criteria {
+            Some(comparator) => comparator.clone(),
+            None => comparator_type.comparator(),
         }
     }
 }
@@ -54,7 +54,7 @@ pub fn read(launch_params: &config::LaunchParams) -> Result<(TransitData, BaseMo
         launch_params.default_transfer_duration,
     )?;
 
-    let data = build_transit_data(&base_model);
+    let data = build_transit_data_with_trip_transfers(&base_model, launch_params.trip_transfers);
 
     Ok((data, base_model))
 }
@@ -186,13 +186,22 @@ fn read_occupancy_data_from_reader<R: std::io::Read>(
 }
 
 pub fn build_transit_data(base_model: &BaseModel) -> TransitData {
+    build_transit_data_with_trip_transfers(base_model, false)
+}
+
+/// Same as `build_transit_data()`, and also precomputes the trip transfers
+/// used by the trip based engine when `with_trip_transfers` is true.
+pub fn build_transit_data_with_trip_transfers(
+    base_model: &BaseModel,
+    with_trip_transfers: bool,
+) -> TransitData {
     info!(
         "Number of vehicle journeys : {}",
         base_model.nb_of_vehicle_journeys()
     );
 
     let start_build_time = SystemTime::now();
-    let data = TransitData::new(base_model);
+    let mut data = TransitData::new(base_model);
     let data_build_duration = timer::duration_since(start_build_time);
     info!("Data constructed in {} ms", data_build_duration);
     info!("Number of missions {} ", data.nb_of_missions());
@@ -203,5 +212,15 @@ pub fn build_transit_data(base_model: &BaseModel) -> TransitData {
         data.calendar().last_date()
     );
 
+    if with_trip_transfers {
+        let start_trip_transfers_time = SystemTime::now();
+        data.compute_trip_transfers();
+        info!(
+            "{} trip transfers computed in {} ms",
+            data.nb_of_trip_transfers(),
+            timer::duration_since(start_trip_transfers_time)
+        );
+    }
+
     data
 }
@@ -37,22 +37,27 @@
 use std::{fmt::Debug, time::SystemTime};
 
 use loki::{
-    filters::Filters,
+    chrono::Duration,
+    filters::{Filters, VehiclePenalties},
+    isochrone::{self, IsochroneOn, IsochroneResponse},
+    matrix::{self, InputStops, MatrixResponse},
     models::ModelRefs,
     places_nearby,
     request::generic_request,
     schedule::{self, ScheduleRequestError, ScheduleRequestInput, ScheduleResponse},
-    tracing::{debug, info, trace},
-    DataWithIters,
+    tracing::{debug, info, trace, warn},
+    Cancellation, ComputeStatus, DataWithIters, EngineStats, NaiveDateTime, PositiveDuration,
+    SearchTrace,
 };
 
 use loki::{
-    response, transit_data_filtered::FilterMemory, BadRequest, MultiCriteriaRaptor, RequestDebug,
-    RequestIO, RequestInput, RequestTypes as RequestTypesTrait, RequestWithIters,
+    response, transit_data_filtered::FilterMemory, BadRequest, ConnectionScan, MultiCriteriaRaptor,
+    RequestDebug, RequestIO, RequestInput, RequestTypes as RequestTypesTrait, RequestWithIters,
+    TripBased,
 };
 
-use super::config;
 use crate::{
+    config::EngineType,
     datetime::DateTimeRepresent,
     loki::{DataTrait, TransitData},
     timer,
@@ -63,48 +68,337 @@ use loki::{
     transit_data_filtered::TransitDataFiltered,
 };
 
+mod explain;
+pub use explain::NoJourneyReason;
+
+/// Journeys computed by `Solver::solve_journey_request_with_cancellation()`
+pub struct JourneysSolution {
+    pub journeys: Vec<response::Response>,
+    pub status: ComputeStatus,
+    pub stats: EngineStats,
+    /// The steps of the search, when the trace recording is enabled
+    /// and the journeys were computed with `EngineType::Raptor`.
+    pub trace: Option<SearchTrace>,
+}
+
 pub struct Solver {
     engine: MultiCriteriaRaptor<RequestTypes>,
 
+    connection_scan: ConnectionScan<RequestTypes>,
+
+    trip_based: TripBased<RequestTypes>,
+
     filter_memory: FilterMemory,
+
+    vehicle_penalties: VehiclePenalties,
+
+    nb_of_threads: usize,
+
+    engine_type: EngineType,
 }
 
 impl Solver {
     pub fn new(nb_of_stops: usize, nb_of_missions: usize) -> Self {
         Self {
             engine: MultiCriteriaRaptor::new(nb_of_stops, nb_of_missions),
+            connection_scan: ConnectionScan::new(nb_of_stops),
+            trip_based: TripBased::new(nb_of_stops),
             filter_memory: FilterMemory::new(),
+            vehicle_penalties: VehiclePenalties::new(),
+            nb_of_threads: 1,
+            engine_type: EngineType::default(),
         }
     }
 
+    /// Sets the engine used by `solve_journey_request()`
+    /// and `solve_journey_request_with_cancellation()`.
+    ///
+    /// With `EngineType::ConnectionScan`, departure after requests provide
+    /// at most one journey : the one that arrives the earliest.
+    /// With `EngineType::TripBased`, departure after requests provide the journeys
+    /// that arrive the earliest for each number of legs, provided that
+    /// the trip transfers have been computed in the data.
+    /// Arrival before requests, and requests with via stops,
+    /// are always solved with `EngineType::Raptor`.
+    pub fn set_engine_type(&mut self, engine_type: EngineType) {
+        self.engine_type = engine_type;
+    }
+
+    /// Sets the number of threads used to ride the missions
+    /// in each round of a journeys computation.
+    ///
+    /// With `1` (the default), journeys are computed in the calling thread.
+    /// The journeys obtained do not depend on `nb_of_threads`.
+    pub fn set_nb_of_threads(&mut self, nb_of_threads: usize) {
+        self.nb_of_threads = nb_of_threads.max(1);
+    }
+
+    /// Enables (or disables) the recording of a `SearchTrace`
+    /// by `solve_journey_request_with_cancellation()`.
+    ///
+    /// Only journeys computed with `EngineType::Raptor` are traced.
+    pub fn set_trace_recording(&mut self, record: bool) {
+        self.engine.set_trace_recording(record);
+    }
+
     fn fill_allowed_stops_and_vehicles(&mut self, model: &ModelRefs<'_>, filters: &Filters) {
         self.filter_memory
             .fill_allowed_stops_and_vehicles(filters, model);
     }
 
+    // computed once per query, and shared by all the requests solved for this query
+    fn fill_vehicle_penalties(&mut self, model: &ModelRefs<'_>, request_input: &RequestInput) {
+        self.vehicle_penalties
+            .fill(&request_input.preferences, model);
+    }
+
     pub fn solve_journey_request(
         &mut self,
         data: &TransitData,
         model: &ModelRefs<'_>,
         request_input: &RequestInput,
         has_filters: Option<Filters>,
-        comparator_type: config::ComparatorType,
         datetime_represent: DateTimeRepresent,
     ) -> Result<Vec<response::Response>, BadRequest>
     where
         Self: Sized,
     {
-        if let Some(filters) = has_filters {
+        self.solve_journey_request_with_cancellation(
+            data,
+            model,
+            request_input,
+            has_filters,
+            datetime_represent,
+            &Cancellation::none(),
+        )
+        .map(|solution| solution.journeys)
+    }
+
+    /// Same as `solve_journey_request()`, but the computation is interrupted
+    /// when `cancellation` is triggered.
+    ///
+    /// When interrupted, the journeys found so far are returned
+    /// along with `ComputeStatus::Interrupted`.
+    ///
+    /// The statistics of the engine are also returned, to help understanding slow requests.
+    #[allow(clippy::too_many_arguments)]
+    pub fn solve_journey_request_with_cancellation(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        has_filters: Option<Filters>,
+        datetime_represent: DateTimeRepresent,
+        cancellation: &Cancellation,
+    ) -> Result<JourneysSolution, BadRequest>
+    where
+        Self: Sized,
+    {
+        self.fill_vehicle_penalties(model, request_input);
+        let use_filters = if let Some(filters) = has_filters {
             self.fill_allowed_stops_and_vehicles(model, &filters);
+            true
+        } else {
+            false
+        };
+        self.solve_with_filled_filters(
+            data,
+            model,
+            request_input,
+            use_filters,
+            datetime_represent,
+            cancellation,
+        )
+    }
+
+    /// Solves `request_input` at each of `datetimes` (at `request_input.datetime` when empty),
+    /// and merges the journeys found, without duplicates.
+    ///
+    /// While less than `min_nb_of_journeys` journeys with distinct vehicles are found,
+    /// the request is solved again just after the earliest departure (just before the latest
+    /// arrival for arrival before requests) of the journeys found by the previous computation,
+    /// as long as this new datetime is within `horizon` of the first datetime.
+    ///
+    /// The returned stats and trace are the ones of the last computation.
+    #[allow(clippy::too_many_arguments)]
+    pub fn solve_journey_request_at_datetimes(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        datetimes: &[NaiveDateTime],
+        min_nb_of_journeys: usize,
+        horizon: PositiveDuration,
+        has_filters: Option<Filters>,
+        datetime_represent: DateTimeRepresent,
+        cancellation: &Cancellation,
+    ) -> Result<JourneysSolution, BadRequest> {
+        self.fill_vehicle_penalties(model, request_input);
+        let use_filters = if let Some(filters) = has_filters {
+            self.fill_allowed_stops_and_vehicles(model, &filters);
+            true
+        } else {
+            false
+        };
+        let datetimes = if datetimes.is_empty() {
+            std::slice::from_ref(&request_input.datetime)
+        } else {
+            datetimes
+        };
+
+        let mut request_input = request_input.clone();
+        let mut journeys: Vec<response::Response> = Vec::new();
+        let mut has_solution = None;
+        let mut has_next_datetime = None;
+        for datetime in datetimes {
+            request_input.datetime = *datetime;
+            let mut solution = self.solve_with_filled_filters(
+                data,
+                model,
+                &request_input,
+                use_filters,
+                datetime_represent,
+                cancellation,
+            )?;
+            has_next_datetime = next_datetime(&solution.journeys, datetime_represent);
+            merge_journeys(&mut journeys, &mut solution);
+            let is_interrupted = solution.status == ComputeStatus::Interrupted;
+            has_solution = Some(solution);
+            if is_interrupted {
+                break;
+            }
+        }
+        let mut solution = has_solution.unwrap();
+
+        let horizon = Duration::seconds(i64::from(horizon.total_seconds_u32()));
+        let first_datetime = datetimes[0];
+        while journeys.len() < min_nb_of_journeys && solution.status == ComputeStatus::Complete {
+            let Some(datetime) = has_next_datetime else {
+                break;
+            };
+            if datetime - first_datetime > horizon || first_datetime - datetime > horizon {
+                break;
+            }
+            debug!(
+                "{} journeys found, solving again at {}",
+                journeys.len(),
+                datetime
+            );
+            request_input.datetime = datetime;
+            solution = match self.solve_with_filled_filters(
+                data,
+                model,
+                &request_input,
+                use_filters,
+                datetime_represent,
+                cancellation,
+            ) {
+                Ok(solution) => solution,
+                // the datetime is out of the validity period of the data
+                Err(BadRequest::RequestedDatetime) => break,
+                Err(err) => return Err(err),
+            };
+            has_next_datetime = next_datetime(&solution.journeys, datetime_represent);
+            merge_journeys(&mut journeys, &mut solution);
+        }
+
+        solution.journeys = journeys;
+        Ok(solution)
+    }
 
+    // solves with the filters previously filled in `self.filter_memory`
+    // when `use_filters` is set
+    fn solve_with_filled_filters(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        use_filters: bool,
+        datetime_represent: DateTimeRepresent,
+        cancellation: &Cancellation,
+    ) -> Result<JourneysSolution, BadRequest> {
+        let has_vias = !request_input.via_stops_and_stay_duration.is_empty();
+        let engine_type = match (self.engine_type, datetime_represent) {
+            (EngineType::Raptor, _) => EngineType::Raptor,
+            (EngineType::ConnectionScan | EngineType::TripBased, _) if has_vias => {
+                warn!("Only the raptor engine can solve requests with via stops. I'll use raptor instead.");
+                EngineType::Raptor
+            }
+            (EngineType::ConnectionScan, DateTimeRepresent::Departure) => {
+                EngineType::ConnectionScan
+            }
+            (EngineType::ConnectionScan, DateTimeRepresent::Arrival) => {
+                warn!("The connection scan engine cannot solve arrival before requests. I'll use raptor instead.");
+                EngineType::Raptor
+            }
+            (EngineType::TripBased, DateTimeRepresent::Departure) => {
+                if data.has_trip_transfers() {
+                    EngineType::TripBased
+                } else {
+                    warn!("The trip transfers have not been computed, so the trip based engine cannot be used. I'll use raptor instead.");
+                    EngineType::Raptor
+                }
+            }
+            (EngineType::TripBased, DateTimeRepresent::Arrival) => {
+                warn!("The trip based engine cannot solve arrival before requests. I'll use raptor instead.");
+                EngineType::Raptor
+            }
+        };
+        let mut solution = match engine_type {
+            EngineType::Raptor => self.solve_with_raptor(
+                data,
+                model,
+                request_input,
+                use_filters,
+                datetime_represent,
+                cancellation,
+            )?,
+            EngineType::ConnectionScan => self.solve_with_connection_scan(
+                data,
+                model,
+                request_input,
+                use_filters,
+                cancellation,
+            )?,
+            EngineType::TripBased => {
+                self.solve_with_trip_based(data, model, request_input, use_filters, cancellation)?
+            }
+        };
+        if request_input.tighten_journeys
+            && matches!(datetime_represent, DateTimeRepresent::Departure)
+        {
+            self.tighten_journeys(
+                data,
+                model,
+                request_input,
+                use_filters,
+                &mut solution,
+                cancellation,
+            )?;
+        }
+        Ok(solution)
+    }
+
+    fn solve_with_raptor(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        use_filters: bool,
+        datetime_represent: DateTimeRepresent,
+        cancellation: &Cancellation,
+    ) -> Result<JourneysSolution, BadRequest> {
+        let (journeys, status) = if use_filters {
             let filtered_data = TransitDataFiltered::new(data, &self.filter_memory);
             select_journeys_implem_and_solve(
                 &mut self.engine,
                 &filtered_data,
                 model,
                 request_input,
-                comparator_type,
+                &self.vehicle_penalties,
                 datetime_represent,
+                self.nb_of_threads,
+                cancellation,
             )
         } else {
             select_journeys_implem_and_solve(
@@ -112,12 +406,334 @@ impl Solver {
                 data,
                 model,
                 request_input,
-                comparator_type,
+                &self.vehicle_penalties,
+                datetime_represent,
+                self.nb_of_threads,
+                cancellation,
+            )
+        }?;
+        Ok(JourneysSolution {
+            journeys,
+            status,
+            stats: self.engine.stats().clone(),
+            trace: self.engine.trace().cloned(),
+        })
+    }
+
+    // replaces each journey of `solution` by the journey found by an arrival before search
+    // that departs the latest while arriving at the same time, with at most as many legs
+    fn tighten_journeys(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        use_filters: bool,
+        solution: &mut JourneysSolution,
+        cancellation: &Cancellation,
+    ) -> Result<(), BadRequest> {
+        let mut tightened_journeys: Vec<response::Response> =
+            Vec::with_capacity(solution.journeys.len());
+        let mut journeys = std::mem::take(&mut solution.journeys).into_iter();
+        for journey in journeys.by_ref() {
+            let arrival_datetime = journey.arrival.to_datetime;
+            // the tightened journey must not depart before the requested datetime
+            let has_max_journey_duration = (arrival_datetime - request_input.datetime)
+                .to_std()
+                .ok()
+                .and_then(|duration| u32::try_from(duration.as_secs()).ok())
+                .map(|seconds| PositiveDuration::from_hms(0, 0, seconds));
+            let mut tightened_journey = journey;
+            if let Some(max_journey_duration) = has_max_journey_duration {
+                let mut arrival_input = request_input.clone();
+                arrival_input.datetime = arrival_datetime;
+                arrival_input.max_journey_duration = max_journey_duration;
+                let arrival_solution = self.solve_with_raptor(
+                    data,
+                    model,
+                    &arrival_input,
+                    use_filters,
+                    DateTimeRepresent::Arrival,
+                    cancellation,
+                )?;
+                if arrival_solution.status == ComputeStatus::Interrupted {
+                    solution.status = ComputeStatus::Interrupted;
+                    tightened_journeys.push(tightened_journey);
+                    break;
+                }
+                let has_latest_journey = arrival_solution
+                    .journeys
+                    .into_iter()
+                    .filter(|candidate| {
+                        candidate.nb_of_vehicles() <= tightened_journey.nb_of_vehicles()
+                    })
+                    .max_by_key(latest_departure);
+                if let Some(latest_journey) = has_latest_journey {
+                    if latest_departure(&latest_journey) > latest_departure(&tightened_journey) {
+                        trace!(
+                            "Journey tightened to depart at {}",
+                            latest_departure(&latest_journey)
+                        );
+                        tightened_journey = latest_journey;
+                    }
+                }
+            }
+            if !tightened_journeys
+                .iter()
+                .any(|other| other.has_same_vehicles(&tightened_journey))
+            {
+                tightened_journeys.push(tightened_journey);
+            }
+        }
+        // the journeys not tightened because of an interruption
+        tightened_journeys.extend(journeys);
+        solution.journeys = tightened_journeys;
+        Ok(())
+    }
+
+    fn solve_with_connection_scan(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        use_filters: bool,
+        cancellation: &Cancellation,
+    ) -> Result<JourneysSolution, BadRequest> {
+        let (journeys, status) = if use_filters {
+            let filtered_data = TransitDataFiltered::new(data, &self.filter_memory);
+            solve_with_connection_scan_inner(
+                &mut self.connection_scan,
+                &filtered_data,
+                model,
+                request_input,
+                &self.vehicle_penalties,
+                cancellation,
+            )
+        } else {
+            solve_with_connection_scan_inner(
+                &mut self.connection_scan,
+                data,
+                model,
+                request_input,
+                &self.vehicle_penalties,
+                cancellation,
+            )
+        }?;
+        Ok(JourneysSolution {
+            journeys,
+            status,
+            stats: self.connection_scan.stats().clone(),
+            trace: None,
+        })
+    }
+
+    fn solve_with_trip_based(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        use_filters: bool,
+        cancellation: &Cancellation,
+    ) -> Result<JourneysSolution, BadRequest> {
+        let (journeys, status) = if use_filters {
+            let filtered_data = TransitDataFiltered::new(data, &self.filter_memory);
+            solve_with_trip_based_inner(
+                &mut self.trip_based,
+                &filtered_data,
+                model,
+                request_input,
+                &self.vehicle_penalties,
+                cancellation,
+            )
+        } else {
+            solve_with_trip_based_inner(
+                &mut self.trip_based,
+                data,
+                model,
+                request_input,
+                &self.vehicle_penalties,
+                cancellation,
+            )
+        }?;
+        Ok(JourneysSolution {
+            journeys,
+            status,
+            stats: self.trip_based.stats().clone(),
+            trace: None,
+        })
+    }
+
+    /// Computes all the Pareto-optimal journeys that depart (or arrive, depending on `datetime_represent`)
+    /// in the window `[request_input.datetime, request_input.datetime + range_duration]`.
+    ///
+    /// The departure (resp. arrival) datetime is considered as an additional criterion,
+    /// so a journey that leaves earlier (resp. arrives later) than another one is kept
+    /// only if it is better on some other criterion.
+    pub fn solve_range_journey_request(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        range_duration: PositiveDuration,
+        has_filters: Option<Filters>,
+        datetime_represent: DateTimeRepresent,
+    ) -> Result<Vec<response::Response>, BadRequest>
+    where
+        Self: Sized,
+    {
+        self.solve_range_journey_request_with_cancellation(
+            data,
+            model,
+            request_input,
+            range_duration,
+            has_filters,
+            datetime_represent,
+            &Cancellation::none(),
+        )
+        .map(|solution| solution.journeys)
+    }
+
+    /// Same as `solve_range_journey_request()`, but the computation is interrupted
+    /// when `cancellation` is triggered.
+    ///
+    /// When interrupted, no further datetime of the range is explored, and the journeys
+    /// found so far are returned along with `ComputeStatus::Interrupted`.
+    ///
+    /// The returned stats are accumulated over all iterations of the range.
+    #[allow(clippy::too_many_arguments)]
+    pub fn solve_range_journey_request_with_cancellation(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        range_duration: PositiveDuration,
+        has_filters: Option<Filters>,
+        datetime_represent: DateTimeRepresent,
+        cancellation: &Cancellation,
+    ) -> Result<JourneysSolution, BadRequest>
+    where
+        Self: Sized,
+    {
+        self.fill_vehicle_penalties(model, request_input);
+        let (journeys, status) = if let Some(filters) = has_filters {
+            self.fill_allowed_stops_and_vehicles(model, &filters);
+
+            let filtered_data = TransitDataFiltered::new(data, &self.filter_memory);
+            select_range_implem_and_solve(
+                &mut self.engine,
+                &filtered_data,
+                model,
+                request_input,
+                &self.vehicle_penalties,
+                range_duration,
+                datetime_represent,
+                cancellation,
+            )
+        } else {
+            select_range_implem_and_solve(
+                &mut self.engine,
+                data,
+                model,
+                request_input,
+                &self.vehicle_penalties,
+                range_duration,
+                datetime_represent,
+                cancellation,
+            )
+        }?;
+        Ok(JourneysSolution {
+            journeys,
+            status,
+            stats: self.engine.stats().clone(),
+            trace: self.engine.trace().cloned(),
+        })
+    }
+
+    /// Computes the best datetime at every stop that can be reached from the departures
+    /// (or that can reach the arrivals, depending on `datetime_represent`)
+    /// of `request_input` within `request_input.max_journey_duration`.
+    pub fn solve_isochrone(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        has_filters: Option<Filters>,
+        datetime_represent: DateTimeRepresent,
+    ) -> Result<Vec<IsochroneResponse>, BadRequest>
+    where
+        Self: Sized,
+    {
+        self.fill_vehicle_penalties(model, request_input);
+        if let Some(filters) = has_filters {
+            self.fill_allowed_stops_and_vehicles(model, &filters);
+
+            let filtered_data = TransitDataFiltered::new(data, &self.filter_memory);
+            select_isochrone_implem_and_solve(
+                &mut self.engine,
+                &filtered_data,
+                model,
+                request_input,
+                &self.vehicle_penalties,
+                datetime_represent,
+            )
+        } else {
+            select_isochrone_implem_and_solve(
+                &mut self.engine,
+                data,
+                model,
+                request_input,
+                &self.vehicle_penalties,
                 datetime_represent,
             )
         }
     }
 
+    /// Computes the earliest arrival at each of the `destinations`, for each of the `origins`.
+    ///
+    /// See `loki::matrix::solve_matrix_request()`.
+    pub fn solve_matrix(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        origins: &[InputStops],
+        destinations: &[InputStops],
+        has_filters: Option<Filters>,
+    ) -> Result<MatrixResponse, BadRequest>
+    where
+        Self: Sized,
+    {
+        let start_compute_time = SystemTime::now();
+        let result = if let Some(filters) = has_filters {
+            self.fill_allowed_stops_and_vehicles(model, &filters);
+
+            let filtered_data = TransitDataFiltered::new(data, &self.filter_memory);
+            matrix::solve_matrix_request(
+                &mut self.engine,
+                &filtered_data,
+                model,
+                request_input,
+                origins,
+                destinations,
+            )
+        } else {
+            matrix::solve_matrix_request(
+                &mut self.engine,
+                data,
+                model,
+                request_input,
+                origins,
+                destinations,
+            )
+        };
+        info!(
+            "Computed a matrix of {} origins and {} destinations in {} ms",
+            origins.len(),
+            destinations.len(),
+            timer::duration_since(start_compute_time),
+        );
+        result
+    }
+
     pub fn solve_schedule(
         &mut self,
         data: &TransitData,
@@ -148,15 +764,19 @@ impl Solver {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn select_journeys_implem_and_solve<Data>(
     engine: &mut MultiCriteriaRaptor<RequestTypes>,
     data: &Data,
     model: &ModelRefs<'_>,
     request_input: &RequestInput,
-    comparator_type: config::ComparatorType,
+    vehicle_penalties: &VehiclePenalties,
     datetime_represent: DateTimeRepresent,
-) -> Result<Vec<response::Response>, BadRequest>
+    nb_of_threads: usize,
+    cancellation: &Cancellation,
+) -> Result<(Vec<response::Response>, ComputeStatus), BadRequest>
 where
+    Data: Sync,
     Data: DataWithIters<
         Position = generic_request::Position,
         Mission = generic_request::Mission,
@@ -166,7 +786,6 @@ where
     >,
 {
     use crate::datetime::DateTimeRepresent::{Arrival, Departure};
-    use config::ComparatorType::{Basic, Occupancy, Robustness};
 
     trace!(
         "departures {:#?}",
@@ -178,7 +797,7 @@ where
     );
     debug!("datetime : {}", request_input.datetime);
     debug!("datetime_represents : {}", datetime_represent);
-    debug!("comparator type : {}", comparator_type);
+    debug!("comparator : {}", request_input.comparator);
     debug!("realtime level : {:?}", request_input.real_time_level);
     debug!(
         "leg_arrival_penalty : {}",
@@ -194,63 +813,382 @@ where
         request_input.max_journey_duration
     );
     debug!("too_late_threshold : {}", request_input.too_late_threshold);
+    debug!(
+        "lower_bound_pruning : {}",
+        request_input.lower_bound_pruning
+    );
+    debug!(
+        "pareto_slack : {} or {}%",
+        request_input.pareto_slack_duration, request_input.pareto_slack_percentage
+    );
 
-    let responses = match (datetime_represent, comparator_type) {
-        (Arrival, Occupancy) => {
-            let request = request::arrive_before::occupancy_comparator::Request::new(
-                model,
-                data,
-                request_input,
-            )?;
-            solve_journeys_request_inner(engine, &request, data)
-        }
-        (Departure, Occupancy) => {
-            let request = request::depart_after::occupancy_comparator::Request::new(
+    let responses = match datetime_represent {
+        Arrival => {
+            let request = request::arrive_before::Request::new(
                 model,
                 data,
                 request_input,
+                vehicle_penalties,
             )?;
-            solve_journeys_request_inner(engine, &request, data)
-        }
-        (Arrival, Basic) => {
-            let request =
-                request::arrive_before::basic_comparator::Request::new(model, data, request_input)?;
-            solve_journeys_request_inner(engine, &request, data)
+            solve_journeys_request_inner(engine, &request, data, nb_of_threads, cancellation)
         }
-        (Departure, Basic) => {
+        Departure => {
             let request =
-                request::depart_after::basic_comparator::Request::new(model, data, request_input)?;
-            solve_journeys_request_inner(engine, &request, data)
+                request::depart_after::Request::new(model, data, request_input, vehicle_penalties)?;
+            solve_journeys_request_inner(engine, &request, data, nb_of_threads, cancellation)
         }
+    };
+
+    Ok(responses)
+}
+
+fn solve_with_connection_scan_inner<Data>(
+    engine: &mut ConnectionScan<RequestTypes>,
+    data: &Data,
+    model: &ModelRefs<'_>,
+    request_input: &RequestInput,
+    vehicle_penalties: &VehiclePenalties,
+    cancellation: &Cancellation,
+) -> Result<(Vec<response::Response>, ComputeStatus), BadRequest>
+where
+    Data: DataWithIters<
+        Position = generic_request::Position,
+        Mission = generic_request::Mission,
+        Stop = generic_request::Stop,
+        Trip = generic_request::Trip,
+        Transfer = generic_request::Transfer,
+    >,
+{
+    debug!(
+        "connection scan at {}, realtime level : {:?}, max_journey_duration : {}",
+        request_input.datetime, request_input.real_time_level, request_input.max_journey_duration
+    );
+    let request =
+        request::depart_after::Request::new(model, data, request_input, vehicle_penalties)?;
+
+    let start_compute_time = SystemTime::now();
+    let status = engine.compute(
+        &request,
+        data,
+        request_input.real_time_level,
+        request_input.max_journey_duration,
+        cancellation,
+    );
+    if status == ComputeStatus::Interrupted {
+        warn!(
+            "Connection scan interrupted after {} ms.",
+            timer::duration_since(start_compute_time),
+        );
+    }
+    info!(
+        "Computed {} journeys in {} ms with connection scan",
+        engine.nb_of_journeys(),
+        timer::duration_since(start_compute_time),
+    );
+    debug!("Engine stats : {}", engine.stats());
+
+    let responses = engine
+        .responses()
+        .filter_map(|pt_journey| {
+            request
+                .create_response(pt_journey)
+                .map_err(|err| {
+                    trace!(
+                        "An error occured while converting an engine journey to response. {:?}",
+                        err
+                    );
+                })
+                .ok()
+        })
+        .map(|journey| journey.to_response(data))
+        .collect();
+
+    Ok((responses, status))
+}
 
-        (Arrival, Robustness) => {
-            let request = request::arrive_before::robustness_comparator::Request::new(
+fn solve_with_trip_based_inner<Data>(
+    engine: &mut TripBased<RequestTypes>,
+    data: &Data,
+    model: &ModelRefs<'_>,
+    request_input: &RequestInput,
+    vehicle_penalties: &VehiclePenalties,
+    cancellation: &Cancellation,
+) -> Result<(Vec<response::Response>, ComputeStatus), BadRequest>
+where
+    Data: DataWithIters<
+        Position = generic_request::Position,
+        Mission = generic_request::Mission,
+        Stop = generic_request::Stop,
+        Trip = generic_request::Trip,
+        Transfer = generic_request::Transfer,
+    >,
+{
+    debug!(
+        "trip based at {}, realtime level : {:?}",
+        request_input.datetime, request_input.real_time_level
+    );
+    let request =
+        request::depart_after::Request::new(model, data, request_input, vehicle_penalties)?;
+
+    let start_compute_time = SystemTime::now();
+    let status = engine.compute(&request, data, request_input.real_time_level, cancellation);
+    if status == ComputeStatus::Interrupted {
+        warn!(
+            "Trip based search interrupted after {} ms.",
+            timer::duration_since(start_compute_time),
+        );
+    }
+    info!(
+        "Computed {} journeys in {} ms with trip based",
+        engine.nb_of_journeys(),
+        timer::duration_since(start_compute_time),
+    );
+    debug!("Engine stats : {}", engine.stats());
+
+    let responses = engine
+        .responses()
+        .filter_map(|pt_journey| {
+            request
+                .create_response(pt_journey)
+                .map_err(|err| {
+                    trace!(
+                        "An error occured while converting an engine journey to response. {:?}",
+                        err
+                    );
+                })
+                .ok()
+        })
+        .map(|journey| journey.to_response(data))
+        .collect();
+
+    Ok((responses, status))
+}
+
+fn select_isochrone_implem_and_solve<Data>(
+    engine: &mut MultiCriteriaRaptor<RequestTypes>,
+    data: &Data,
+    model: &ModelRefs<'_>,
+    request_input: &RequestInput,
+    vehicle_penalties: &VehiclePenalties,
+    datetime_represent: DateTimeRepresent,
+) -> Result<Vec<IsochroneResponse>, BadRequest>
+where
+    Data: DataWithIters<
+        Position = generic_request::Position,
+        Mission = generic_request::Mission,
+        Stop = generic_request::Stop,
+        Trip = generic_request::Trip,
+        Transfer = generic_request::Transfer,
+    >,
+{
+    use crate::datetime::DateTimeRepresent::{Arrival, Departure};
+
+    debug!(
+        "isochrone at {}, datetime_represents : {}, max_journey_duration : {}",
+        request_input.datetime, datetime_represent, request_input.max_journey_duration
+    );
+
+    let start_compute_time = SystemTime::now();
+    let isochrone_on = match datetime_represent {
+        Departure => {
+            let request = request::depart_after::Request::new_one_to_all(
                 model,
                 data,
                 request_input,
+                vehicle_penalties,
             )?;
-            solve_journeys_request_inner(engine, &request, data)
+            engine.compute_one_to_all(&request);
+            IsochroneOn::ArrivalTimes
         }
-        (Departure, Robustness) => {
-            let request = request::depart_after::robustness_comparator::Request::new(
+        Arrival => {
+            let request = request::arrive_before::Request::new_one_to_all(
                 model,
                 data,
                 request_input,
+                vehicle_penalties,
             )?;
-            solve_journeys_request_inner(engine, &request, data)
+            engine.compute_one_to_all(&request);
+            IsochroneOn::DepartureTimes
         }
     };
-
+    let responses = isochrone::isochrone_responses(engine, data, model, isochrone_on);
+    info!(
+        "Computed isochrone with {} stops reached in {} ms with {} rounds",
+        responses.len(),
+        timer::duration_since(start_compute_time),
+        engine.nb_of_rounds(),
+    );
     Ok(responses)
 }
 
+#[allow(clippy::too_many_arguments)]
+fn select_range_implem_and_solve<Data>(
+    engine: &mut MultiCriteriaRaptor<RequestTypes>,
+    data: &Data,
+    model: &ModelRefs<'_>,
+    request_input: &RequestInput,
+    vehicle_penalties: &VehiclePenalties,
+    range_duration: PositiveDuration,
+    datetime_represent: DateTimeRepresent,
+    cancellation: &Cancellation,
+) -> Result<(Vec<response::Response>, ComputeStatus), BadRequest>
+where
+    Data: DataWithIters<
+        Position = generic_request::Position,
+        Mission = generic_request::Mission,
+        Stop = generic_request::Stop,
+        Trip = generic_request::Trip,
+        Transfer = generic_request::Transfer,
+    >,
+{
+    use crate::datetime::DateTimeRepresent::{Arrival, Departure};
+
+    let from_datetime = request_input.datetime;
+    let until_datetime =
+        from_datetime + Duration::seconds(i64::from(range_duration.total_seconds_u32()));
+    debug!(
+        "range from {} until {}, datetime_represents : {}, comparator : {}",
+        from_datetime, until_datetime, datetime_represent, request_input.comparator
+    );
+
+    let datetimes = match datetime_represent {
+        Departure => generic_request::range_departure_datetimes(
+            &request_input.departures_stop_and_fallback_duration,
+            &from_datetime,
+            &until_datetime,
+            request_input.real_time_level,
+            model,
+            data,
+        )?,
+        Arrival => generic_request::range_arrival_datetimes(
+            &request_input.arrivals_stop_and_fallback_duration,
+            &from_datetime,
+            &until_datetime,
+            request_input.real_time_level,
+            model,
+            data,
+        )?,
+    };
+    debug!("{} iterations in range", datetimes.len());
+
+    let (mut responses, status) = match datetime_represent {
+        Arrival => solve_range_request_inner::<_, request::arrive_before::Request<_>>(
+            engine,
+            data,
+            model,
+            request_input,
+            vehicle_penalties,
+            &datetimes,
+            cancellation,
+        )?,
+        Departure => solve_range_request_inner::<_, request::depart_after::Request<_>>(
+            engine,
+            data,
+            model,
+            request_input,
+            vehicle_penalties,
+            &datetimes,
+            cancellation,
+        )?,
+    };
+
+    responses.sort_by_key(|response| response.first_vehicle_board_datetime());
+    Ok((responses, status))
+}
+
+fn solve_range_request_inner<'data, 'model, Data, Request>(
+    engine: &mut MultiCriteriaRaptor<RequestTypes>,
+    data: &'data Data,
+    model: &'model ModelRefs<'model>,
+    request_input: &RequestInput,
+    vehicle_penalties: &'data VehiclePenalties,
+    datetimes: &[NaiveDateTime],
+    cancellation: &Cancellation,
+) -> Result<(Vec<response::Response>, ComputeStatus), BadRequest>
+where
+    Request: RequestWithIters,
+    Request: RequestIO<'data, 'model, Data> + RequestDebug,
+    Data: DataTrait,
+    Request: RequestTypesTrait<
+        Position = generic_request::Position,
+        Mission = generic_request::Mission,
+        Stop = generic_request::Stop,
+        Trip = generic_request::Trip,
+        Transfer = generic_request::Transfer,
+        Departure = generic_request::Departure,
+        Arrival = generic_request::Arrival,
+        Criteria = generic_request::Criteria,
+    >,
+    Request::Criteria: Debug,
+{
+    let start_compute_time = SystemTime::now();
+    let mut responses: Vec<response::Response> = Vec::new();
+    let mut iteration_input = request_input.clone();
+    let mut status = ComputeStatus::Complete;
+    let mut nb_of_iterations = 0;
+    engine.init_range();
+    for datetime in datetimes {
+        iteration_input.datetime = *datetime;
+        let request = Request::new(model, data, &iteration_input, vehicle_penalties)?;
+        status = engine.compute_range_iteration(&request, cancellation);
+        nb_of_iterations += 1;
+        trace!(
+            "Range iteration at {} : {} new journeys in {} rounds",
+            datetime,
+            engine.nb_of_journeys(),
+            engine.nb_of_rounds()
+        );
+        let new_responses = engine
+            .responses()
+            .filter_map(|pt_journey| {
+                request
+                    .create_response(pt_journey)
+                    .map_err(|err| {
+                        trace!(
+                            "An error occured while converting an engine journey to response. {:?}",
+                            err
+                        );
+                    })
+                    .ok()
+            })
+            .map(|journey| journey.to_response(data));
+        for response in new_responses {
+            if !responses
+                .iter()
+                .any(|other| response.is_same_journey(other))
+            {
+                responses.push(response);
+            }
+        }
+        if status == ComputeStatus::Interrupted {
+            info!(
+                "Range search interrupted after {} of {} iterations. The {} journeys found may not be optimal.",
+                nb_of_iterations,
+                datetimes.len(),
+                responses.len(),
+            );
+            break;
+        }
+    }
+    info!(
+        "Computed {} journeys in a range of {} iterations in {} ms. Tree size : {}",
+        responses.len(),
+        nb_of_iterations,
+        timer::duration_since(start_compute_time),
+        engine.tree_size(),
+    );
+    Ok((responses, status))
+}
+
 fn solve_journeys_request_inner<'data, 'model, Data, Request>(
     engine: &mut MultiCriteriaRaptor<RequestTypes>,
     request: &Request,
     data: &'data Data,
-) -> Vec<response::Response>
+    nb_of_threads: usize,
+    cancellation: &Cancellation,
+) -> (Vec<response::Response>, ComputeStatus)
 where
-    Request: RequestWithIters,
+    Request: RequestWithIters + Sync,
     Request: RequestIO<'data, 'model, Data> + RequestDebug,
     Data: DataTrait,
     Request: RequestTypesTrait<
@@ -284,7 +1222,14 @@ where
     );
 
     let start_compute_time = SystemTime::now();
-    engine.compute(request);
+    let status = engine.compute_in_parallel(request, nb_of_threads, cancellation);
+    if status == ComputeStatus::Interrupted {
+        warn!(
+            "Computation interrupted after {} ms. The {} journeys found may not be optimal.",
+            timer::duration_since(start_compute_time),
+            engine.nb_of_journeys(),
+        );
+    }
     info!(
         "Computed {} journeys in {} ms with {} rounds. Tree size : {}",
         engine.nb_of_journeys(),
@@ -292,6 +1237,7 @@ where
         engine.nb_of_rounds(),
         engine.tree_size(),
     );
+    debug!("Engine stats : {}", engine.stats());
 
     let journeys_iter = engine.responses().filter_map(|pt_journey| {
         request
@@ -309,5 +1255,45 @@ where
         .map(|journey| journey.to_response(data))
         .collect();
 
-    responses
+    (responses, status)
+}
+
+// moves to `journeys` the journeys of `solution` that are not already there
+fn merge_journeys(journeys: &mut Vec<response::Response>, solution: &mut JourneysSolution) {
+    for new_journey in solution.journeys.drain(..) {
+        if !journeys
+            .iter()
+            .any(|journey| journey.has_same_vehicles(&new_journey))
+        {
+            journeys.push(new_journey);
+        }
+    }
+}
+
+// the datetime at which a request should be solved to find other journeys than `journeys` :
+// just after the latest departure that catches their earliest vehicle
+// (resp. just before their latest arrival)
+fn next_datetime(
+    journeys: &[response::Response],
+    datetime_represent: DateTimeRepresent,
+) -> Option<NaiveDateTime> {
+    match datetime_represent {
+        DateTimeRepresent::Departure => journeys
+            .iter()
+            .map(latest_departure)
+            .min()
+            .map(|departure| departure + Duration::seconds(1)),
+        DateTimeRepresent::Arrival => journeys
+            .iter()
+            .map(|journey| journey.arrival.to_datetime)
+            .max()
+            .map(|arrival| arrival - Duration::seconds(1)),
+    }
+}
+
+// the latest datetime at which one can leave the departure of `journey`
+// and still board its first vehicle
+fn latest_departure(journey: &response::Response) -> NaiveDateTime {
+    let fallback_duration = journey.departure.to_datetime - journey.departure.from_datetime;
+    journey.first_vehicle_board_datetime() - fallback_duration
 }
@@ -0,0 +1,390 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use std::fmt;
+
+use loki::{
+    filters::Filters,
+    models::ModelRefs,
+    request::generic_request::{self, parse_input_stops},
+    response,
+    tracing::debug,
+    transit_data::data_interface::DataIters,
+    transit_data_filtered::TransitDataFiltered,
+    BadRequest, Cancellation, PositiveDuration, RealTimeLevel, RequestInput,
+};
+
+use super::{select_journeys_implem_and_solve, Solver};
+use crate::{
+    datetime::DateTimeRepresent,
+    loki::{DataTrait, TransitData},
+};
+
+// the max journey duration used to check whether
+// the journeys are too long for the request
+const RELAXED_MAX_JOURNEY_DURATION: PositiveDuration = PositiveDuration::from_hms(48, 0, 0);
+
+/// A reason why no journey was found for a request,
+/// as diagnosed by `Solver::explain_no_journey()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoJourneyReason {
+    /// All the departure stops of the request are forbidden by the filters.
+    DepartureStopsFiltered,
+    /// All the arrival stops of the request are forbidden by the filters.
+    ArrivalStopsFiltered,
+    /// No vehicle can be boarded at the departure stops between the requested datetime
+    /// and the end of the max journey duration.
+    NoTripAtDepartureStops,
+    /// No vehicle debarks at the arrival stops between the start of the max journey duration
+    /// and the requested datetime.
+    NoTripAtArrivalStops,
+    /// Some journeys exist, but are forbidden by the filters.
+    ForbiddenByFilters,
+    /// Some journeys exist, but need at least `nb_of_legs_needed` legs.
+    TooManyLegs {
+        max_nb_of_legs: u8,
+        nb_of_legs_needed: usize,
+    },
+    /// Some journeys exist, but last at least `duration_needed`.
+    TooLong {
+        max_journey_duration: PositiveDuration,
+        duration_needed: PositiveDuration,
+    },
+    /// Some journeys exist on the base schedule, but use vehicles
+    /// that are cancelled or delayed in real time.
+    RealTimeDisruptions,
+}
+
+impl fmt::Display for NoJourneyReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NoJourneyReason::DepartureStopsFiltered => {
+                write!(f, "All departure stops are forbidden by the filters.")
+            }
+            NoJourneyReason::ArrivalStopsFiltered => {
+                write!(f, "All arrival stops are forbidden by the filters.")
+            }
+            NoJourneyReason::NoTripAtDepartureStops => write!(
+                f,
+                "No vehicle can be boarded at the departure stops within the max journey duration."
+            ),
+            NoJourneyReason::NoTripAtArrivalStops => write!(
+                f,
+                "No vehicle reaches the arrival stops within the max journey duration."
+            ),
+            NoJourneyReason::ForbiddenByFilters => {
+                write!(f, "Journeys exist but are forbidden by the filters.")
+            }
+            NoJourneyReason::TooManyLegs {
+                max_nb_of_legs,
+                nb_of_legs_needed,
+            } => write!(
+                f,
+                "Journeys exist but need {} legs, while at most {} legs are allowed.",
+                nb_of_legs_needed, max_nb_of_legs
+            ),
+            NoJourneyReason::TooLong {
+                max_journey_duration,
+                duration_needed,
+            } => write!(
+                f,
+                "Journeys exist but last {}, while the max journey duration is {}.",
+                duration_needed, max_journey_duration
+            ),
+            NoJourneyReason::RealTimeDisruptions => write!(
+                f,
+                "Journeys exist on the base schedule, but use disrupted vehicles in real time."
+            ),
+        }
+    }
+}
+
+impl Solver {
+    /// Diagnoses why `solve_journey_request()` finds no journey for this request.
+    ///
+    /// The departure and arrival stops are checked first. When they do not explain
+    /// the absence of journeys, the request is solved again with `EngineType::Raptor`,
+    /// relaxing in turn the filters, `max_nb_of_legs`, `max_journey_duration`
+    /// and the real time level.
+    ///
+    /// The returned list is empty when no reason was found,
+    /// for example because journeys do exist for this request.
+    pub fn explain_no_journey(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        has_filters: Option<Filters>,
+        datetime_represent: DateTimeRepresent,
+    ) -> Result<Vec<NoJourneyReason>, BadRequest> {
+        self.fill_vehicle_penalties(model, request_input);
+        let use_filters = if let Some(filters) = has_filters {
+            self.fill_allowed_stops_and_vehicles(model, &filters);
+            true
+        } else {
+            false
+        };
+
+        let reasons =
+            self.explain_with_stops(data, model, request_input, use_filters, datetime_represent)?;
+        if !reasons.is_empty() {
+            return Ok(reasons);
+        }
+
+        let mut reasons = Vec::new();
+
+        if use_filters
+            && !self
+                .solve_for_explanation(data, model, request_input, false, datetime_represent)?
+                .is_empty()
+        {
+            reasons.push(NoJourneyReason::ForbiddenByFilters);
+        }
+
+        {
+            let mut relaxed_input = request_input.clone();
+            relaxed_input.max_nb_of_legs = u8::MAX;
+            let journeys = self.solve_for_explanation(
+                data,
+                model,
+                &relaxed_input,
+                use_filters,
+                datetime_represent,
+            )?;
+            let has_nb_of_legs_needed = journeys
+                .iter()
+                .map(|journey| journey.nb_of_vehicles())
+                .filter(|nb_of_legs| *nb_of_legs > usize::from(request_input.max_nb_of_legs))
+                .min();
+            if let Some(nb_of_legs_needed) = has_nb_of_legs_needed {
+                reasons.push(NoJourneyReason::TooManyLegs {
+                    max_nb_of_legs: request_input.max_nb_of_legs,
+                    nb_of_legs_needed,
+                });
+            }
+        }
+
+        if request_input.max_journey_duration < RELAXED_MAX_JOURNEY_DURATION {
+            let mut relaxed_input = request_input.clone();
+            relaxed_input.max_journey_duration = RELAXED_MAX_JOURNEY_DURATION;
+            let journeys = self.solve_for_explanation(
+                data,
+                model,
+                &relaxed_input,
+                use_filters,
+                datetime_represent,
+            )?;
+            let has_seconds_needed = journeys
+                .iter()
+                .filter_map(|journey| u32::try_from(journey.total_duration()).ok())
+                .filter(|seconds| *seconds > request_input.max_journey_duration.total_seconds_u32())
+                .min();
+            if let Some(seconds) = has_seconds_needed {
+                reasons.push(NoJourneyReason::TooLong {
+                    max_journey_duration: request_input.max_journey_duration,
+                    duration_needed: PositiveDuration::from_hms(0, 0, seconds),
+                });
+            }
+        }
+
+        if request_input.real_time_level == RealTimeLevel::RealTime {
+            let mut base_input = request_input.clone();
+            base_input.real_time_level = RealTimeLevel::Base;
+            if !self
+                .solve_for_explanation(data, model, &base_input, use_filters, datetime_represent)?
+                .is_empty()
+            {
+                reasons.push(NoJourneyReason::RealTimeDisruptions);
+            }
+        }
+
+        debug!("No journey reasons : {:?}", reasons);
+        Ok(reasons)
+    }
+
+    // checks that the departure (resp. arrival) stops are allowed by the filters,
+    // and that some trips can be boarded (resp. debarked) there
+    fn explain_with_stops(
+        &self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        use_filters: bool,
+        datetime_represent: DateTimeRepresent,
+    ) -> Result<Vec<NoJourneyReason>, BadRequest> {
+        let calendar = data.calendar();
+        let datetime = calendar
+            .from_naive_datetime(&request_input.datetime)
+            .ok_or(BadRequest::RequestedDatetime)?;
+        let is_stop_allowed = |stop: &generic_request::Stop| {
+            !use_filters
+                || self
+                    .filter_memory
+                    .is_stop_allowed(&data.stop_point_idx(stop))
+        };
+        let is_vehicle_journey_allowed = |vehicle_journey_idx: &_| {
+            !use_filters
+                || self
+                    .filter_memory
+                    .is_vehicle_journey_allowed(vehicle_journey_idx)
+        };
+        let real_time_level = request_input.real_time_level;
+        let max_journey_duration = request_input.max_journey_duration;
+
+        let departures: Vec<_> = parse_input_stops(
+            &request_input.departures_stop_and_fallback_duration,
+            model,
+            data,
+            "departure",
+        );
+        if departures.is_empty() {
+            return Err(BadRequest::NoValidDepartureStop);
+        }
+        let arrivals: Vec<_> = parse_input_stops(
+            &request_input.arrivals_stop_and_fallback_duration,
+            model,
+            data,
+            "arrival",
+        );
+        if arrivals.is_empty() {
+            return Err(BadRequest::NoValidArrivalStop);
+        }
+
+        let mut reasons = Vec::new();
+        let allowed_departures: Vec<_> = departures
+            .into_iter()
+            .filter(|(stop, _)| is_stop_allowed(stop))
+            .collect();
+        if allowed_departures.is_empty() {
+            reasons.push(NoJourneyReason::DepartureStopsFiltered);
+        }
+        let allowed_arrivals: Vec<_> = arrivals
+            .into_iter()
+            .filter(|(stop, _)| is_stop_allowed(stop))
+            .collect();
+        if allowed_arrivals.is_empty() {
+            reasons.push(NoJourneyReason::ArrivalStopsFiltered);
+        }
+        if !reasons.is_empty() {
+            return Ok(reasons);
+        }
+
+        match datetime_represent {
+            DateTimeRepresent::Departure => {
+                let until_time = datetime + max_journey_duration;
+                let has_trip = allowed_departures.iter().any(|(stop, fallback_duration)| {
+                    let waiting_time = datetime + *fallback_duration;
+                    data.missions_at(stop).any(|(mission, position)| {
+                        data.earliest_trip_to_board(
+                            waiting_time,
+                            &mission,
+                            &position,
+                            real_time_level,
+                            is_vehicle_journey_allowed,
+                        )
+                        .and_then(|(trip, _, _)| data.board_time_of(&trip, &position))
+                        .is_some_and(|board_time| board_time <= until_time)
+                    })
+                });
+                if !has_trip {
+                    reasons.push(NoJourneyReason::NoTripAtDepartureStops);
+                }
+            }
+            DateTimeRepresent::Arrival => {
+                let has_from_time = datetime.checked_sub(&max_journey_duration);
+                let has_trip = allowed_arrivals.iter().any(|(stop, fallback_duration)| {
+                    let Some(waiting_time) = datetime.checked_sub(fallback_duration) else {
+                        return false;
+                    };
+                    data.missions_at(stop).any(|(mission, position)| {
+                        data.latest_trip_that_debark(
+                            waiting_time,
+                            &mission,
+                            &position,
+                            real_time_level,
+                            is_vehicle_journey_allowed,
+                        )
+                        .and_then(|(trip, _, _)| data.debark_time_of(&trip, &position))
+                        .is_some_and(|debark_time| {
+                            has_from_time.is_none_or(|from_time| debark_time >= from_time)
+                        })
+                    })
+                });
+                if !has_trip {
+                    reasons.push(NoJourneyReason::NoTripAtArrivalStops);
+                }
+            }
+        }
+
+        Ok(reasons)
+    }
+
+    // solves with raptor, with the filters stored in `self.filter_memory` when `use_filters` is true
+    fn solve_for_explanation(
+        &mut self,
+        data: &TransitData,
+        model: &ModelRefs<'_>,
+        request_input: &RequestInput,
+        use_filters: bool,
+        datetime_represent: DateTimeRepresent,
+    ) -> Result<Vec<response::Response>, BadRequest> {
+        let (journeys, _) = if use_filters {
+            let filtered_data = TransitDataFiltered::new(data, &self.filter_memory);
+            select_journeys_implem_and_solve(
+                &mut self.engine,
+                &filtered_data,
+                model,
+                request_input,
+                &self.vehicle_penalties,
+                datetime_represent,
+                self.nb_of_threads,
+                &Cancellation::none(),
+            )
+        } else {
+            select_journeys_implem_and_solve(
+                &mut self.engine,
+                data,
+                model,
+                request_input,
+                &self.vehicle_penalties,
+                datetime_represent,
+                self.nb_of_threads,
+                &Cancellation::none(),
+            )
+        }?;
+        Ok(journeys)
+    }
+}
@@ -39,7 +39,7 @@ use loki::{
     RequestInput,
 };
 
-use crate::config::RequestParams;
+use crate::config::{ComparatorType, RequestParams};
 
 pub fn make_query_stop_areas(
     base_model: &BaseModel,
@@ -47,6 +47,7 @@ pub fn make_query_stop_areas(
     from_stop_area: &str,
     to_stop_area: &str,
     request_params: &RequestParams,
+    comparator_type: ComparatorType,
 ) -> Result<RequestInput, UnknownStopArea> {
     let departures_stop_and_fallback_duration =
         stops_of_stop_area(base_model, from_stop_area, PositiveDuration::zero())?;
@@ -57,12 +58,24 @@ pub fn make_query_stop_areas(
         datetime: *datetime,
         departures_stop_and_fallback_duration,
         arrivals_stop_and_fallback_duration,
+        via_stops_and_stay_duration: Vec::new(),
         leg_arrival_penalty: request_params.leg_arrival_penalty,
         leg_walking_penalty: request_params.leg_walking_penalty,
         max_nb_of_legs: request_params.max_nb_of_legs,
         max_journey_duration: request_params.max_journey_duration,
+        max_walking_duration: request_params.max_walking_duration,
+        max_transfer_walking_duration: request_params.max_transfer_walking_duration,
+        walking_speed_factor: request_params.walking_speed_factor,
+        max_waiting_duration: request_params.max_waiting_duration,
+        max_initial_waiting_duration: request_params.max_initial_waiting_duration,
         too_late_threshold: request_params.too_late_threshold,
         real_time_level: RealTimeLevel::Base,
+        lower_bound_pruning: request_params.lower_bound_pruning,
+        pareto_slack_duration: request_params.pareto_slack_duration,
+        pareto_slack_percentage: request_params.pareto_slack_percentage,
+        tighten_journeys: request_params.tighten_journeys,
+        comparator: request_params.comparator(comparator_type),
+        preferences: Vec::new(),
     };
 
     Ok(request_input)
@@ -76,12 +89,9 @@ pub fn stops_of_stop_area(
     let mut result = Vec::new();
     for stop_idx in base_model.stop_points() {
         let stop_area_name = base_model.stop_area_id(stop_idx);
-        let stop_name = base_model.stop_point_name(stop_idx);
+        let stop_id = base_model.stop_point_id(stop_idx);
         if stop_area_name == stop_area_uri {
-            result.push((
-                InputStop::StopArea(stop_name.to_string()),
-                duration_to_stops,
-            ));
+            result.push((InputStop::StopPoint(stop_id.to_string()), duration_to_stops));
         }
     }
 
@@ -0,0 +1,206 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+mod utils;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Error;
+use loki::{
+    models::base_model::BaseModel, Cancellation, CancellationToken, ComputeStatus, DataTrait,
+    PositiveDuration, TransitData,
+};
+use loki_launch::{
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::Solver,
+};
+use utils::{make_request_from_config, model_builder::ModelBuilder, Config};
+
+fn solve_with_cancellation(cancellation: &Cancellation) -> Result<(usize, ComputeStatus), Error> {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:20:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("B", "10:30:00")
+                .st("C", "11:00:00");
+        })
+        .add_transfer("B", "B", "00:02:00")
+        .build();
+
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+    let request_input = make_request_from_config(&config);
+
+    let solution = solver.solve_journey_request_with_cancellation(
+        &data,
+        &model_refs,
+        &request_input,
+        None,
+        DateTimeRepresent::Departure,
+        cancellation,
+    )?;
+    Ok((solution.journeys.len(), solution.status))
+}
+
+#[test]
+fn test_not_cancelled() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let deadline = SystemTime::now() + Duration::from_secs(3600);
+    let cancellation = Cancellation::none()
+        .with_deadline(deadline)
+        .with_token(CancellationToken::new());
+
+    let (nb_of_journeys, status) = solve_with_cancellation(&cancellation)?;
+
+    assert_eq!(status, ComputeStatus::Complete);
+    assert_eq!(nb_of_journeys, 1);
+    Ok(())
+}
+
+#[test]
+fn test_cancelled_by_token() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let token = CancellationToken::new();
+    let cancellation = Cancellation::none().with_token(token.clone());
+    token.cancel();
+
+    let (nb_of_journeys, status) = solve_with_cancellation(&cancellation)?;
+
+    assert_eq!(status, ComputeStatus::Interrupted);
+    assert_eq!(nb_of_journeys, 0);
+    Ok(())
+}
+
+#[test]
+fn test_cancelled_by_deadline() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let deadline = SystemTime::now() - Duration::from_secs(1);
+    let cancellation = Cancellation::none().with_deadline(deadline);
+
+    let (nb_of_journeys, status) = solve_with_cancellation(&cancellation)?;
+
+    assert_eq!(status, ComputeStatus::Interrupted);
+    assert_eq!(nb_of_journeys, 0);
+    Ok(())
+}
+
+#[test]
+fn test_range_cancelled_by_token() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:20:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:30:00")
+                .st("B", "10:50:00");
+        })
+        .build();
+
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "B");
+    let request_input = make_request_from_config(&config);
+    let range_duration = PositiveDuration::from_hms(1, 0, 0);
+
+    let solution = solver.solve_range_journey_request_with_cancellation(
+        &data,
+        &model_refs,
+        &request_input,
+        range_duration,
+        None,
+        DateTimeRepresent::Departure,
+        &Cancellation::none(),
+    )?;
+    assert_eq!(solution.status, ComputeStatus::Complete);
+    assert_eq!(solution.journeys.len(), 2);
+
+    let token = CancellationToken::new();
+    let cancellation = Cancellation::none().with_token(token.clone());
+    token.cancel();
+
+    let solution = solver.solve_range_journey_request_with_cancellation(
+        &data,
+        &model_refs,
+        &request_input,
+        range_duration,
+        None,
+        DateTimeRepresent::Departure,
+        &cancellation,
+    )?;
+    assert_eq!(solution.status, ComputeStatus::Interrupted);
+    assert!(solution.journeys.is_empty());
+    // only the first iteration of the range is started
+    assert_eq!(solution.stats.nb_of_rounds, 0);
+
+    Ok(())
+}
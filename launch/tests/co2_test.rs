@@ -0,0 +1,161 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    models::{base_model::BaseModel, real_time_model::RealTimeModel, ModelRefs},
+    transit_model::objects::Coord,
+    PositiveDuration,
+};
+use loki_launch::{config::ComparatorType, datetime::DateTimeRepresent};
+use utils::{build_and_solve, model_builder::ModelBuilder, Config};
+
+// From A to C, a bus is faster than a train (that stops at T), but emits more CO2
+fn build_model() -> BaseModel {
+    let coord = |lon: f64| Coord { lon, lat: 48.8 };
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .stop_area("sa:A", |_| {})
+        .stop_area("sa:C", |_| {})
+        .stop_area("sa:T", |_| {})
+        .stop_point("A", |stop_point| stop_point.coord = coord(2.3))
+        .stop_point("T", |stop_point| stop_point.coord = coord(2.35))
+        .stop_point("C", |stop_point| stop_point.coord = coord(2.4))
+        .physical_mode("Bus", |physical_mode| {
+            physical_mode.id = "Bus".to_string();
+            physical_mode.co2_emission = Some(100.0);
+        })
+        .physical_mode("Train", |physical_mode| {
+            physical_mode.id = "Train".to_string();
+            physical_mode.co2_emission = Some(5.0);
+        })
+        .vj("bus", |vj_builder| {
+            vj_builder
+                .physical_mode("Bus")
+                .st("A", "10:00:00")
+                .st("C", "10:30:00");
+        })
+        .vj("train", |vj_builder| {
+            vj_builder
+                .physical_mode("Train")
+                .st("A", "10:00:00")
+                .st("T", "10:30:00")
+                .st("C", "11:00:00");
+        })
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+fn solve(
+    model_refs: &ModelRefs<'_>,
+    datetime: &str,
+    datetime_represent: DateTimeRepresent,
+    comparator_type: ComparatorType,
+) -> Result<Vec<String>, Error> {
+    let mut config = Config::new(datetime, "A", "C");
+    config.datetime_represent = datetime_represent;
+    config.comparator_type = comparator_type;
+    let journeys = build_and_solve(model_refs, &config)?;
+    let mut vehicle_journeys: Vec<String> = journeys
+        .iter()
+        .map(|journey| journey.first_vj_uri(model_refs).to_string())
+        .collect();
+    vehicle_journeys.sort();
+    Ok(vehicle_journeys)
+}
+
+#[test]
+fn test_co2_comparator() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let journeys = solve(
+        &model_refs,
+        "2020-01-01T09:50:00",
+        DateTimeRepresent::Departure,
+        ComparatorType::Basic,
+    )?;
+    assert_eq!(journeys, vec!["bus"]);
+
+    // the train is slower, but emits less CO2
+    let journeys = solve(
+        &model_refs,
+        "2020-01-01T09:50:00",
+        DateTimeRepresent::Departure,
+        ComparatorType::Co2,
+    )?;
+    assert_eq!(journeys, vec!["bus", "train"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_co2_comparator_arrive_before() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    // both vehicles depart at 10:00, so with an arrive before request,
+    // they are equivalent for the basic comparator,
+    // and the train is better when comparing CO2 emissions
+    let journeys = solve(
+        &model_refs,
+        "2020-01-01T11:10:00",
+        DateTimeRepresent::Arrival,
+        ComparatorType::Basic,
+    )?;
+    assert_eq!(journeys.len(), 1);
+
+    let journeys = solve(
+        &model_refs,
+        "2020-01-01T11:10:00",
+        DateTimeRepresent::Arrival,
+        ComparatorType::Co2,
+    )?;
+    assert_eq!(journeys, vec!["train"]);
+
+    Ok(())
+}
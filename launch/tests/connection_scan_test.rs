@@ -0,0 +1,209 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    filters::{parse_filter, Filters},
+    models::base_model::BaseModel,
+    Cancellation, DataTrait, NaiveDateTime, PositiveDuration, TransitData,
+};
+use loki_launch::{
+    config::EngineType,
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::Solver,
+};
+use utils::{make_request_from_config, model_builder::ModelBuilder, Config};
+
+// returns, for each journey, its arrival datetime and the vehicle journeys it uses
+fn solve(
+    data: &TransitData,
+    model_refs: &ModelRefs<'_>,
+    config: &Config,
+    engine_type: EngineType,
+) -> Result<Vec<(NaiveDateTime, Vec<String>)>, Error> {
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    solver.set_engine_type(engine_type);
+    let request_input = make_request_from_config(config);
+
+    let forbidden_filters = config
+        .forbidden_uris
+        .iter()
+        .filter_map(|forbidden_uri| parse_filter(model_refs, forbidden_uri, "test"));
+    let filters = Filters::new(forbidden_filters, std::iter::empty(), false, false);
+
+    let solution = solver.solve_journey_request_with_cancellation(
+        data,
+        model_refs,
+        &request_input,
+        filters,
+        config.datetime_represent,
+        &Cancellation::none(),
+    )?;
+    let journeys = solution
+        .journeys
+        .iter()
+        .map(|journey| {
+            let vehicle_journeys = std::iter::once(&journey.first_vehicle)
+                .chain(journey.connections.iter().map(|(_, _, vehicle)| vehicle))
+                .map(|vehicle| {
+                    model_refs
+                        .vehicle_journey_name(&vehicle.vehicle_journey)
+                        .to_string()
+                })
+                .collect();
+            (journey.last_vehicle_debark_datetime(), vehicle_journeys)
+        })
+        .collect();
+    Ok(journeys)
+}
+
+fn earliest_arrival(journeys: &[(NaiveDateTime, Vec<String>)]) -> Option<NaiveDateTime> {
+    journeys.iter().map(|(arrival, _)| *arrival).min()
+}
+
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:05:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("D", "10:10:00")
+                .st("C", "10:20:00");
+        })
+        .vj("direct", |vj_builder| {
+            vj_builder
+                .route("3")
+                .st("A", "09:55:00")
+                .st("C", "11:00:00");
+        })
+        .vj("through", |vj_builder| {
+            vj_builder
+                .route("4")
+                .st("A", "10:01:00")
+                .st_detailed("E", "10:03:00", "10:03:00", 1, 1, None) // no pickup nor dropoff at E
+                .st("C", "10:30:00");
+        })
+        .add_transfer("B", "D", "00:02:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_connection_scan_earliest_arrival() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+
+    let raptor_journeys = solve(&data, &model_refs, &config, EngineType::Raptor)?;
+    let csa_journeys = solve(&data, &model_refs, &config, EngineType::ConnectionScan)?;
+
+    assert_eq!(csa_journeys.len(), 1);
+    assert_eq!(
+        earliest_arrival(&csa_journeys),
+        earliest_arrival(&raptor_journeys)
+    );
+    assert_eq!(csa_journeys[0].1, vec!["first", "second"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_connection_scan_with_filters() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.forbidden_uris = vec!["route:2"];
+
+    // the next fastest journey rides "through" past E,
+    // where neither pickup nor drop off are allowed
+    let csa_journeys = solve(&data, &model_refs, &config, EngineType::ConnectionScan)?;
+    assert_eq!(csa_journeys.len(), 1);
+    assert_eq!(csa_journeys[0].1, vec!["through"]);
+    assert_eq!(
+        earliest_arrival(&csa_journeys),
+        earliest_arrival(&solve(&data, &model_refs, &config, EngineType::Raptor)?)
+    );
+
+    config.forbidden_uris = vec!["route:2", "route:4"];
+    let csa_journeys = solve(&data, &model_refs, &config, EngineType::ConnectionScan)?;
+    assert_eq!(csa_journeys.len(), 1);
+    assert_eq!(csa_journeys[0].1, vec!["direct"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_connection_scan_arrival_falls_back_to_raptor() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let mut config = Config::new("2020-01-01T11:10:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+
+    let mut raptor_journeys = solve(&data, &model_refs, &config, EngineType::Raptor)?;
+    let mut csa_journeys = solve(&data, &model_refs, &config, EngineType::ConnectionScan)?;
+    raptor_journeys.sort();
+    csa_journeys.sort();
+    assert_eq!(csa_journeys, raptor_journeys);
+
+    Ok(())
+}
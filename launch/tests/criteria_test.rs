@@ -0,0 +1,152 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+mod utils;
+use anyhow::Error;
+use loki::{
+    models::base_model::BaseModel,
+    request::comparator::{Comparator, Criterion},
+    Cancellation, DataTrait, PositiveDuration, TransitData,
+};
+use loki_launch::{
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::Solver,
+};
+use utils::{make_request_from_config, model_builder::ModelBuilder, Config};
+
+fn solve(
+    data: &TransitData,
+    model_refs: &ModelRefs<'_>,
+    config: &Config,
+) -> Result<Vec<String>, Error> {
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    let request_input = make_request_from_config(config);
+
+    let solution = solver.solve_journey_request_with_cancellation(
+        data,
+        model_refs,
+        &request_input,
+        None,
+        config.datetime_represent,
+        &Cancellation::none(),
+    )?;
+    let mut vj_uris: Vec<String> = solution
+        .journeys
+        .iter()
+        .map(|journey| journey.first_vj_uri(model_refs).to_string())
+        .collect();
+    vj_uris.sort();
+    Ok(vj_uris)
+}
+
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:05:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("D", "10:10:00")
+                .st("C", "10:20:00");
+        })
+        .vj("direct", |vj_builder| {
+            vj_builder
+                .route("3")
+                .st("A", "09:55:00")
+                .st("C", "11:00:00");
+        })
+        .add_transfer("B", "D", "00:02:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_criteria() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    for datetime_represent in [DateTimeRepresent::Departure, DateTimeRepresent::Arrival] {
+        let mut config = match datetime_represent {
+            DateTimeRepresent::Departure => Config::new("2020-01-01T09:50:00", "A", "C"),
+            DateTimeRepresent::Arrival => Config::new("2020-01-01T11:10:00", "A", "C"),
+        };
+        config.datetime_represent = datetime_represent;
+
+        // "direct" is slower than "first" then "second", but walks less
+        assert_eq!(solve(&data, &model_refs, &config)?, vec!["direct", "first"]);
+
+        config.request_params.criteria = Some("time".parse()?);
+        assert_eq!(solve(&data, &model_refs, &config)?, vec!["first"]);
+
+        // "direct" has less legs than "first" then "second"
+        config.request_params.criteria = Some("time,nb_of_legs".parse()?);
+        assert_eq!(solve(&data, &model_refs, &config)?, vec!["direct", "first"]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_criteria() -> Result<(), Error> {
+    let comparator: Comparator = "time, walking,nb_of_legs,walking".parse()?;
+    assert_eq!(
+        comparator.criteria(),
+        &[Criterion::Time, Criterion::Walking, Criterion::NbOfLegs]
+    );
+    assert_eq!(comparator.to_string(), "time,walking,nb_of_legs");
+
+    assert!("walking,nb_of_legs".parse::<Comparator>().is_err());
+    assert!("time,fare".parse::<Comparator>().is_err());
+
+    assert_eq!("time,walking".parse::<Comparator>()?, Comparator::basic());
+
+    Ok(())
+}
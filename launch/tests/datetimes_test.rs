@@ -0,0 +1,167 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    chrono::NaiveDate, models::base_model::BaseModel, Cancellation, DataTrait, NaiveDateTime,
+    PositiveDuration, TransitData,
+};
+use loki_launch::{
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::Solver,
+};
+use utils::{make_request_from_config, model_builder::ModelBuilder, Config};
+
+// three vehicles go from A to B, one every hour
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("v1", |vj_builder| {
+            vj_builder.st("A", "10:00:00").st("B", "10:30:00");
+        })
+        .vj("v2", |vj_builder| {
+            vj_builder.st("A", "11:00:00").st("B", "11:30:00");
+        })
+        .vj("v3", |vj_builder| {
+            vj_builder.st("A", "12:00:00").st("B", "12:30:00");
+        })
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+fn datetime(hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2020, 1, 1)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
+
+fn solve(
+    model_refs: &ModelRefs<'_>,
+    config: &Config,
+    datetimes: &[NaiveDateTime],
+    min_nb_of_journeys: usize,
+    horizon: PositiveDuration,
+) -> Result<Vec<String>, Error> {
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    let request_input = make_request_from_config(config);
+    let solution = solver.solve_journey_request_at_datetimes(
+        &data,
+        model_refs,
+        &request_input,
+        datetimes,
+        min_nb_of_journeys,
+        horizon,
+        None,
+        config.datetime_represent,
+        &Cancellation::none(),
+    )?;
+    Ok(solution
+        .journeys
+        .iter()
+        .map(|journey| journey.first_vj_uri(model_refs).to_string())
+        .collect())
+}
+
+#[test]
+fn test_several_datetimes() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let config = Config::new("2020-01-01T09:50:00", "A", "B");
+    let horizon = PositiveDuration::from_hms(24, 0, 0);
+
+    let journeys = solve(&model_refs, &config, &[], 0, horizon)?;
+    assert_eq!(journeys, vec!["v1"]);
+
+    // the journey found at 09:50 and 09:55 is kept once
+    let datetimes = [datetime(9, 50), datetime(9, 55), datetime(10, 50)];
+    let journeys = solve(&model_refs, &config, &datetimes, 0, horizon)?;
+    assert_eq!(journeys, vec!["v1", "v2"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_min_nb_of_journeys() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let config = Config::new("2020-01-01T09:50:00", "A", "B");
+
+    let journeys = solve(
+        &model_refs,
+        &config,
+        &[],
+        3,
+        PositiveDuration::from_hms(24, 0, 0),
+    )?;
+    assert_eq!(journeys, vec!["v1", "v2", "v3"]);
+
+    // after `v2`, the search would start at 11:00:01, which is more than 1h after 09:50
+    let journeys = solve(
+        &model_refs,
+        &config,
+        &[],
+        3,
+        PositiveDuration::from_hms(1, 0, 0),
+    )?;
+    assert_eq!(journeys, vec!["v1", "v2"]);
+
+    let mut config = Config::new("2020-01-01T12:40:00", "A", "B");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    let journeys = solve(
+        &model_refs,
+        &config,
+        &[],
+        2,
+        PositiveDuration::from_hms(24, 0, 0),
+    )?;
+    assert_eq!(journeys, vec!["v3", "v2"]);
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+mod utils;
+use anyhow::Error;
+use loki::{models::base_model::BaseModel, Cancellation, DataTrait, PositiveDuration, TransitData};
+use loki_launch::{
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::Solver,
+};
+use utils::{make_request_from_config, model_builder::ModelBuilder, Config};
+
+#[test]
+fn test_engine_stats() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:20:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("B", "10:30:00")
+                .st("C", "11:00:00");
+        })
+        .add_transfer("B", "B", "00:02:00")
+        .build();
+
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+    let request_input = make_request_from_config(&config);
+
+    let solution = solver.solve_journey_request_with_cancellation(
+        &data,
+        &model_refs,
+        &request_input,
+        None,
+        DateTimeRepresent::Departure,
+        &Cancellation::none(),
+    )?;
+
+    assert_eq!(solution.journeys.len(), 1);
+
+    let stats = &solution.stats;
+    // one round to ride "first", one round to ride "second"
+    assert_eq!(stats.nb_of_rounds, 2);
+    assert_eq!(stats.nb_of_trips_boarded, 2);
+    assert!(stats.nb_of_missions_scanned >= 2);
+    assert_eq!(stats.arrive_front_size, 1);
+    assert_eq!(stats.max_board_front_size, 1);
+    assert!(stats.journeys_tree_size > 0);
+
+    // the stats are reset by each computation
+    let solution = solver.solve_journey_request_with_cancellation(
+        &data,
+        &model_refs,
+        &request_input,
+        None,
+        DateTimeRepresent::Departure,
+        &Cancellation::none(),
+    )?;
+    assert_eq!(solution.stats.nb_of_trips_boarded, 2);
+
+    Ok(())
+}
@@ -0,0 +1,231 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    fares::{compute_fare, Cost, Fare},
+    models::base_model::BaseModel,
+    rust_decimal::Decimal,
+    transit_model::objects::{ObjectType, PerimeterAction, TicketUse},
+    PositiveDuration,
+};
+use loki_launch::loki::models::{real_time_model::RealTimeModel, ModelRefs};
+use utils::{build_and_solve, model_builder::ModelBuilder, Config};
+
+// A journey from A to C uses line "L1" from A (zone 1) to B (zone 1),
+// and then line "L2" from B to C (zone 2)
+fn model_builder() -> ModelBuilder {
+    ModelBuilder::new("2020-01-01", "2020-01-02")
+        .stop_area("sa:A", |_| {})
+        .stop_area("sa:B", |_| {})
+        .stop_area("sa:C", |_| {})
+        .stop_point("A", |stop_point| {
+            stop_point.fare_zone_id = Some("1".to_string());
+        })
+        .stop_point("B", |stop_point| {
+            stop_point.fare_zone_id = Some("1".to_string());
+        })
+        .stop_point("C", |stop_point| {
+            stop_point.fare_zone_id = Some("2".to_string());
+        })
+        .vj("first", |vj_builder| {
+            vj_builder
+                .line("L1")
+                .st("A", "10:00:00")
+                .st("B", "10:10:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .line("L2")
+                .st("B", "10:20:00")
+                .st("C", "10:30:00");
+        })
+        .add_transfer("B", "B", "00:02:00")
+}
+
+fn fare_from_a_to_c(model_builder: ModelBuilder) -> Result<Fare, Error> {
+    let model = model_builder.build();
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+    let journeys = build_and_solve(&model_refs, &config)?;
+    assert_eq!(journeys.len(), 1);
+    assert_eq!(journeys[0].nb_of_vehicles(), 2);
+
+    Ok(compute_fare(&journeys[0], &model_refs))
+}
+
+fn euros(value: &str) -> Cost {
+    Cost {
+        value: value.parse::<Decimal>().unwrap(),
+        currency: "EUR".to_string(),
+    }
+}
+
+fn ticket_ids(fare: &Fare) -> Vec<&str> {
+    fare.tickets
+        .iter()
+        .map(|ticket| ticket.ticket_id.as_str())
+        .collect()
+}
+
+#[test]
+fn test_one_ticket_per_section() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let model_builder = model_builder().ticket("single", "1.50", "EUR", |ticket_use| {
+        ticket_use.max_transfers = Some(0);
+    });
+    let fare = fare_from_a_to_c(model_builder)?;
+
+    assert!(fare.is_complete());
+    assert_eq!(ticket_ids(&fare), vec!["single", "single"]);
+    assert_eq!(fare.tickets[0].sections, vec![0]);
+    assert_eq!(fare.tickets[1].sections, vec![1]);
+    assert_eq!(fare.total(), Some(euros("3.00")));
+
+    Ok(())
+}
+
+#[test]
+fn test_ticket_with_transfer() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let with_transfer = |ticket_use: &mut TicketUse| {
+        ticket_use.max_transfers = Some(1);
+    };
+    let fare = fare_from_a_to_c(model_builder().ticket("t+", "1.50", "EUR", with_transfer))?;
+    assert_eq!(ticket_ids(&fare), vec!["t+"]);
+    assert_eq!(fare.tickets[0].sections, vec![0, 1]);
+    assert_eq!(fare.total(), Some(euros("1.50")));
+
+    // the second vehicle is boarded 20 minutes after the first one
+    let with_short_time_limit = |ticket_use: &mut TicketUse| {
+        ticket_use.max_transfers = Some(1);
+        ticket_use.boarding_time_limit = Some(15 * 60);
+    };
+    let fare =
+        fare_from_a_to_c(model_builder().ticket("t+", "1.50", "EUR", with_short_time_limit))?;
+    assert_eq!(ticket_ids(&fare), vec!["t+", "t+"]);
+    assert_eq!(fare.total(), Some(euros("3.00")));
+
+    Ok(())
+}
+
+#[test]
+fn test_perimeter_and_zones() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let no_transfer = |ticket_use: &mut TicketUse| {
+        ticket_use.max_transfers = Some(0);
+    };
+    let model_builder = model_builder()
+        // only valid on line L1
+        .ticket("urban", "1.00", "EUR", no_transfer)
+        .ticket_perimeter("urban", ObjectType::Line, "L1", PerimeterAction::Included)
+        // only valid from zone 1 to zone 2
+        .ticket("zone_1_2", "2.00", "EUR", no_transfer)
+        .ticket_zone_restriction("zone_1_2", "1", "2")
+        // valid everywhere, but expensive
+        .ticket("taxi", "20.00", "EUR", no_transfer);
+
+    let fare = fare_from_a_to_c(model_builder)?;
+    assert_eq!(ticket_ids(&fare), vec!["urban", "zone_1_2"]);
+    assert_eq!(
+        fare.section_ticket(1)
+            .map(|ticket| ticket.ticket_id.as_str()),
+        Some("zone_1_2")
+    );
+    assert_eq!(fare.total(), Some(euros("3.00")));
+
+    Ok(())
+}
+
+#[test]
+fn test_unpriced_section() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let model_builder = model_builder()
+        .ticket("urban", "1.00", "EUR", |_| {})
+        .ticket_perimeter("urban", ObjectType::Line, "L2", PerimeterAction::Excluded);
+
+    let fare = fare_from_a_to_c(model_builder)?;
+    assert!(!fare.is_complete());
+    assert_eq!(ticket_ids(&fare), vec!["urban"]);
+    assert_eq!(fare.tickets[0].sections, vec![0]);
+    assert_eq!(fare.unpriced_sections, vec![1]);
+    assert_eq!(fare.total(), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_several_currencies() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let no_transfer = |ticket_use: &mut TicketUse| {
+        ticket_use.max_transfers = Some(0);
+    };
+    // the cheapest amount would be "single", but its amount
+    // cannot be compared with the amount of "urban"
+    let several_currencies = model_builder()
+        .ticket("urban", "2.00", "EUR", no_transfer)
+        .ticket("single", "1.00", "CHF", no_transfer);
+
+    let fare = fare_from_a_to_c(several_currencies)?;
+    assert!(fare.tickets.is_empty());
+    assert_eq!(fare.unpriced_sections, vec![0, 1]);
+    assert_eq!(fare.total(), None);
+
+    // a ticket in another currency that cannot cover the journey is ignored
+    let unusable_currency = model_builder()
+        .ticket("urban", "2.00", "EUR", no_transfer)
+        .ticket("single", "1.00", "CHF", no_transfer)
+        .ticket_perimeter("single", ObjectType::Line, "L3", PerimeterAction::Included);
+
+    let fare = fare_from_a_to_c(unusable_currency)?;
+    assert_eq!(ticket_ids(&fare), vec!["urban", "urban"]);
+    assert_eq!(fare.total(), Some(euros("4.00")));
+
+    Ok(())
+}
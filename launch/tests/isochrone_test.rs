@@ -0,0 +1,184 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+mod utils;
+use anyhow::Error;
+use loki::{models::base_model::BaseModel, PositiveDuration};
+use loki_launch::{
+    config::RequestParams,
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+};
+use utils::{
+    build_and_solve_isochrone,
+    model_builder::{AsDateTime, ModelBuilder},
+    Config,
+};
+
+#[test]
+fn test_isochrone_departure() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:20:00")
+                .st("C", "10:40:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("B", "10:30:00")
+                .st("D", "11:00:00");
+        })
+        .vj("far", |vj_builder| {
+            vj_builder
+                .route("3")
+                .st("D", "11:10:00")
+                .st("E", "13:00:00");
+        })
+        .add_transfer("B", "B", "00:02:00")
+        .add_transfer("D", "D", "00:02:00")
+        .build();
+
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "E");
+    let config = Config {
+        request_params: RequestParams {
+            max_journey_duration: PositiveDuration::from_hms(2, 0, 0),
+            ..config.request_params
+        },
+        ..config
+    };
+
+    let responses = build_and_solve_isochrone(&model_refs, &config)?;
+
+    let reached: Vec<_> = responses
+        .iter()
+        .map(|response| {
+            (
+                model_refs.stop_point_name(&response.stop_point_idx),
+                response.datetime,
+                response.nb_of_legs,
+            )
+        })
+        .collect();
+
+    // "E" cannot be reached within 2 hours
+    assert_eq!(
+        reached,
+        vec![
+            ("A", "2020-01-01T09:50:00".as_datetime(), 0),
+            ("B", "2020-01-01T10:20:00".as_datetime(), 1),
+            ("C", "2020-01-01T10:40:00".as_datetime(), 1),
+            ("D", "2020-01-01T11:00:00".as_datetime(), 2),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_isochrone_arrival() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:20:00")
+                .st("C", "10:40:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("D", "09:30:00")
+                .st("A", "09:50:00");
+        })
+        .add_transfer("A", "A", "00:02:00")
+        .build();
+
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let config = Config::new("2020-01-01T11:00:00", "A", "C");
+    let config = Config {
+        datetime_represent: DateTimeRepresent::Arrival,
+        ..config
+    };
+
+    let responses = build_and_solve_isochrone(&model_refs, &config)?;
+
+    let reached: Vec<_> = responses
+        .iter()
+        .map(|response| {
+            (
+                model_refs.stop_point_name(&response.stop_point_idx),
+                response.datetime,
+                response.nb_of_legs,
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        reached,
+        vec![
+            ("C", "2020-01-01T11:00:00".as_datetime(), 0),
+            ("B", "2020-01-01T10:20:00".as_datetime(), 1),
+            ("A", "2020-01-01T10:00:00".as_datetime(), 1),
+            ("D", "2020-01-01T09:30:00".as_datetime(), 2),
+        ]
+    );
+
+    Ok(())
+}
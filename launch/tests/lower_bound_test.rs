@@ -0,0 +1,188 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{models::base_model::BaseModel, Cancellation, DataTrait, PositiveDuration, TransitData};
+use loki_launch::{
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::{JourneysSolution, Solver},
+};
+use utils::{
+    from_to_stop_point_names, make_request_from_config, model_builder::ModelBuilder, Config,
+};
+
+fn solve(
+    data: &TransitData,
+    model_refs: &ModelRefs<'_>,
+    config: &Config,
+    lower_bound_pruning: bool,
+) -> Result<JourneysSolution, Error> {
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    let mut request_input = make_request_from_config(config);
+    request_input.lower_bound_pruning = lower_bound_pruning;
+
+    let solution = solver.solve_journey_request_with_cancellation(
+        data,
+        model_refs,
+        &request_input,
+        None,
+        config.datetime_represent,
+        &Cancellation::none(),
+    )?;
+    Ok(solution)
+}
+
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("direct", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("C", "10:30:00");
+        })
+        .vj("detour", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("A", "10:00:00")
+                .st("D", "10:20:00");
+        })
+        .vj("far", |vj_builder| {
+            vj_builder
+                .route("3")
+                .st("D", "10:30:00")
+                .st("C", "11:30:00");
+        })
+        .vj("dead_end", |vj_builder| {
+            vj_builder
+                .route("4")
+                .st("A", "10:00:00")
+                .st("E", "10:10:00");
+        })
+        .vj("from_f", |vj_builder| {
+            vj_builder
+                .route("5")
+                .st("F", "10:00:00")
+                .st("C", "10:40:00");
+        })
+        .add_transfer("D", "D", "00:02:00")
+        .add_transfer("E", "E", "00:02:00")
+        .add_transfer("F", "F", "00:02:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_lower_bound_pruning_depart_after() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+
+    let without_pruning = solve(&data, &model_refs, &config, false)?;
+    let with_pruning = solve(&data, &model_refs, &config, true)?;
+
+    assert_eq!(without_pruning.journeys.len(), 1);
+    assert_eq!(with_pruning.journeys.len(), 1);
+    let journey = &with_pruning.journeys[0];
+    assert_eq!(journey.nb_of_sections(), 1);
+    let (from_sp, to_sp) = from_to_stop_point_names(
+        &journey.first_vehicle,
+        &model_refs,
+        config.request_params.real_time_level,
+    )?;
+    assert_eq!(from_sp, "A");
+    assert_eq!(to_sp, "C");
+
+    // without pruning, the waits at D and E are explored
+    // and "far" is ridden in a second round
+    assert_eq!(without_pruning.stats.nb_of_lower_bound_prunings, 0);
+    assert_eq!(without_pruning.stats.nb_of_rounds, 2);
+
+    // with pruning, D is too far from C to beat the "direct" journey
+    // and C cannot be reached from E
+    assert_eq!(with_pruning.stats.nb_of_lower_bound_prunings, 2);
+    assert_eq!(with_pruning.stats.nb_of_rounds, 1);
+    assert!(
+        with_pruning.stats.nb_of_missions_scanned < without_pruning.stats.nb_of_missions_scanned
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_lower_bound_pruning_arrive_before() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let mut config = Config::new("2020-01-01T12:00:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+
+    let without_pruning = solve(&data, &model_refs, &config, false)?;
+    let with_pruning = solve(&data, &model_refs, &config, true)?;
+
+    assert_eq!(with_pruning.journeys.len(), without_pruning.journeys.len());
+    for (pruned, unpruned) in with_pruning
+        .journeys
+        .iter()
+        .zip(without_pruning.journeys.iter())
+    {
+        assert_eq!(pruned.nb_of_sections(), unpruned.nb_of_sections());
+        assert_eq!(
+            pruned.first_vj_uri(&model_refs),
+            unpruned.first_vj_uri(&model_refs)
+        );
+    }
+    // F cannot be reached from A
+    assert_eq!(without_pruning.stats.nb_of_lower_bound_prunings, 0);
+    assert_eq!(with_pruning.stats.nb_of_lower_bound_prunings, 1);
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+mod utils;
+use anyhow::Error;
+use loki::{models::base_model::BaseModel, DataTrait, InputStop, PositiveDuration, TransitData};
+use loki_launch::{
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::Solver,
+};
+use utils::{
+    make_request_from_config,
+    model_builder::{AsDateTime, ModelBuilder},
+    Config,
+};
+
+fn input_stops(stop_point_uri: &str) -> Vec<(InputStop, PositiveDuration)> {
+    vec![(
+        InputStop::StopPoint(stop_point_uri.to_string()),
+        PositiveDuration::zero(),
+    )]
+}
+
+#[test]
+fn test_matrix() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:20:00")
+                .st("C", "10:40:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("B", "10:30:00")
+                .st("D", "11:00:00");
+        })
+        .add_transfer("B", "B", "00:02:00")
+        .build();
+
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "D");
+    let request_input = make_request_from_config(&config);
+
+    let origins = vec![input_stops("A"), input_stops("B"), input_stops("unknown")];
+    let destinations = vec![input_stops("C"), input_stops("D"), input_stops("A")];
+
+    let matrix = solver.solve_matrix(
+        &data,
+        &model_refs,
+        &request_input,
+        &origins,
+        &destinations,
+        None,
+    )?;
+
+    assert_eq!(matrix.nb_of_origins(), 3);
+    assert_eq!(matrix.nb_of_destinations(), 3);
+
+    let a_to_c = matrix.cell(0, 0).unwrap();
+    assert_eq!(a_to_c.arrival_datetime, "2020-01-01T10:40:00".as_datetime());
+    assert_eq!(a_to_c.duration, PositiveDuration::from_hms(0, 50, 0));
+    assert_eq!(a_to_c.nb_of_transfers, 0);
+
+    let a_to_d = matrix.cell(0, 1).unwrap();
+    assert_eq!(a_to_d.arrival_datetime, "2020-01-01T11:00:00".as_datetime());
+    assert_eq!(a_to_d.duration, PositiveDuration::from_hms(1, 10, 0));
+    assert_eq!(a_to_d.nb_of_transfers, 1);
+
+    let a_to_a = matrix.cell(0, 2).unwrap();
+    assert_eq!(a_to_a.duration, PositiveDuration::zero());
+    assert_eq!(a_to_a.nb_of_transfers, 0);
+
+    let b_to_d = matrix.cell(1, 1).unwrap();
+    assert_eq!(b_to_d.arrival_datetime, "2020-01-01T11:00:00".as_datetime());
+    assert_eq!(b_to_d.nb_of_transfers, 0);
+
+    // no vehicle goes from B to A
+    assert!(matrix.cell(1, 2).is_none());
+
+    // the unknown origin reaches nothing
+    assert!(matrix.cell(2, 0).is_none());
+    assert!(matrix.cell(2, 1).is_none());
+    assert!(matrix.cell(2, 2).is_none());
+
+    Ok(())
+}
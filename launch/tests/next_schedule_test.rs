@@ -537,6 +537,36 @@ fn test_forbidden_filter(fixture_model: BaseModel) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn test_on_vehicle_journey(fixture_model: BaseModel) -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    // In this test we expect to receive all departures of the stop_points
+    // served by the vehicle journey toto_1
+
+    let mut config = ScheduleConfig::new(
+        ScheduleOn::BoardTimes,
+        "vehicle_journey:toto_1",
+        "2020-01-02T10:00:00",
+    );
+    config.duration = 4 * 60 * 60; // 4h
+
+    // toto_1 serves A, B and C, where toto_2 also stops
+    // we expect 4 results :
+    //  - 2 on stop_point A (10:00:00 & 11:00:00)
+    //  - 2 on stop_point B (10:05:00 & 11:05:00)
+    //  - 0 on stop_point C because there is no departure on terminus
+    let result = build_and_solve_schedule(&config, &fixture_model)?;
+    assert_eq!(result.len(), 4);
+
+    // we don't want the departures of toto_2
+    config.forbidden_uris = vec!["vehicle_journey:toto_2"];
+    let result = build_and_solve_schedule(&config, &fixture_model)?;
+    assert_eq!(result.len(), 2);
+
+    Ok(())
+}
+
 struct ScheduleConfig<'a> {
     pub schedule_on: ScheduleOn,
     pub input_filter: String,
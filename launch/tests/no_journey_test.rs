@@ -0,0 +1,285 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    filters::{parse_filter, Filters},
+    models::{base_model::BaseModel, VehicleJourneyIdx},
+    DataTrait, PositiveDuration, RealTimeLevel, TransitData,
+};
+use loki_launch::{
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::{NoJourneyReason, Solver},
+};
+use utils::{
+    make_request_from_config,
+    model_builder::{AsDate, ModelBuilder},
+    Config,
+};
+
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:05:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("D", "10:10:00")
+                .st("C", "10:20:00");
+        })
+        .vj("slow", |vj_builder| {
+            vj_builder
+                .route("3")
+                .st("E", "10:00:00")
+                .st("F", "14:00:00");
+        })
+        .add_transfer("B", "D", "00:02:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+// solves the request of `config`, and returns the journeys found
+// along with the reasons why no journey was found
+fn solve_and_explain(
+    data: &TransitData,
+    model_refs: &ModelRefs<'_>,
+    config: &Config,
+) -> Result<(usize, Vec<NoJourneyReason>), Error> {
+    let make_filters = || {
+        let forbidden_filters = config
+            .forbidden_uris
+            .iter()
+            .filter_map(|forbidden_uri| parse_filter(model_refs, forbidden_uri, "test"));
+        Filters::new(forbidden_filters, std::iter::empty(), false, false)
+    };
+
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    let request_input = make_request_from_config(config);
+    let journeys = solver.solve_journey_request(
+        data,
+        model_refs,
+        &request_input,
+        make_filters(),
+        config.datetime_represent,
+    )?;
+    let reasons = solver.explain_no_journey(
+        data,
+        model_refs,
+        &request_input,
+        make_filters(),
+        config.datetime_represent,
+    )?;
+    Ok((journeys.len(), reasons))
+}
+
+#[test]
+fn test_no_reason_when_journeys_exist() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+    let (nb_of_journeys, reasons) = solve_and_explain(&data, &model_refs, &config)?;
+    assert_eq!(nb_of_journeys, 1);
+    assert!(reasons.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_stops_forbidden_by_filters() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.forbidden_uris = vec!["stop_point:A"];
+    let (nb_of_journeys, reasons) = solve_and_explain(&data, &model_refs, &config)?;
+    assert_eq!(nb_of_journeys, 0);
+    assert_eq!(reasons, vec![NoJourneyReason::DepartureStopsFiltered]);
+
+    config.forbidden_uris = vec!["stop_point:C"];
+    let (nb_of_journeys, reasons) = solve_and_explain(&data, &model_refs, &config)?;
+    assert_eq!(nb_of_journeys, 0);
+    assert_eq!(reasons, vec![NoJourneyReason::ArrivalStopsFiltered]);
+
+    Ok(())
+}
+
+#[test]
+fn test_vehicles_forbidden_by_filters() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    // the only vehicle leaving A is forbidden
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.forbidden_uris = vec!["route:1"];
+    let (nb_of_journeys, reasons) = solve_and_explain(&data, &model_refs, &config)?;
+    assert_eq!(nb_of_journeys, 0);
+    assert_eq!(reasons, vec![NoJourneyReason::NoTripAtDepartureStops]);
+
+    // the vehicle that reaches C is forbidden
+    config.forbidden_uris = vec!["route:2"];
+    let (nb_of_journeys, reasons) = solve_and_explain(&data, &model_refs, &config)?;
+    assert_eq!(nb_of_journeys, 0);
+    assert_eq!(reasons, vec![NoJourneyReason::ForbiddenByFilters]);
+
+    Ok(())
+}
+
+#[test]
+fn test_no_trip_after_datetime() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let config = Config::new("2020-01-02T10:30:00", "A", "C");
+    let (nb_of_journeys, reasons) = solve_and_explain(&data, &model_refs, &config)?;
+    assert_eq!(nb_of_journeys, 0);
+    assert_eq!(reasons, vec![NoJourneyReason::NoTripAtDepartureStops]);
+
+    let mut config = Config::new("2020-01-01T10:15:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    let (nb_of_journeys, reasons) = solve_and_explain(&data, &model_refs, &config)?;
+    assert_eq!(nb_of_journeys, 0);
+    assert_eq!(reasons, vec![NoJourneyReason::NoTripAtArrivalStops]);
+
+    Ok(())
+}
+
+#[test]
+fn test_too_many_legs() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.max_nb_of_legs = 1;
+    let (nb_of_journeys, reasons) = solve_and_explain(&data, &model_refs, &config)?;
+    assert_eq!(nb_of_journeys, 0);
+    assert_eq!(
+        reasons,
+        vec![NoJourneyReason::TooManyLegs {
+            max_nb_of_legs: 1,
+            nb_of_legs_needed: 2
+        }]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_too_long() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "E", "F");
+    config.request_params.max_journey_duration = PositiveDuration::from_hms(2, 0, 0);
+    let (nb_of_journeys, reasons) = solve_and_explain(&data, &model_refs, &config)?;
+    assert_eq!(nb_of_journeys, 0);
+    assert_eq!(
+        reasons,
+        vec![NoJourneyReason::TooLong {
+            max_journey_duration: PositiveDuration::from_hms(2, 0, 0),
+            duration_needed: PositiveDuration::from_hms(4, 10, 0),
+        }]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_real_time_disruptions() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let mut data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let vehicle_journey_idx = base_model.vehicle_journey_idx("second").unwrap();
+    data.remove_real_time_vehicle(
+        &VehicleJourneyIdx::Base(vehicle_journey_idx),
+        "2020-01-01".as_date(),
+    )
+    .unwrap();
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.real_time_level = RealTimeLevel::RealTime;
+    let (nb_of_journeys, reasons) = solve_and_explain(&data, &model_refs, &config)?;
+    assert_eq!(nb_of_journeys, 0);
+    // the vehicle "second" of the next day gives a journey that is too long
+    assert!(reasons.contains(&NoJourneyReason::RealTimeDisruptions));
+
+    // the base schedule is untouched
+    config.request_params.real_time_level = RealTimeLevel::Base;
+    let (nb_of_journeys, reasons) = solve_and_explain(&data, &model_refs, &config)?;
+    assert_eq!(nb_of_journeys, 1);
+    assert!(reasons.is_empty());
+
+    Ok(())
+}
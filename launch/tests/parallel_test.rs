@@ -0,0 +1,194 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+mod utils;
+use anyhow::Error;
+use loki::{
+    models::base_model::BaseModel, response::Response, Cancellation, DataTrait, PositiveDuration,
+    TransitData,
+};
+use loki_launch::{
+    config::ComparatorType,
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::{JourneysSolution, Solver},
+};
+use utils::{make_request_from_config, model_builder::ModelBuilder, Config};
+
+fn solve(
+    data: &TransitData,
+    model_refs: &ModelRefs<'_>,
+    config: &Config,
+    nb_of_threads: usize,
+) -> Result<JourneysSolution, Error> {
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    solver.set_nb_of_threads(nb_of_threads);
+    let request_input = make_request_from_config(config);
+
+    let solution = solver.solve_journey_request_with_cancellation(
+        data,
+        model_refs,
+        &request_input,
+        None,
+        config.datetime_represent,
+        &Cancellation::none(),
+    )?;
+    Ok(solution)
+}
+
+fn summary(journey: &Response, model_refs: &ModelRefs<'_>) -> String {
+    format!(
+        "{} {} {} {}",
+        journey.first_vj_uri(model_refs),
+        journey.first_vehicle_board_datetime(),
+        journey.last_vehicle_debark_datetime(),
+        journey.nb_of_transfers()
+    )
+}
+
+// several missions leave A in the first round and debark at the same stops,
+// so that the debarks found by different threads compete with each other
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("direct_slow", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:30:00")
+                .st("F", "12:00:00");
+        })
+        .vj("to_b_fast", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("A", "10:05:00")
+                .st("B", "10:20:00");
+        })
+        .vj("to_b_late", |vj_builder| {
+            vj_builder
+                .route("3")
+                .st("A", "10:10:00")
+                .st("B", "10:40:00")
+                .st("C", "10:50:00");
+        })
+        .vj("to_c", |vj_builder| {
+            vj_builder
+                .route("4")
+                .st("A", "10:00:00")
+                .st("C", "10:45:00");
+        })
+        .vj("b_to_f", |vj_builder| {
+            vj_builder
+                .route("5")
+                .st("B", "10:35:00")
+                .st("D", "10:50:00")
+                .st("F", "11:20:00");
+        })
+        .vj("c_to_f", |vj_builder| {
+            vj_builder
+                .route("6")
+                .st("C", "10:55:00")
+                .st("E", "11:00:00")
+                .st("F", "11:10:00");
+        })
+        .vj("d_to_f", |vj_builder| {
+            vj_builder
+                .route("7")
+                .st("D", "10:55:00")
+                .st("F", "11:05:00");
+        })
+        .add_transfer("B", "B", "00:02:00")
+        .add_transfer("C", "C", "00:02:00")
+        .add_transfer("D", "D", "00:02:00")
+        .add_transfer("E", "E", "00:02:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_parallel_ride_gives_same_journeys() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    for datetime_represent in [DateTimeRepresent::Departure, DateTimeRepresent::Arrival] {
+        for comparator_type in [
+            ComparatorType::Basic,
+            ComparatorType::Robustness,
+            ComparatorType::Occupancy,
+        ] {
+            let mut config = match datetime_represent {
+                DateTimeRepresent::Departure => Config::new("2020-01-01T09:50:00", "A", "F"),
+                DateTimeRepresent::Arrival => Config::new("2020-01-01T12:30:00", "A", "F"),
+            };
+            config.datetime_represent = datetime_represent;
+            config.comparator_type = comparator_type;
+
+            let sequential = solve(&data, &model_refs, &config, 1)?;
+            let sequential_journeys: Vec<String> = sequential
+                .journeys
+                .iter()
+                .map(|journey| summary(journey, &model_refs))
+                .collect();
+            assert!(!sequential_journeys.is_empty());
+
+            for nb_of_threads in [2, 3, 8] {
+                let parallel = solve(&data, &model_refs, &config, nb_of_threads)?;
+                let parallel_journeys: Vec<String> = parallel
+                    .journeys
+                    .iter()
+                    .map(|journey| summary(journey, &model_refs))
+                    .collect();
+                assert_eq!(parallel_journeys, sequential_journeys);
+                assert_eq!(parallel.stats.nb_of_rounds, sequential.stats.nb_of_rounds);
+                assert_eq!(
+                    parallel.stats.nb_of_missions_scanned,
+                    sequential.stats.nb_of_missions_scanned
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
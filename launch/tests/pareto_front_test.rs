@@ -0,0 +1,179 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{models::base_model::BaseModel, Cancellation, DataTrait, PositiveDuration, TransitData};
+use loki_launch::{
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::Solver,
+};
+use utils::{
+    make_request_from_config,
+    model_builder::{AsDateTime, ModelBuilder},
+    Config,
+};
+
+// returns the number of vehicles and the arrival datetime of each journey
+fn solve(
+    data: &TransitData,
+    model_refs: &ModelRefs<'_>,
+    config: &Config,
+) -> Result<Vec<(usize, String)>, Error> {
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    let request_input = make_request_from_config(config);
+
+    let solution = solver.solve_journey_request_with_cancellation(
+        data,
+        model_refs,
+        &request_input,
+        None,
+        config.datetime_represent,
+        &Cancellation::none(),
+    )?;
+    let mut journeys: Vec<(usize, String)> = solution
+        .journeys
+        .iter()
+        .map(|journey| {
+            (
+                journey.nb_of_vehicles(),
+                journey.last_vehicle_debark_datetime().to_string(),
+            )
+        })
+        .collect();
+    journeys.sort();
+    Ok(journeys)
+}
+
+// Each additional leg allows to arrive earlier at C,
+// so that the front of journeys at C contains one journey per number of legs.
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("direct", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("C", "11:30:00");
+        })
+        .vj("A_to_B", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("A", "10:00:00")
+                .st("B", "10:10:00");
+        })
+        .vj("B_to_C", |vj_builder| {
+            vj_builder
+                .route("3")
+                .st("B", "10:20:00")
+                .st("C", "11:10:00");
+        })
+        .vj("B_to_D", |vj_builder| {
+            vj_builder
+                .route("4")
+                .st("B", "10:20:00")
+                .st("D", "10:30:00");
+        })
+        .vj("D_to_C", |vj_builder| {
+            vj_builder
+                .route("5")
+                .st("D", "10:40:00")
+                .st("C", "10:50:00");
+        })
+        .vj("late_direct", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:30:00")
+                .st("C", "12:00:00");
+        })
+        .add_transfer("B", "B", "00:02:00")
+        .add_transfer("D", "D", "00:02:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_pareto_front_with_trade_offs() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let expected = vec![
+        (1, "2020-01-01T11:30:00".as_datetime().to_string()),
+        (2, "2020-01-01T11:10:00".as_datetime().to_string()),
+        (3, "2020-01-01T10:50:00".as_datetime().to_string()),
+    ];
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.criteria = Some("time,nb_of_legs".parse()?);
+    assert_eq!(solve(&data, &model_refs, &config)?, expected);
+
+    // "late_direct" departs after all other journeys, with less legs
+    let mut config = Config::new("2020-01-01T12:10:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    config.request_params.criteria = Some("time,nb_of_legs".parse()?);
+    assert_eq!(
+        solve(&data, &model_refs, &config)?,
+        vec![(1, "2020-01-01T12:00:00".as_datetime().to_string())]
+    );
+
+    // all the other journeys depart at 10:00, so the one with the less legs is kept
+    let mut config = Config::new("2020-01-01T11:40:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    config.request_params.criteria = Some("time,nb_of_legs".parse()?);
+    assert_eq!(
+        solve(&data, &model_refs, &config)?,
+        vec![expected[0].clone()]
+    );
+
+    // with only the time criterion, only the journey that arrives the earliest is kept
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.criteria = Some("time".parse()?);
+    assert_eq!(
+        solve(&data, &model_refs, &config)?,
+        vec![expected[2].clone()]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+mod utils;
+use anyhow::Error;
+use loki::{models::base_model::BaseModel, Cancellation, DataTrait, PositiveDuration, TransitData};
+use loki_launch::{
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::Solver,
+};
+use utils::{make_request_from_config, model_builder::ModelBuilder, Config};
+
+fn solve(
+    data: &TransitData,
+    model_refs: &ModelRefs<'_>,
+    config: &Config,
+) -> Result<Vec<String>, Error> {
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    let request_input = make_request_from_config(config);
+
+    let solution = solver.solve_journey_request_with_cancellation(
+        data,
+        model_refs,
+        &request_input,
+        None,
+        config.datetime_represent,
+        &Cancellation::none(),
+    )?;
+    let mut vj_uris: Vec<String> = solution
+        .journeys
+        .iter()
+        .map(|journey| journey.first_vj_uri(model_refs).to_string())
+        .collect();
+    vj_uris.sort();
+    Ok(vj_uris)
+}
+
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("fast", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:20:00")
+                .st("C", "10:30:00");
+        })
+        .vj("slow", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("A", "10:15:00")
+                .st("C", "10:35:00");
+        })
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_pareto_slack_depart_after() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    // "slow" arrives 5 minutes after "fast", and "fast" takes 40 minutes
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+
+    // without slack, "slow" is dominated by "fast"
+    assert_eq!(solve(&data, &model_refs, &config)?, vec!["fast"]);
+
+    config.request_params.pareto_slack_duration = "00:05:00".parse()?;
+    assert_eq!(solve(&data, &model_refs, &config)?, vec!["fast", "slow"]);
+
+    config.request_params.pareto_slack_duration = "00:04:00".parse()?;
+    assert_eq!(solve(&data, &model_refs, &config)?, vec!["fast"]);
+
+    // 15% of 40 minutes is 6 minutes
+    config.request_params.pareto_slack_percentage = 15;
+    assert_eq!(solve(&data, &model_refs, &config)?, vec!["fast", "slow"]);
+
+    // 10% of 40 minutes is 4 minutes
+    config.request_params.pareto_slack_percentage = 10;
+    assert_eq!(solve(&data, &model_refs, &config)?, vec!["fast"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_pareto_slack_arrive_before() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    // "slow" departs 5 minutes before "fast", and "fast" takes 40 minutes
+    let mut config = Config::new("2020-01-01T11:00:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+
+    assert_eq!(solve(&data, &model_refs, &config)?, vec!["fast"]);
+
+    config.request_params.pareto_slack_duration = "00:05:00".parse()?;
+    assert_eq!(solve(&data, &model_refs, &config)?, vec!["fast", "slow"]);
+
+    config.request_params.pareto_slack_duration = "00:00:00".parse()?;
+    config.request_params.pareto_slack_percentage = 15;
+    assert_eq!(solve(&data, &model_refs, &config)?, vec!["fast", "slow"]);
+
+    config.request_params.pareto_slack_percentage = 10;
+    assert_eq!(solve(&data, &model_refs, &config)?, vec!["fast"]);
+
+    Ok(())
+}
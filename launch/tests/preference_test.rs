@@ -0,0 +1,212 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    models::{base_model::BaseModel, real_time_model::RealTimeModel, ModelRefs},
+    PositiveDuration,
+};
+use loki_launch::datetime::DateTimeRepresent;
+use utils::{build_and_solve, model_builder::ModelBuilder, Config};
+
+// From A to C, `bus` departs later and arrives earlier than `tram`
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("bus", |vj_builder| {
+            vj_builder
+                .line("38")
+                .st("A", "10:10:00")
+                .st("C", "10:30:00");
+        })
+        .vj("tram", |vj_builder| {
+            vj_builder
+                .line("T1")
+                .st("A", "10:00:00")
+                .st("C", "10:40:00");
+        })
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+fn solve(model_refs: &ModelRefs<'_>, config: &Config) -> Result<Vec<String>, Error> {
+    let journeys = build_and_solve(model_refs, config)?;
+    let mut vehicle_journeys: Vec<String> = journeys
+        .iter()
+        .map(|journey| journey.first_vj_uri(model_refs).to_string())
+        .collect();
+    vehicle_journeys.sort();
+    Ok(vehicle_journeys)
+}
+
+#[test]
+fn test_penalized_line() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    assert_eq!(solve(&model_refs, &config)?, vec!["bus"]);
+
+    // a 5 minutes penalty is not enough to lose 10 minutes
+    config.preferences = vec![("line:38", 5 * 60)];
+    assert_eq!(solve(&model_refs, &config)?, vec!["bus"]);
+
+    config.preferences = vec![("line:38", 20 * 60)];
+    assert_eq!(solve(&model_refs, &config)?, vec!["tram"]);
+
+    let mut config = Config::new("2020-01-01T11:00:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    assert_eq!(solve(&model_refs, &config)?, vec!["bus"]);
+
+    config.preferences = vec![("line:38", 20 * 60)];
+    assert_eq!(solve(&model_refs, &config)?, vec!["tram"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_favored_line() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    // the bonus of boarding `tram` is at most the 2 minutes leg arrival penalty,
+    // which is not enough to make up for 10 minutes
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.preferences = vec![("line:T1", -20 * 60)];
+    assert_eq!(solve(&model_refs, &config)?, vec!["bus"]);
+
+    // with a 15 minutes leg arrival penalty, the bonus of `tram` is 15 minutes
+    config.request_params.leg_arrival_penalty = PositiveDuration::from_hms(0, 15, 0);
+    assert_eq!(solve(&model_refs, &config)?, vec!["tram"]);
+
+    config.preferences = vec![("line:T1", -5 * 60)];
+    assert_eq!(solve(&model_refs, &config)?, vec!["bus"]);
+
+    let mut config = Config::new("2020-01-01T11:00:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    config.request_params.leg_arrival_penalty = PositiveDuration::from_hms(0, 15, 0);
+    config.preferences = vec![("line:T1", -20 * 60)];
+    assert_eq!(solve(&model_refs, &config)?, vec!["tram"]);
+
+    // unknown uris, stop filters and vehicle filters other than
+    // lines, routes, networks and modes are ignored
+    config.preferences = vec![
+        ("line:unknown", -20 * 60),
+        ("stop_point:C", -20 * 60),
+        ("vehicle_journey:tram", -20 * 60),
+    ];
+    assert_eq!(solve(&model_refs, &config)?, vec!["bus"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_large_penalty() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    // the penalty is bounded by the max journey duration,
+    // and the departure time minus the penalty may precede the start of the dataset
+    let mut config = Config::new("2020-01-01T11:00:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    config.preferences = vec![("line:38", i32::MAX)];
+    assert_eq!(solve(&model_refs, &config)?, vec!["tram"]);
+
+    // both penalties are bounded to the same duration, so `bus` departs later
+    config.preferences = vec![("line:38", i32::MAX), ("line:T1", 1_000_000_000)];
+    assert_eq!(solve(&model_refs, &config)?, vec!["bus"]);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.preferences = vec![("line:38", i32::MAX)];
+    assert_eq!(solve(&model_refs, &config)?, vec!["tram"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_favored_line_does_not_penalize_other_lines() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    // From A to C, `first` then `second` arrive 30 minutes earlier than `direct`,
+    // which has no transfer. `other` is never boarded.
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("direct", |vj_builder| {
+            vj_builder.line("D").st("A", "10:00:00").st("C", "11:00:00");
+        })
+        .vj("first", |vj_builder| {
+            vj_builder.line("F").st("A", "10:00:00").st("B", "10:10:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder.line("S").st("B", "10:15:00").st("C", "10:30:00");
+        })
+        .vj("other", |vj_builder| {
+            vj_builder.line("O").st("E", "10:00:00").st("F", "10:10:00");
+        })
+        .add_transfer("B", "B", "00:02:00")
+        .build();
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct", "first"]);
+
+    // favoring `other` leaves the boardings of the other vehicles unchanged,
+    // so the journey with two legs is not penalized more than the direct one
+    config.preferences = vec![("line:O", -40 * 60)];
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct", "first"]);
+
+    Ok(())
+}
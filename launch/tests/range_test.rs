@@ -0,0 +1,297 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+mod utils;
+use anyhow::Error;
+use loki::{models::base_model::BaseModel, Cancellation, DataTrait, PositiveDuration, TransitData};
+use loki_launch::{
+    config::ComparatorType,
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::Solver,
+};
+use rstest::rstest;
+use utils::{
+    build_and_solve_range, make_request_from_config,
+    model_builder::{AsDateTime, ModelBuilder},
+    Config,
+};
+
+#[rstest]
+#[case(ComparatorType::Occupancy)]
+#[case(ComparatorType::Basic)]
+#[case(ComparatorType::Robustness)]
+fn test_range_departure(#[case] comparator_type: ComparatorType) -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:20:00");
+        })
+        .vj("slow", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("A", "10:10:00")
+                .st("B", "11:00:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:30:00")
+                .st("B", "10:50:00");
+        })
+        .vj("too_late", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "11:30:00")
+                .st("B", "11:50:00");
+        })
+        .build();
+
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "B");
+    let config = Config {
+        comparator_type,
+        ..config
+    };
+
+    let responses =
+        build_and_solve_range(&model_refs, &config, PositiveDuration::from_hms(1, 0, 0))?;
+
+    // "slow" leaves after "first" but arrives after "second"
+    // and "too_late" leaves after the end of the range
+    assert_eq!(responses.len(), 2);
+
+    assert_eq!(responses[0].first_vj_uri(&model_refs), "first");
+    assert_eq!(
+        responses[0].first_vehicle.from_datetime,
+        "2020-01-01T10:00:00".as_datetime()
+    );
+    assert_eq!(responses[1].first_vj_uri(&model_refs), "second");
+    assert_eq!(
+        responses[1].first_vehicle.from_datetime,
+        "2020-01-01T10:30:00".as_datetime()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_range_keeps_earlier_departures_that_arrive_earlier() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("direct", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:10:00")
+                .st("C", "11:00:00");
+        })
+        .vj("toto", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("A", "10:05:00")
+                .st("B", "10:20:00");
+        })
+        .vj("tata", |vj_builder| {
+            vj_builder
+                .route("3")
+                .st("B", "10:30:00")
+                .st("C", "10:55:00");
+        })
+        .add_transfer("B", "B", "00:02:00")
+        .build();
+
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+
+    let responses =
+        build_and_solve_range(&model_refs, &config, PositiveDuration::from_hms(1, 0, 0))?;
+
+    // "toto" then "tata" leaves earlier than "direct", but arrives earlier
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0].first_vj_uri(&model_refs), "toto");
+    assert_eq!(responses[0].nb_of_transfers(), 1);
+    assert_eq!(responses[1].first_vj_uri(&model_refs), "direct");
+    assert_eq!(responses[1].nb_of_transfers(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_range_arrival() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:20:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:30:00")
+                .st("B", "10:50:00");
+        })
+        .vj("too_late", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "11:30:00")
+                .st("B", "11:50:00");
+        })
+        .build();
+
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let config = Config::new("2020-01-01T10:00:00", "A", "B");
+    let config = Config {
+        datetime_represent: DateTimeRepresent::Arrival,
+        ..config
+    };
+
+    let responses =
+        build_and_solve_range(&model_refs, &config, PositiveDuration::from_hms(1, 0, 0))?;
+
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0].first_vj_uri(&model_refs), "first");
+    assert_eq!(responses[1].first_vj_uri(&model_refs), "second");
+
+    Ok(())
+}
+
+#[test]
+fn test_range_with_large_fronts() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    // 9 journeys departing at 10:30 that arrive within the pareto slack of each other,
+    // so that the fronts at C hold more than 8 elements after the first iteration of the range,
+    // and "fast" departing at 10:00, that arrives more than the pareto slack before all of them
+    let mut model_builder = ModelBuilder::new("2020-01-01", "2020-01-02");
+    for idx in 0..9 {
+        // each journey goes through its own stop, so that they belong to different missions
+        let stop = format!("S{}", idx);
+        let arrival_time = format!("11:3{}:00", idx);
+        model_builder = model_builder.vj(&format!("late_{}", idx), |vj_builder| {
+            vj_builder
+                .route(&format!("late_{}", idx))
+                .st("A", "10:30:00")
+                .st(&stop, "11:00:00")
+                .st("C", arrival_time.as_str());
+        });
+    }
+    let model = model_builder
+        .vj("fast", |vj_builder| {
+            vj_builder
+                .route("fast")
+                .st("A", "10:00:00")
+                .st("C", "11:10:00");
+        })
+        .build();
+
+    let base_model = BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.pareto_slack_duration = "00:10:00".parse()?;
+    let request_input = make_request_from_config(&config);
+
+    let solution = solver.solve_range_journey_request_with_cancellation(
+        &data,
+        &model_refs,
+        &request_input,
+        PositiveDuration::from_hms(1, 0, 0),
+        None,
+        DateTimeRepresent::Departure,
+        &Cancellation::none(),
+    )?;
+
+    assert_eq!(solution.journeys.len(), 10);
+    assert_eq!(solution.journeys[0].first_vj_uri(&model_refs), "fast");
+    for (idx, journey) in solution.journeys[1..].iter().enumerate() {
+        assert_eq!(
+            journey.first_vehicle.from_datetime,
+            "2020-01-01T10:30:00".as_datetime()
+        );
+        assert_eq!(
+            journey.last_vehicle_debark_datetime(),
+            format!("2020-01-01T11:3{}:00", idx).as_str().as_datetime()
+        );
+    }
+
+    // the labels found at C by the first iteration are all dominated by "fast"
+    // beyond the pareto slack, and must be removed from the fronts during the second iteration
+    assert_eq!(solution.stats.max_debark_front_size, 1);
+    assert_eq!(solution.stats.arrive_front_size, 1);
+
+    Ok(())
+}
@@ -39,6 +39,7 @@ use anyhow::Error;
 use loki_launch::config::ComparatorType;
 
 use loki::{
+    filters::parse_filter_expression,
     models::{base_model::BaseModel, real_time_model::RealTimeModel, ModelRefs},
     transit_model::objects::Availability::Available,
     PositiveDuration, RealTimeLevel,
@@ -456,3 +457,174 @@ fn test_filter_accessibility_with_transfer(
 
     Ok(())
 }
+
+#[rstest]
+#[case(ComparatorType::Occupancy)]
+#[case(ComparatorType::Basic)]
+fn test_filter_vehicle_journey_property(
+    #[case] comparator_type: ComparatorType,
+    fixture_model: BaseModel,
+) -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    // With Filter : only the vehicle journeys that accept bikes are allowed
+    let config = Config::new("2020-01-01T09:59:00", "A", "C");
+    let config = Config {
+        comparator_type,
+        allowed_uris: vec!["vehicle_journey_property:bike_accepted"],
+        ..config
+    };
+
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&fixture_model, &real_time_model);
+
+    let responses = build_and_solve(&model_refs, &config)?;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].first_vj_uri(&model_refs), "toto_bike");
+
+    // With Filter : vehicle_journey:toto is forbidden
+    let config = Config {
+        allowed_uris: Vec::new(),
+        forbidden_uris: vec!["vehicle_journey:toto"],
+        ..config
+    };
+    let responses = build_and_solve(&model_refs, &config)?;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].first_vj_uri(&model_refs), "toto_bike");
+
+    Ok(())
+}
+
+#[rstest]
+#[case(ComparatorType::Occupancy)]
+#[case(ComparatorType::Basic)]
+fn test_filter_company(
+    #[case] comparator_type: ComparatorType,
+    fixture_model: BaseModel,
+) -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&fixture_model, &real_time_model);
+
+    // every vehicle journey is operated by the default company
+    let config = Config::new("2020-01-01T09:59:00", "A", "C");
+    let config = Config {
+        comparator_type,
+        allowed_uris: vec!["company:default_company"],
+        ..config
+    };
+    let responses = build_and_solve(&model_refs, &config)?;
+    assert_eq!(responses.len(), 1);
+
+    let config = Config {
+        allowed_uris: Vec::new(),
+        forbidden_uris: vec!["company:default_company"],
+        ..config
+    };
+    let responses = build_and_solve(&model_refs, &config)?;
+    assert_eq!(responses.len(), 0);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(ComparatorType::Occupancy)]
+#[case(ComparatorType::Basic)]
+fn test_filter_equipment(
+    #[case] comparator_type: ComparatorType,
+    fixture_model: BaseModel,
+) -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&fixture_model, &real_time_model);
+
+    // With Filter : only the stop points with a wheelchair boarding are allowed
+    let config = Config::new("2020-01-01T09:59:00", "A", "C");
+    let config = Config {
+        comparator_type,
+        allowed_uris: vec!["equipment:wheelchair_boarding"],
+        ..config
+    };
+    let responses = build_and_solve(&model_refs, &config)?;
+    assert_eq!(responses.len(), 1);
+
+    // J has no equipment
+    let config = Config {
+        end: "J".into(),
+        ..config
+    };
+    let responses = build_and_solve(&model_refs, &config)?;
+    assert_eq!(responses.len(), 0);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(ComparatorType::Occupancy)]
+#[case(ComparatorType::Basic)]
+fn test_filter_expression(
+    #[case] comparator_type: ComparatorType,
+    fixture_model: BaseModel,
+) -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&fixture_model, &real_time_model);
+
+    // With Filter : route:R2 and route:R3 are forbidden
+    let config = Config::new("2020-01-01T09:59:00", "A", "J");
+    let config = Config {
+        comparator_type,
+        filter_expression: Some("NOT (route:R2 OR route:R3)"),
+        ..config
+    };
+    let responses = build_and_solve(&model_refs, &config)?;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].first_vj_uri(&model_refs), "toto");
+
+    // With Filter : only the vehicle journeys of route:R1 that accept bikes are allowed
+    let config = Config {
+        end: "C".into(),
+        filter_expression: Some("route:R1 AND vehicle_journey_property:bike_accepted"),
+        ..config
+    };
+    let responses = build_and_solve(&model_refs, &config)?;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].first_vj_uri(&model_refs), "toto_bike");
+
+    // With Filter : stop_point:C is forbidden
+    let config = Config {
+        filter_expression: Some("NOT stop_point:C"),
+        ..config
+    };
+    let responses = build_and_solve(&model_refs, &config)?;
+    assert_eq!(responses.len(), 0);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_invalid_filter_expression(fixture_model: BaseModel) {
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&fixture_model, &real_time_model);
+
+    let is_invalid = |expression_str: &str| {
+        parse_filter_expression(&model_refs, expression_str, "test").is_err()
+    };
+    assert!(!is_invalid("route:R1"));
+    assert!(!is_invalid(
+        "(route:R1 or route:R2) AND NOT network:default_network"
+    ));
+    assert!(!is_invalid("stop_point:A OR (stop_area:sa:B)"));
+
+    assert!(is_invalid(""));
+    assert!(is_invalid("(route:R1"));
+    assert!(is_invalid("route:R1 AND"));
+    assert!(is_invalid("route:R1 route:R2"));
+    assert!(is_invalid("route:R1)"));
+    assert!(is_invalid("route:unknown"));
+    // stop and vehicle filters cannot be combined
+    assert!(is_invalid("route:R1 AND stop_point:C"));
+}
@@ -0,0 +1,191 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    models::base_model::BaseModel, Cancellation, DataTrait, PositiveDuration, SearchTrace,
+    TraceDecision, TraceEventKind, TransitData,
+};
+use loki_launch::{
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::{JourneysSolution, Solver},
+};
+use utils::{make_request_from_config, model_builder::ModelBuilder, Config};
+
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder.st("A", "10:00:00").st("B", "10:05:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder.st("D", "10:10:00").st("C", "10:20:00");
+        })
+        .add_transfer("B", "D", "00:02:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+fn solve(
+    data: &TransitData,
+    model_refs: &ModelRefs<'_>,
+    config: &Config,
+    record_trace: bool,
+) -> Result<JourneysSolution, Error> {
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    solver.set_trace_recording(record_trace);
+    let request_input = make_request_from_config(config);
+    let solution = solver.solve_journey_request_with_cancellation(
+        data,
+        model_refs,
+        &request_input,
+        None,
+        config.datetime_represent,
+        &Cancellation::none(),
+    )?;
+    Ok(solution)
+}
+
+// returns the decisions of the events of `trace` of kind `kind` at `stop`
+fn decisions(trace: &SearchTrace, kind: TraceEventKind, stop: &str) -> Vec<TraceDecision> {
+    trace
+        .departures
+        .iter()
+        .chain(trace.rounds.iter().flat_map(|round| round.events.iter()))
+        .filter(|event| event.kind == kind && event.stop == stop)
+        .map(|event| event.decision)
+        .collect()
+}
+
+#[test]
+fn test_no_trace_by_default() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+    let solution = solve(&data, &model_refs, &config, false)?;
+    assert_eq!(solution.journeys.len(), 1);
+    assert!(solution.trace.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_trace_of_a_journey() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+    let solution = solve(&data, &model_refs, &config, true)?;
+    assert_eq!(solution.journeys.len(), 1);
+
+    let trace = solution.trace.unwrap();
+    assert_eq!(trace.departures.len(), 1);
+    assert_eq!(
+        decisions(&trace, TraceEventKind::Departure, "A"),
+        vec![TraceDecision::Kept]
+    );
+    assert_eq!(trace.rounds[0].round, 0);
+
+    let events: Vec<_> = trace
+        .rounds
+        .iter()
+        .flat_map(|round| round.events.iter())
+        .collect();
+    let board = events
+        .iter()
+        .find(|event| event.kind == TraceEventKind::Board && event.stop == "A")
+        .unwrap();
+    assert_eq!(board.decision, TraceDecision::Kept);
+    assert!(board.trip.as_ref().unwrap().starts_with("first"));
+
+    let transfer = events
+        .iter()
+        .find(|event| event.kind == TraceEventKind::Transfer)
+        .unwrap();
+    assert_eq!(transfer.stop, "B");
+    assert_eq!(transfer.to_stop.as_deref(), Some("D"));
+    assert_eq!(transfer.decision, TraceDecision::Kept);
+
+    assert_eq!(
+        decisions(&trace, TraceEventKind::Arrival, "C"),
+        vec![TraceDecision::Kept]
+    );
+
+    let json = serde_json::to_string(&trace)?;
+    assert!(json.contains(r#""kind":"transfer""#));
+    assert!(json.contains(r#""to_stop":"D""#));
+
+    Ok(())
+}
+
+#[test]
+fn test_trace_explains_missing_journey() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.max_nb_of_legs = 1;
+    let solution = solve(&data, &model_refs, &config, true)?;
+    assert!(solution.journeys.is_empty());
+
+    // the second vehicle cannot be boarded, as it would need a second leg
+    let trace = solution.trace.unwrap();
+    assert_eq!(
+        decisions(&trace, TraceEventKind::Board, "D"),
+        vec![TraceDecision::Invalid]
+    );
+    assert!(decisions(&trace, TraceEventKind::Arrival, "C").is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    models::{base_model::BaseModel, real_time_model::RealTimeModel, ModelRefs},
+    PositiveDuration,
+};
+use utils::{build_and_solve, model_builder::ModelBuilder, Config};
+
+// From A to C, both `early` and `late` reach B in time to board `last`,
+// but `late` stops at D, so it does not belong to the same mission as `early`
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("early", |vj_builder| {
+            vj_builder.st("A", "10:00:00").st("B", "10:10:00");
+        })
+        .vj("late", |vj_builder| {
+            vj_builder
+                .st("A", "10:30:00")
+                .st("D", "10:35:00")
+                .st("B", "10:40:00");
+        })
+        .vj("last", |vj_builder| {
+            vj_builder.st("B", "11:00:00").st("C", "11:30:00");
+        })
+        .add_transfer("B", "B", "00:00:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_tighten_journeys() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    let journeys = build_and_solve(&model_refs, &config)?;
+    assert_eq!(journeys.len(), 1);
+    assert_eq!(journeys[0].first_vj_uri(&model_refs), "early");
+
+    config.request_params.tighten_journeys = true;
+    let journeys = build_and_solve(&model_refs, &config)?;
+    assert_eq!(journeys.len(), 1);
+    assert_eq!(journeys[0].first_vj_uri(&model_refs), "late");
+    assert_eq!(
+        journeys[0].arrival.to_datetime,
+        config.datetime.date().and_hms_opt(11, 30, 0).unwrap()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,308 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    chrono_tz::UTC,
+    filters::{parse_filter, Filters},
+    models::{base_model::BaseModel, VehicleJourneyIdx},
+    robustness::Regularity,
+    Cancellation, DataTrait, NaiveDateTime, PositiveDuration, RealTimeLevel, TransitData,
+};
+use loki_launch::{
+    config::{ComparatorType, EngineType},
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::Solver,
+};
+use utils::{
+    disruption_builder::StopTimesBuilder,
+    make_request_from_config,
+    model_builder::{AsDate, ModelBuilder},
+    Config,
+};
+
+// returns, sorted, for each journey its arrival datetime and the vehicle journeys it uses
+fn solve(
+    data: &TransitData,
+    model_refs: &ModelRefs<'_>,
+    config: &Config,
+    engine_type: EngineType,
+) -> Result<Vec<(NaiveDateTime, Vec<String>)>, Error> {
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    solver.set_engine_type(engine_type);
+    let request_input = make_request_from_config(config);
+
+    let forbidden_filters = config
+        .forbidden_uris
+        .iter()
+        .filter_map(|forbidden_uri| parse_filter(model_refs, forbidden_uri, "test"));
+    let filters = Filters::new(forbidden_filters, std::iter::empty(), false, false);
+
+    let solution = solver.solve_journey_request_with_cancellation(
+        data,
+        model_refs,
+        &request_input,
+        filters,
+        config.datetime_represent,
+        &Cancellation::none(),
+    )?;
+    let mut journeys: Vec<_> = solution
+        .journeys
+        .iter()
+        .map(|journey| {
+            let vehicle_journeys = std::iter::once(&journey.first_vehicle)
+                .chain(journey.connections.iter().map(|(_, _, vehicle)| vehicle))
+                .map(|vehicle| {
+                    model_refs
+                        .vehicle_journey_name(&vehicle.vehicle_journey)
+                        .to_string()
+                })
+                .collect();
+            (journey.last_vehicle_debark_datetime(), vehicle_journeys)
+        })
+        .collect();
+    journeys.sort();
+    Ok(journeys)
+}
+
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:05:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("D", "10:10:00")
+                .st("C", "10:20:00");
+        })
+        .vj("direct", |vj_builder| {
+            vj_builder
+                .route("3")
+                .st("A", "09:55:00")
+                .st("C", "11:00:00");
+        })
+        .vj("through", |vj_builder| {
+            vj_builder
+                .route("4")
+                .st("A", "10:01:00")
+                .st_detailed("E", "10:03:00", "10:03:00", 1, 1, None) // no pickup nor dropoff at E
+                .st("C", "10:30:00");
+        })
+        .add_transfer("B", "D", "00:02:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+fn build_data(base_model: &BaseModel) -> TransitData {
+    let mut data = loki_launch::read::build_transit_data(base_model);
+    data.compute_trip_transfers();
+    data
+}
+
+#[test]
+fn test_trip_based_earliest_arrival_by_nb_of_legs() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data = build_data(model_refs.base);
+    assert!(data.has_trip_transfers());
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.comparator_type = ComparatorType::Basic;
+
+    let trip_based_journeys = solve(&data, &model_refs, &config, EngineType::TripBased)?;
+    assert_eq!(
+        trip_based_journeys,
+        vec![
+            (
+                "2020-01-01T10:20:00".parse()?,
+                vec!["first".to_string(), "second".to_string()]
+            ),
+            ("2020-01-01T10:30:00".parse()?, vec!["through".to_string()]),
+        ]
+    );
+    assert_eq!(
+        trip_based_journeys,
+        solve(&data, &model_refs, &config, EngineType::Raptor)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_trip_based_with_filters() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data = build_data(model_refs.base);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.comparator_type = ComparatorType::Basic;
+    config.forbidden_uris = vec!["route:4"];
+
+    let trip_based_journeys = solve(&data, &model_refs, &config, EngineType::TripBased)?;
+    assert_eq!(trip_based_journeys.len(), 2);
+    assert_eq!(trip_based_journeys[0].1, vec!["first", "second"]);
+    assert_eq!(trip_based_journeys[1].1, vec!["direct"]);
+    assert_eq!(
+        trip_based_journeys,
+        solve(&data, &model_refs, &config, EngineType::Raptor)?
+    );
+
+    config.forbidden_uris = vec!["route:2", "route:4"];
+    let trip_based_journeys = solve(&data, &model_refs, &config, EngineType::TripBased)?;
+    assert_eq!(trip_based_journeys.len(), 1);
+    assert_eq!(trip_based_journeys[0].1, vec!["direct"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_trip_based_after_real_time_update() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let mut real_time_model = RealTimeModel::new();
+    let mut data = build_data(&base_model);
+
+    let date = "2020-01-01".as_date();
+    let vehicle_journey_idx = base_model.vehicle_journey_idx("second").unwrap();
+    data.remove_real_time_vehicle(&VehicleJourneyIdx::Base(vehicle_journey_idx), date)
+        .unwrap();
+    assert!(data.has_trip_transfers());
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.comparator_type = ComparatorType::Basic;
+    config.request_params.real_time_level = RealTimeLevel::RealTime;
+
+    {
+        let model_refs = ModelRefs::new(&base_model, &real_time_model);
+        let trip_based_journeys = solve(&data, &model_refs, &config, EngineType::TripBased)?;
+        assert_eq!(trip_based_journeys.len(), 1);
+        assert_eq!(trip_based_journeys[0].1, vec!["through"]);
+        assert_eq!(
+            trip_based_journeys,
+            solve(&data, &model_refs, &config, EngineType::Raptor)?
+        );
+    }
+
+    // a new vehicle that can be reached from "first" at B
+    {
+        let stop_times = StopTimesBuilder::new()
+            .st("D", "10:08:00")
+            .st("C", "10:15:00")
+            .finalize(&mut real_time_model, &base_model);
+        let new_vj_idx = real_time_model.insert_new_vehicle_journey("new");
+        let stops = stop_times.iter().map(|stop_time| stop_time.stop.clone());
+        let flows = stop_times.iter().map(|stop_time| stop_time.flow_direction);
+        let board_times = stop_times.iter().map(|stop_time| stop_time.board_time);
+        let debark_times = stop_times.iter().map(|stop_time| stop_time.debark_time);
+        data.insert_real_time_vehicle(
+            stops,
+            flows,
+            board_times,
+            debark_times,
+            base_model.occupancy_data(),
+            std::iter::once(date),
+            UTC,
+            VehicleJourneyIdx::New(new_vj_idx),
+            Regularity::Rare,
+            &[],
+        )
+        .unwrap();
+    }
+
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let trip_based_journeys = solve(&data, &model_refs, &config, EngineType::TripBased)?;
+    assert_eq!(trip_based_journeys.len(), 2);
+    assert_eq!(trip_based_journeys[0].1, vec!["first", "new"]);
+    assert_eq!(
+        trip_based_journeys,
+        solve(&data, &model_refs, &config, EngineType::Raptor)?
+    );
+
+    // the base schedule is untouched
+    config.request_params.real_time_level = RealTimeLevel::Base;
+    let trip_based_journeys = solve(&data, &model_refs, &config, EngineType::TripBased)?;
+    assert_eq!(trip_based_journeys.len(), 2);
+    assert_eq!(trip_based_journeys[0].1, vec!["first", "second"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_trip_based_falls_back_to_raptor() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    // trip transfers are not computed
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+    assert!(!data.has_trip_transfers());
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+    assert_eq!(
+        solve(&data, &model_refs, &config, EngineType::TripBased)?,
+        solve(&data, &model_refs, &config, EngineType::Raptor)?
+    );
+
+    // arrival before requests
+    let data = build_data(model_refs.base);
+    let mut config = Config::new("2020-01-01T11:10:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    assert_eq!(
+        solve(&data, &model_refs, &config, EngineType::TripBased)?,
+        solve(&data, &model_refs, &config, EngineType::Raptor)?
+    );
+
+    Ok(())
+}
@@ -100,7 +100,6 @@ fn remove_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -132,7 +131,6 @@ fn remove_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -147,7 +145,6 @@ fn remove_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -216,7 +213,6 @@ fn remove_successive_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -243,7 +239,6 @@ fn remove_successive_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -270,7 +265,6 @@ fn remove_successive_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -297,7 +291,6 @@ fn remove_successive_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -354,7 +347,6 @@ fn remove_middle_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -380,7 +372,6 @@ fn remove_middle_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -406,7 +397,6 @@ fn remove_middle_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -458,7 +448,6 @@ fn modify_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -498,6 +487,7 @@ fn modify_vj() -> Result<(), Error> {
             UTC,
             &vj_idx,
             Regularity::Rare,
+            &[],
         );
         assert!(result.is_ok());
     }
@@ -513,7 +503,6 @@ fn modify_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -532,7 +521,6 @@ fn modify_vj() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -590,7 +578,6 @@ fn modify_vj_with_local_zone() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -636,6 +623,7 @@ fn modify_vj_with_local_zone() -> Result<(), Error> {
             UTC,
             &vj_idx,
             Regularity::Rare,
+            &[],
         );
         assert!(result.is_ok());
     }
@@ -651,7 +639,6 @@ fn modify_vj_with_local_zone() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -680,7 +667,6 @@ fn modify_vj_with_local_zone() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -709,7 +695,6 @@ fn modify_vj_with_local_zone() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -728,7 +713,6 @@ fn modify_vj_with_local_zone() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -790,7 +774,6 @@ fn remove_vj_with_local_zone() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -807,7 +790,6 @@ fn remove_vj_with_local_zone() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -843,7 +825,6 @@ fn remove_vj_with_local_zone() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -874,7 +855,6 @@ fn remove_vj_with_local_zone() -> Result<(), Error> {
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             config.datetime_represent,
         )?;
 
@@ -945,6 +925,7 @@ fn insert_invalid_vj() -> Result<(), Error> {
             UTC,
             vj_idx,
             Regularity::Rare,
+            &[],
         );
         match insert_result {
             Err(InsertionError::InvalidDate(_, _)) => {
@@ -982,6 +963,7 @@ fn insert_invalid_vj() -> Result<(), Error> {
             UTC,
             vj_idx,
             Regularity::Rare,
+            &[],
         );
         match insert_result {
             Err(InsertionError::NoValidDates(_)) => {
@@ -1025,6 +1007,7 @@ fn insert_invalid_vj() -> Result<(), Error> {
             UTC,
             vj_idx,
             Regularity::Rare,
+            &[],
         );
         match insert_result {
             Err(InsertionError::Times(_, _, _, _)) => {
@@ -1055,6 +1038,7 @@ fn insert_invalid_vj() -> Result<(), Error> {
             UTC,
             vj_idx,
             Regularity::Rare,
+            &[],
         );
         match insert_result {
             Err(InsertionError::RealTimeVehicleJourneyAlreadyExistsOnDate(_, _)) => {
@@ -1098,6 +1082,7 @@ fn insert_invalid_vj() -> Result<(), Error> {
             UTC,
             vj_idx.clone(),
             Regularity::Rare,
+            &[],
         );
 
         assert!(insert_result.is_ok());
@@ -1117,6 +1102,7 @@ fn insert_invalid_vj() -> Result<(), Error> {
             UTC,
             vj_idx,
             Regularity::Rare,
+            &[],
         );
 
         match insert_result {
@@ -40,7 +40,8 @@ pub mod model_builder;
 use anyhow::{format_err, Error};
 use loki::{
     chrono::TimeZone,
-    filters::{parse_filter, Filters},
+    filters::{parse_filter, parse_filter_expression, Filters, Preference},
+    isochrone::IsochroneResponse,
     models::ModelRefs,
     InputStop, RealTimeLevel,
 };
@@ -78,6 +79,12 @@ pub struct Config<'a> {
 
     pub forbidden_uris: Vec<&'a str>,
 
+    /// boolean expression over filters, like "NOT (line:1 OR line:2)"
+    pub filter_expression: Option<&'a str>,
+
+    /// uris of the preferred (negative weight) or avoided (positive weight) vehicles
+    pub preferences: Vec<(&'a str, i32)>,
+
     pub wheelchair_accessible: bool,
     pub bike_accessible: bool,
 }
@@ -106,6 +113,8 @@ impl<'a> Config<'a> {
             end: end.into(),
             allowed_uris: Default::default(),
             forbidden_uris: Default::default(),
+            filter_expression: None,
+            preferences: Default::default(),
             bike_accessible: false,
             wheelchair_accessible: false,
         }
@@ -128,12 +137,31 @@ pub fn make_request_from_config(config: &Config) -> RequestInput {
             InputStop::StopPoint(end_stop_point_uri.clone()),
             PositiveDuration::zero(),
         )],
+        via_stops_and_stay_duration: Vec::new(),
         leg_arrival_penalty: config.request_params.leg_arrival_penalty,
         leg_walking_penalty: config.request_params.leg_walking_penalty,
         max_nb_of_legs: config.request_params.max_nb_of_legs,
         max_journey_duration: config.request_params.max_journey_duration,
+        max_walking_duration: config.request_params.max_walking_duration,
+        max_transfer_walking_duration: config.request_params.max_transfer_walking_duration,
+        walking_speed_factor: config.request_params.walking_speed_factor,
+        max_waiting_duration: config.request_params.max_waiting_duration,
+        max_initial_waiting_duration: config.request_params.max_initial_waiting_duration,
         too_late_threshold: config.request_params.too_late_threshold,
         real_time_level: config.request_params.real_time_level,
+        lower_bound_pruning: config.request_params.lower_bound_pruning,
+        pareto_slack_duration: config.request_params.pareto_slack_duration,
+        pareto_slack_percentage: config.request_params.pareto_slack_percentage,
+        tighten_journeys: config.request_params.tighten_journeys,
+        comparator: config.request_params.comparator(config.comparator_type),
+        preferences: config
+            .preferences
+            .iter()
+            .map(|(uri, weight)| Preference {
+                uri: uri.to_string(),
+                weight: *weight,
+            })
+            .collect(),
     }
 }
 
@@ -156,9 +184,15 @@ pub fn build_and_solve(
         .iter()
         .filter_map(|forbidden_uri| parse_filter(model, forbidden_uri, "test"));
 
-    let filters = Filters::new(
+    let has_expression = config
+        .filter_expression
+        .map(|expression_str| parse_filter_expression(model, expression_str, "test"))
+        .transpose()?;
+
+    let filters = Filters::new_with_expression(
         forbidden_filters,
         allowed_filters,
+        has_expression,
         config.wheelchair_accessible,
         config.bike_accessible,
     );
@@ -170,7 +204,6 @@ pub fn build_and_solve(
         model,
         &request_input,
         filters,
-        config.comparator_type,
         config.datetime_represent,
     )?;
     for response in responses.iter() {
@@ -179,6 +212,61 @@ pub fn build_and_solve(
     Ok(responses)
 }
 
+pub fn build_and_solve_range(
+    model: &ModelRefs<'_>,
+    config: &Config,
+    range_duration: PositiveDuration,
+) -> Result<Vec<response::Response>, Error> {
+    use loki::DataTrait;
+    let data: TransitData = loki_launch::read::build_transit_data(model.base);
+
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+
+    let request_input = make_request_from_config(config);
+
+    let responses = solver.solve_range_journey_request(
+        &data,
+        model,
+        &request_input,
+        range_duration,
+        None,
+        config.datetime_represent,
+    )?;
+    for response in responses.iter() {
+        debug!("{}", response.print(model)?);
+    }
+    Ok(responses)
+}
+
+pub fn build_and_solve_isochrone(
+    model: &ModelRefs<'_>,
+    config: &Config,
+) -> Result<Vec<IsochroneResponse>, Error> {
+    use loki::DataTrait;
+    let data: TransitData = loki_launch::read::build_transit_data(model.base);
+
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+
+    let request_input = make_request_from_config(config);
+
+    let responses = solver.solve_isochrone(
+        &data,
+        model,
+        &request_input,
+        None,
+        config.datetime_represent,
+    )?;
+    for response in responses.iter() {
+        debug!(
+            "{} reached at {} with {} legs",
+            model.stop_point_name(&response.stop_point_idx),
+            response.datetime,
+            response.nb_of_legs
+        );
+    }
+    Ok(responses)
+}
+
 pub fn from_to_stop_point_names<'a>(
     vehicle_section: &VehicleSection,
     model: &'a ModelRefs<'a>,
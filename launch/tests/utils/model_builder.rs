@@ -34,11 +34,14 @@
 
 use loki::{
     chrono_tz::{self},
+    rust_decimal::Decimal,
     transit_model::{
         model::Collections,
         objects::{
-            Calendar, CommercialMode, Date, Equipment, Line, Network, PhysicalMode, Properties,
-            Route, StopArea, StopPoint, StopTime, Time, Transfer, ValidityPeriod, VehicleJourney,
+            Calendar, CommercialMode, Date, Equipment, Line, Network, ObjectType, PerimeterAction,
+            PhysicalMode, Properties, RestrictionType, Route, StopArea, StopPoint, StopTime,
+            Ticket, TicketPrice, TicketUse, TicketUsePerimeter, TicketUseRestriction, Time,
+            Transfer, ValidityPeriod, VehicleJourney,
         },
         Model,
     },
@@ -395,6 +398,74 @@ impl<'a> ModelBuilder {
         self
     }
 
+    /// Add a ticket with a single ticket use, whose id is the ticket id.
+    /// The ticket costs `price` (e.g. "1.50") in `currency` during the whole validity period.
+    pub fn ticket<F>(mut self, id: &str, price: &str, currency: &str, mut initer: F) -> Self
+    where
+        F: FnMut(&mut TicketUse),
+    {
+        self.collections
+            .tickets
+            .push(Ticket {
+                id: id.to_owned(),
+                name: id.to_owned(),
+                comment: None,
+            })
+            .unwrap();
+        let mut ticket_use = TicketUse {
+            id: id.to_owned(),
+            ticket_id: id.to_owned(),
+            max_transfers: None,
+            boarding_time_limit: None,
+            alighting_time_limit: None,
+        };
+        initer(&mut ticket_use);
+        self.collections.ticket_uses.push(ticket_use).unwrap();
+        self.collections.ticket_prices.push(TicketPrice {
+            ticket_id: id.to_owned(),
+            price: price.parse::<Decimal>().unwrap(),
+            currency: currency.to_owned(),
+            ticket_validity_start: self.validity_period.start_date,
+            ticket_validity_end: self.validity_period.end_date,
+        });
+        self
+    }
+
+    pub fn ticket_perimeter(
+        mut self,
+        ticket_use_id: &str,
+        object_type: ObjectType,
+        object_id: &str,
+        perimeter_action: PerimeterAction,
+    ) -> Self {
+        self.collections
+            .ticket_use_perimeters
+            .push(TicketUsePerimeter {
+                ticket_use_id: ticket_use_id.to_owned(),
+                object_type,
+                object_id: object_id.to_owned(),
+                perimeter_action,
+            });
+        self
+    }
+
+    pub fn ticket_zone_restriction(
+        mut self,
+        ticket_use_id: &str,
+        origin_zone: &str,
+        destination_zone: &str,
+    ) -> Self {
+        self.collections
+            .ticket_use_restrictions
+            .push(TicketUseRestriction {
+                ticket_use_id: ticket_use_id.to_owned(),
+                restriction_type: RestrictionType::Zone,
+                use_origin: origin_zone.to_owned(),
+                use_destination: destination_zone.to_owned(),
+            });
+        self
+    }
+
     /// Consume the builder to create a navitia model
     pub fn build(self) -> Model {
         Model::new(self.collections).unwrap()
@@ -0,0 +1,317 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    chrono::NaiveDate, models::base_model::BaseModel, BadRequest, DataTrait, InputStop,
+    NaiveDateTime, PositiveDuration, TransitData,
+};
+use loki_launch::{
+    config::EngineType,
+    datetime::DateTimeRepresent,
+    loki::models::{real_time_model::RealTimeModel, ModelRefs},
+    solver::Solver,
+};
+use utils::{make_request_from_config, model_builder::ModelBuilder, Config};
+
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder
+                .route("1")
+                .st("A", "10:00:00")
+                .st("B", "10:10:00")
+                .st("C", "10:20:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder
+                .route("2")
+                .st("B", "10:40:00")
+                .st("C", "10:50:00");
+        })
+        .vj("third", |vj_builder| {
+            vj_builder
+                .route("3")
+                .st("B", "11:00:00")
+                .st("C", "11:10:00");
+        })
+        .vj("fourth", |vj_builder| {
+            vj_builder
+                .route("4")
+                .st("D", "10:30:00")
+                .st("C", "10:45:00");
+        })
+        .add_transfer("B", "B", "00:00:00")
+        .add_transfer("B", "D", "00:05:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+// returns, sorted, for each journey its arrival datetime and the vehicle journeys it uses
+fn solve(
+    data: &TransitData,
+    model_refs: &ModelRefs<'_>,
+    config: &Config,
+    vias: &[(&str, PositiveDuration)],
+    engine_type: EngineType,
+) -> Result<Vec<(NaiveDateTime, Vec<String>)>, Error> {
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    solver.set_engine_type(engine_type);
+    let mut request_input = make_request_from_config(config);
+    request_input.via_stops_and_stay_duration = vias
+        .iter()
+        .map(|(stop, stay_duration)| (InputStop::StopPoint(stop.to_string()), *stay_duration))
+        .collect();
+
+    let journeys = solver.solve_journey_request(
+        data,
+        model_refs,
+        &request_input,
+        None,
+        config.datetime_represent,
+    )?;
+    let mut journeys: Vec<_> = journeys
+        .iter()
+        .map(|journey| {
+            let vehicle_journeys = std::iter::once(&journey.first_vehicle)
+                .chain(journey.connections.iter().map(|(_, _, vehicle)| vehicle))
+                .map(|vehicle| {
+                    model_refs
+                        .vehicle_journey_name(&vehicle.vehicle_journey)
+                        .to_string()
+                })
+                .collect();
+            (journey.last_vehicle_debark_datetime(), vehicle_journeys)
+        })
+        .collect();
+    journeys.sort();
+    Ok(journeys)
+}
+
+fn datetime(hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2020, 1, 1)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
+
+fn minutes(minutes: u32) -> PositiveDuration {
+    PositiveDuration::from_hms(0, minutes, 0)
+}
+
+#[test]
+fn test_stay_at_via() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+    let journeys = solve(&data, &model_refs, &config, &[], EngineType::Raptor)?;
+    assert_eq!(
+        journeys,
+        vec![(datetime(10, 20), vec!["first".to_string()])]
+    );
+
+    // staying 20 minutes at B, we can board "second" at 10:40
+    let journeys = solve(
+        &data,
+        &model_refs,
+        &config,
+        &[("B", minutes(20))],
+        EngineType::Raptor,
+    )?;
+    assert_eq!(
+        journeys,
+        vec![(
+            datetime(10, 50),
+            vec!["first".to_string(), "second".to_string()]
+        )]
+    );
+
+    // staying 40 minutes at B, "second" is missed
+    let journeys = solve(
+        &data,
+        &model_refs,
+        &config,
+        &[("B", minutes(40))],
+        EngineType::Raptor,
+    )?;
+    assert_eq!(
+        journeys,
+        vec![(
+            datetime(11, 10),
+            vec!["first".to_string(), "third".to_string()]
+        )]
+    );
+
+    // the via stops are visited in order
+    let journeys = solve(
+        &data,
+        &model_refs,
+        &config,
+        &[("B", minutes(0)), ("D", minutes(0))],
+        EngineType::Raptor,
+    )?;
+    assert_eq!(
+        journeys,
+        vec![(
+            datetime(10, 45),
+            vec!["first".to_string(), "fourth".to_string()]
+        )]
+    );
+    let journeys = solve(
+        &data,
+        &model_refs,
+        &config,
+        &[("D", minutes(0)), ("B", minutes(0))],
+        EngineType::Raptor,
+    )?;
+    assert!(journeys.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_stay_at_via_arrive_before() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let mut config = Config::new("2020-01-01T11:30:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+
+    // the earliest arrival that stays 20 minutes at B
+    let journeys = solve(
+        &data,
+        &model_refs,
+        &config,
+        &[("B", minutes(20))],
+        EngineType::Raptor,
+    )?;
+    assert_eq!(
+        journeys,
+        vec![(
+            datetime(10, 50),
+            vec!["first".to_string(), "second".to_string()]
+        )]
+    );
+
+    let journeys = solve(
+        &data,
+        &model_refs,
+        &config,
+        &[("B", minutes(40))],
+        EngineType::Raptor,
+    )?;
+    assert_eq!(
+        journeys,
+        vec![(
+            datetime(11, 10),
+            vec!["first".to_string(), "third".to_string()]
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_via_with_other_engines() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    // requests with vias are solved by raptor
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+    let vias = [("B", minutes(20))];
+    let raptor_journeys = solve(&data, &model_refs, &config, &vias, EngineType::Raptor)?;
+    assert_eq!(
+        solve(
+            &data,
+            &model_refs,
+            &config,
+            &vias,
+            EngineType::ConnectionScan
+        )?,
+        raptor_journeys
+    );
+    assert_eq!(
+        solve(&data, &model_refs, &config, &vias, EngineType::TripBased)?,
+        raptor_journeys
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_unknown_via() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    let config = Config::new("2020-01-01T09:50:00", "A", "C");
+    let result = solve(
+        &data,
+        &model_refs,
+        &config,
+        &[("unknown", minutes(10))],
+        EngineType::Raptor,
+    );
+    let error = result.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<BadRequest>(),
+        Some(BadRequest::NoValidViaStop)
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    models::{base_model::BaseModel, real_time_model::RealTimeModel, ModelRefs},
+    DataTrait, PositiveDuration, TransitData,
+};
+use loki_launch::{datetime::DateTimeRepresent, solver::Solver};
+use utils::{build_and_solve, make_request_from_config, model_builder::ModelBuilder, Config};
+
+// From A to C, `first` then `second` departs later and arrives earlier than `direct`,
+// but waits 90 minutes at B
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder.st("A", "10:30:00").st("B", "10:40:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder.st("B", "12:10:00").st("C", "12:20:00");
+        })
+        .vj("direct", |vj_builder| {
+            vj_builder
+                .st("A", "10:00:00")
+                .st("E", "11:00:00")
+                .st("C", "12:30:00");
+        })
+        .add_transfer("B", "B", "00:00:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+fn solve(model_refs: &ModelRefs<'_>, config: &Config) -> Result<Vec<String>, Error> {
+    let journeys = build_and_solve(model_refs, config)?;
+    let mut vehicle_journeys: Vec<String> = journeys
+        .iter()
+        .map(|journey| journey.first_vj_uri(model_refs).to_string())
+        .collect();
+    vehicle_journeys.sort();
+    Ok(vehicle_journeys)
+}
+
+#[test]
+fn test_max_waiting_duration() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct", "first"]);
+
+    config.request_params.max_waiting_duration = PositiveDuration::from_hms(1, 0, 0);
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct"]);
+
+    let mut config = Config::new("2020-01-01T12:40:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct", "first"]);
+
+    config.request_params.max_waiting_duration = PositiveDuration::from_hms(1, 0, 0);
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_max_initial_waiting_duration() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    // `direct` departs 10 minutes after 09:50, and `first` 40 minutes after
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.max_initial_waiting_duration = Some(PositiveDuration::from_hms(0, 45, 0));
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct", "first"]);
+
+    config.request_params.max_initial_waiting_duration = Some(PositiveDuration::from_hms(0, 10, 0));
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct"]);
+
+    config.request_params.max_initial_waiting_duration = Some(PositiveDuration::from_hms(0, 5, 0));
+    assert!(solve(&model_refs, &config)?.is_empty());
+
+    // `direct` arrives 10 minutes before 12:40, and `second` 20 minutes before
+    let mut config = Config::new("2020-01-01T12:40:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    config.request_params.max_initial_waiting_duration = Some(PositiveDuration::from_hms(0, 15, 0));
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_max_initial_waiting_duration_with_fallback() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+    let data: TransitData = loki_launch::read::build_transit_data(model_refs.base);
+
+    // walking 30 minutes from 09:50 reaches A at 10:20,
+    // so `first` is boarded after waiting 10 minutes at A, and `direct` is missed
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.max_initial_waiting_duration = Some(PositiveDuration::from_hms(0, 15, 0));
+    let mut request_input = make_request_from_config(&config);
+    request_input.departures_stop_and_fallback_duration[0].1 = PositiveDuration::from_hms(0, 30, 0);
+
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    let journeys = solver.solve_journey_request(
+        &data,
+        &model_refs,
+        &request_input,
+        None,
+        config.datetime_represent,
+    )?;
+    assert_eq!(journeys.len(), 1);
+    assert_eq!(journeys[0].first_vj_uri(&model_refs), "first");
+
+    // C must be left at 12:25 to walk 15 minutes until 12:40,
+    // so `second` arrives 5 minutes before, and `direct` arrives too late
+    let mut config = Config::new("2020-01-01T12:40:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    config.request_params.max_initial_waiting_duration = Some(PositiveDuration::from_hms(0, 10, 0));
+    let mut request_input = make_request_from_config(&config);
+    request_input.arrivals_stop_and_fallback_duration[0].1 = PositiveDuration::from_hms(0, 15, 0);
+
+    let journeys = solver.solve_journey_request(
+        &data,
+        &model_refs,
+        &request_input,
+        None,
+        config.datetime_represent,
+    )?;
+    assert_eq!(journeys.len(), 1);
+    assert_eq!(journeys[0].first_vj_uri(&model_refs), "first");
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+mod utils;
+use anyhow::Error;
+use loki::{
+    models::{base_model::BaseModel, real_time_model::RealTimeModel, ModelRefs},
+    BadRequest, NaiveDateTime, PositiveDuration,
+};
+use loki_launch::{config::ComparatorType, datetime::DateTimeRepresent};
+use utils::{build_and_solve, model_builder::ModelBuilder, Config};
+
+// From A to C, `first` then `second` with a 10 minutes walk between B and D
+// is faster than `direct`, which does not need any walking
+fn build_model() -> BaseModel {
+    let model = ModelBuilder::new("2020-01-01", "2020-01-02")
+        .vj("first", |vj_builder| {
+            vj_builder.st("A", "10:00:00").st("B", "10:20:00");
+        })
+        .vj("second", |vj_builder| {
+            vj_builder.st("D", "10:35:00").st("C", "10:50:00");
+        })
+        .vj("direct", |vj_builder| {
+            vj_builder
+                .st("A", "10:00:00")
+                .st("E", "10:30:00")
+                .st("C", "11:00:00");
+        })
+        .add_transfer("B", "D", "00:10:00")
+        .build();
+
+    BaseModel::from_transit_model(
+        model,
+        loki::OccupancyData::empty(),
+        PositiveDuration::zero(),
+    )
+    .unwrap()
+}
+
+fn solve(model_refs: &ModelRefs<'_>, config: &Config) -> Result<Vec<String>, Error> {
+    let journeys = build_and_solve(model_refs, config)?;
+    let mut vehicle_journeys: Vec<String> = journeys
+        .iter()
+        .map(|journey| journey.first_vj_uri(model_refs).to_string())
+        .collect();
+    vehicle_journeys.sort();
+    Ok(vehicle_journeys)
+}
+
+#[test]
+fn test_max_transfer_walking_duration() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct", "first"]);
+
+    config.request_params.max_transfer_walking_duration = PositiveDuration::from_hms(0, 5, 0);
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct"]);
+
+    let mut config = Config::new("2020-01-01T11:10:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    config.request_params.max_transfer_walking_duration = PositiveDuration::from_hms(0, 5, 0);
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_max_walking_duration() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.max_walking_duration = PositiveDuration::from_hms(0, 10, 0);
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct", "first"]);
+
+    config.request_params.max_walking_duration = PositiveDuration::from_hms(0, 9, 0);
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct"]);
+
+    let mut config = Config::new("2020-01-01T11:10:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    config.request_params.max_walking_duration = PositiveDuration::from_hms(0, 9, 0);
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_least_walking_comparator() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    // `direct` walks less and uses less legs,
+    // while `first` arrives earlier
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.comparator_type = ComparatorType::LeastWalking;
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct", "first"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_walking_speed_factor() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    // walking twice as slow, the transfer from B to D takes 20 minutes,
+    // and `second` departs before it ends
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.walking_speed_factor = 0.5;
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct"]);
+
+    let mut config = Config::new("2020-01-01T11:10:00", "A", "C");
+    config.datetime_represent = DateTimeRepresent::Arrival;
+    config.request_params.walking_speed_factor = 0.5;
+    assert_eq!(solve(&model_refs, &config)?, vec!["direct"]);
+
+    // walking twice as fast, the transfer takes 5 minutes
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.walking_speed_factor = 2.0;
+    config.request_params.max_transfer_walking_duration = PositiveDuration::from_hms(0, 5, 0);
+    let journeys = build_and_solve(&model_refs, &config)?;
+    let journey = journeys
+        .iter()
+        .find(|journey| journey.first_vj_uri(&model_refs) == "first")
+        .unwrap();
+    let transfer = &journey.connections[0].0;
+    assert_eq!(
+        transfer.to_datetime,
+        "2020-01-01T10:25:00".parse::<NaiveDateTime>()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_bad_walking_speed_factor() -> Result<(), Error> {
+    let _log_guard = loki_launch::logger::init_test_logger();
+
+    let base_model = build_model();
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let mut config = Config::new("2020-01-01T09:50:00", "A", "C");
+    config.request_params.walking_speed_factor = 0.0;
+    let Err(error) = build_and_solve(&model_refs, &config) else {
+        panic!("a walking speed factor of 0 should be rejected");
+    };
+    assert!(matches!(
+        error.downcast_ref::<BadRequest>(),
+        Some(BadRequest::WalkingSpeedFactor)
+    ));
+
+    Ok(())
+}
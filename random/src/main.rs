@@ -1,4 +1,7 @@
-use loki::{tracing::debug, DataTrait};
+use loki::{
+    tracing::{debug, error, info},
+    Cancellation, DataTrait, EngineStats,
+};
 use loki_launch::{
     config,
     loki::{
@@ -52,7 +55,7 @@ pub struct Config {
     pub datetime_represent: DateTimeRepresent,
 
     /// Which comparator to use for the request
-    /// "basic", "robustness" or "occupancy"
+    /// "basic", "robustness", "occupancy", "co2" or "least_walking"
     #[serde(default)]
     pub comparator_type: config::ComparatorType,
 
@@ -63,6 +66,15 @@ pub struct Config {
     /// Seed for random generator
     #[serde(default = "default_seed")]
     pub seed: u64,
+
+    /// Number of threads used to compute the journeys of each query
+    #[serde(default = "default_nb_of_threads")]
+    pub nb_of_threads: usize,
+
+    /// Which engine to use for the request
+    /// "raptor" or "connection_scan"
+    #[serde(default)]
+    pub engine_type: config::EngineType,
 }
 
 pub fn default_nb_of_queries() -> u32 {
@@ -73,6 +85,10 @@ pub fn default_seed() -> u64 {
     0
 }
 
+pub fn default_nb_of_threads() -> usize {
+    1
+}
+
 #[derive(StructOpt)]
 #[structopt(
     name = "loki_random",
@@ -107,6 +123,8 @@ pub fn launch(config: &Config) -> Result<(), Error> {
     let model_refs = ModelRefs::new(&base_model, &real_time_model);
 
     let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    solver.set_nb_of_threads(config.nb_of_threads);
+    solver.set_engine_type(config.engine_type);
 
     let departure_datetime = match &config.departure_datetime {
         Some(string_datetime) => loki_launch::datetime::parse_datetime(string_datetime)?,
@@ -125,6 +143,7 @@ pub fn launch(config: &Config) -> Result<(), Error> {
     let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(config.seed);
 
     let mut histogram = Histogram::<u64>::new(3)?;
+    let mut stats_histograms = StatsHistograms::new()?;
     for _ in 0..nb_queries {
         let start_stop_idx = base_model.stop_points().choose(&mut rng).unwrap();
         let end_stop_idx = base_model.stop_points().choose(&mut rng).unwrap();
@@ -137,15 +156,16 @@ pub fn launch(config: &Config) -> Result<(), Error> {
             start_stop_area_uri,
             end_stop_area_uri,
             &config.request_params,
+            config.comparator_type,
         )?;
         let before_solve = SystemTime::now();
-        let solve_result = solver.solve_journey_request(
+        let solve_result = solver.solve_journey_request_with_cancellation(
             &data,
             &model_refs,
             &request_input,
             None,
-            config.comparator_type,
             datetime_represent,
+            &Cancellation::none(),
         );
         let solve_duration = before_solve.elapsed().unwrap().as_millis();
         let solve_duration_u64: u64 = TryFrom::try_from(solve_duration).unwrap();
@@ -153,29 +173,114 @@ pub fn launch(config: &Config) -> Result<(), Error> {
 
         match solve_result {
             Err(err) => {
-                log::error!("Error while solving request : {:?}", err);
+                error!("Error while solving request : {:?}", err);
             }
-            Ok(responses) => {
-                for response in &responses {
+            Ok(solution) => {
+                for response in &solution.journeys {
                     debug!("{}", response.print(&model_refs)?);
                 }
+                debug!("Engine stats : {}", solution.stats);
+                stats_histograms.record(&solution.stats)?;
             }
         }
     }
     let total_duration = timer::duration_since(start_all);
 
-    log::info!("Total duration : {} ms", total_duration);
-    log::info!("Average duration per request : {} ms", histogram.mean());
-    log::info!("Nb of requests : {}", nb_queries);
-    log::info!("50'th percentile: {}", histogram.value_at_quantile(0.5));
-    log::info!("70'th percentile: {}", histogram.value_at_quantile(0.7));
-    log::info!("90'th percentile: {}", histogram.value_at_quantile(0.9));
-    log::info!("99'th percentile: {}", histogram.value_at_quantile(0.99));
-    log::info!("100'th percentile: {}", histogram.value_at_quantile(1.0));
+    info!("Total duration : {} ms", total_duration);
+    info!("Average duration per request : {} ms", histogram.mean());
+    info!("Nb of requests : {}", nb_queries);
+    info!("50'th percentile: {}", histogram.value_at_quantile(0.5));
+    info!("70'th percentile: {}", histogram.value_at_quantile(0.7));
+    info!("90'th percentile: {}", histogram.value_at_quantile(0.9));
+    info!("99'th percentile: {}", histogram.value_at_quantile(0.99));
+    info!("100'th percentile: {}", histogram.value_at_quantile(1.0));
+
+    stats_histograms.log();
 
     Ok(())
 }
 
+struct StatsHistograms {
+    nb_of_rounds: Histogram<u64>,
+    nb_of_missions_scanned: Histogram<u64>,
+    nb_of_connections_scanned: Histogram<u64>,
+    nb_of_trips_boarded: Histogram<u64>,
+    nb_of_lower_bound_prunings: Histogram<u64>,
+    journeys_tree_size: Histogram<u64>,
+    max_front_size: Histogram<u64>,
+    total_phases_durations: EngineStats,
+}
+
+impl StatsHistograms {
+    fn new() -> Result<Self, Error> {
+        Ok(Self {
+            nb_of_rounds: Histogram::new(3)?,
+            nb_of_missions_scanned: Histogram::new(3)?,
+            nb_of_connections_scanned: Histogram::new(3)?,
+            nb_of_trips_boarded: Histogram::new(3)?,
+            nb_of_lower_bound_prunings: Histogram::new(3)?,
+            journeys_tree_size: Histogram::new(3)?,
+            max_front_size: Histogram::new(3)?,
+            total_phases_durations: EngineStats::default(),
+        })
+    }
+
+    fn record(&mut self, stats: &EngineStats) -> Result<(), Error> {
+        let to_u64 = |value: usize| u64::try_from(value).unwrap_or(u64::MAX);
+        self.nb_of_rounds.record(to_u64(stats.nb_of_rounds))?;
+        self.nb_of_missions_scanned
+            .record(to_u64(stats.nb_of_missions_scanned))?;
+        self.nb_of_connections_scanned
+            .record(to_u64(stats.nb_of_connections_scanned))?;
+        self.nb_of_trips_boarded
+            .record(to_u64(stats.nb_of_trips_boarded))?;
+        self.nb_of_lower_bound_prunings
+            .record(to_u64(stats.nb_of_lower_bound_prunings))?;
+        self.journeys_tree_size
+            .record(to_u64(stats.journeys_tree_size))?;
+        self.max_front_size.record(to_u64(stats.max_front_size()))?;
+
+        let total = &mut self.total_phases_durations;
+        total.init_duration += stats.init_duration;
+        total.ride_duration += stats.ride_duration;
+        total.transfers_duration += stats.transfers_duration;
+        total.fronts_update_duration += stats.fronts_update_duration;
+        total.results_duration += stats.results_duration;
+        Ok(())
+    }
+
+    fn log(&self) {
+        let histograms = [
+            ("rounds", &self.nb_of_rounds),
+            ("missions scanned", &self.nb_of_missions_scanned),
+            ("connections scanned", &self.nb_of_connections_scanned),
+            ("trips boarded", &self.nb_of_trips_boarded),
+            ("lower bound prunings", &self.nb_of_lower_bound_prunings),
+            ("journeys tree size", &self.journeys_tree_size),
+            ("max front size", &self.max_front_size),
+        ];
+        for (name, histogram) in histograms {
+            info!(
+                "Engine {} : mean {:.1}, 50'th percentile {}, 90'th percentile {}, max {}",
+                name,
+                histogram.mean(),
+                histogram.value_at_quantile(0.5),
+                histogram.value_at_quantile(0.9),
+                histogram.max(),
+            );
+        }
+        let total = &self.total_phases_durations;
+        info!(
+            "Engine total time per phase in ms : init {}, ride {}, transfers {}, fronts update {}, results {}",
+            total.init_duration.as_millis(),
+            total.ride_duration.as_millis(),
+            total.transfers_duration.as_millis(),
+            total.fronts_update_duration.as_millis(),
+            total.results_duration.as_millis(),
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -40,6 +40,7 @@ use crate::{
     master_worker::DataAndModels,
     metrics,
     navitia_proto::LocationContext,
+    search_traces::SharedSearchTraces,
     zmq_worker::{RequestMessage, ResponseMessage},
 };
 use anyhow::{format_err, Context, Error};
@@ -54,12 +55,13 @@ use loki_launch::{
             base_model::{PREFIX_ID_STOP_AREA, PREFIX_ID_STOP_POINT},
             ModelRefs,
         },
+        request::comparator::Criterion,
         schedule::{self, ScheduleOn, ScheduleRequestInput},
         tracing::{debug, error, info, trace, warn},
-        DataTrait, InputStop, NaiveDateTime, PositiveDuration, RealTimeLevel, RequestInput,
-        TransitData,
+        Cancellation, ComputeStatus, DataTrait, InputStop, NaiveDateTime, PositiveDuration,
+        RealTimeLevel, RequestInput, TransitData,
     },
-    solver::Solver,
+    solver::{NoJourneyReason, Solver},
     timer,
 };
 use std::{
@@ -70,11 +72,17 @@ use std::{
 };
 use tokio::sync::mpsc;
 
+// the walking speed, in meters per second, that navitia uses by default,
+// and that the transfer durations of the data are assumed to be computed with
+const REFERENCE_WALKING_SPEED: f64 = 1.12;
+
 pub struct ComputeWorker {
     data_and_models: Arc<RwLock<DataAndModels>>,
     solver: Solver,
     worker_id: WorkerId,
+    explain_no_journey: bool,
     default_request_params: config::RequestParams,
+    search_traces: SharedSearchTraces,
     request_channel: mpsc::Receiver<RequestMessage>,
     responses_channel: mpsc::Sender<(WorkerId, ResponseMessage)>,
 }
@@ -83,10 +91,19 @@ impl ComputeWorker {
     pub fn new(
         worker_id: WorkerId,
         data_and_models: Arc<RwLock<DataAndModels>>,
+        nb_threads_per_request: usize,
+        explain_no_journey: bool,
         default_request_params: config::RequestParams,
+        search_traces: SharedSearchTraces,
         responses_channel: mpsc::Sender<(WorkerId, ResponseMessage)>,
     ) -> (Self, mpsc::Sender<RequestMessage>) {
-        let solver = Solver::new(0, 0);
+        let mut solver = Solver::new(0, 0);
+        solver.set_nb_of_threads(nb_threads_per_request);
+        let record_traces = search_traces
+            .lock()
+            .map(|search_traces| search_traces.is_enabled())
+            .unwrap_or(false);
+        solver.set_trace_recording(record_traces);
 
         let (requests_channel_sender, requests_channel_receiver) = mpsc::channel(1);
 
@@ -94,7 +111,9 @@ impl ComputeWorker {
             data_and_models,
             solver,
             worker_id,
+            explain_no_journey,
             default_request_params,
+            search_traces,
             responses_channel,
             request_channel: requests_channel_receiver,
         };
@@ -153,6 +172,7 @@ impl ComputeWorker {
         proto_request: navitia_proto::Request,
     ) -> Result<navitia_proto::Response, Error> {
         check_deadline(&proto_request)?;
+        let cancellation = make_cancellation(&proto_request);
         let request_id = proto_request.request_id.clone().unwrap_or_default();
         let requested_api = proto_request.requested_api();
 
@@ -169,10 +189,19 @@ impl ComputeWorker {
                 let journey_request = proto_request.journeys.ok_or_else(|| {
                     format_err!("request.journey should not be empty for api PtPlanner.")
                 });
-                let result = self.handle_journey_request(journey_request);
+                let result =
+                    self.handle_journey_request(journey_request, &request_id, &cancellation);
                 metrics::observe(metrics::Metric::Journeys, start_request_time);
                 result
             }
+            navitia_proto::Api::Isochrone => {
+                let journey_request = proto_request.journeys.ok_or_else(|| {
+                    format_err!("request.journey should not be empty for api Isochrone.")
+                });
+                let result = self.handle_isochrone_request(journey_request);
+                metrics::observe(metrics::Metric::Isochrone, start_request_time);
+                result
+            }
             navitia_proto::Api::PlacesNearby => {
                 let places_nearby_request = proto_request.places_nearby.ok_or_else(|| {
                     format_err!("request.places_nearby should not be empty for api PlacesNearby.")
@@ -219,6 +248,8 @@ impl ComputeWorker {
     fn handle_journey_request(
         &mut self,
         proto_request: Result<navitia_proto::JourneysRequest, Error>,
+        request_id: &str,
+        cancellation: &Cancellation,
     ) -> Result<navitia_proto::Response, Error> {
         match proto_request {
             Err(err) => {
@@ -245,6 +276,10 @@ impl ComputeWorker {
                             &model_refs,
                             &mut self.solver,
                             &self.default_request_params,
+                            request_id,
+                            &self.search_traces,
+                            self.explain_no_journey,
+                            cancellation,
                         );
                         Ok(make_proto_response(solve_result, &model_refs))
                     }
@@ -255,6 +290,51 @@ impl ComputeWorker {
         }
     }
 
+    fn handle_isochrone_request(
+        &mut self,
+        proto_request: Result<navitia_proto::JourneysRequest, Error>,
+    ) -> Result<navitia_proto::Response, Error> {
+        match proto_request {
+            Err(err) => {
+                // send a response saying that the isochrone request could not be handled
+                warn!("Could not handle isochrone request : {}", err);
+                Ok(make_error_response(&err))
+            }
+            Ok(journey_request) => {
+                let rw_lock_read_guard = self.data_and_models.read().map_err(|err| {
+                    format_err!(
+                        "Compute worker {} failed to acquire read lock on data_and_models. {}",
+                        self.worker_id.id,
+                        err
+                    )
+                })?;
+
+                let data_and_models = rw_lock_read_guard.deref();
+                match data_and_models {
+                    Some((data, base_model, real_time_model)) => {
+                        let model_refs = ModelRefs::new(base_model, real_time_model);
+                        let solve_result = solve_isochrone(
+                            &journey_request,
+                            data,
+                            &model_refs,
+                            &mut self.solver,
+                            &self.default_request_params,
+                        );
+                        match solve_result {
+                            Ok(proto_response) => Ok(proto_response),
+                            Err(err) => {
+                                error!("Error while solving isochrone request : {:?}", err);
+                                Ok(make_error_response(&err))
+                            }
+                        }
+                    }
+                    None => Ok(make_error_response(&format_err!("No data loaded."))),
+                }
+                // RwLock is released
+            }
+        }
+    }
+
     fn handle_places_nearby(
         &mut self,
         proto_request: Result<navitia_proto::PlacesNearbyRequest, Error>,
@@ -384,34 +464,161 @@ impl ComputeWorker {
 }
 
 fn check_deadline(proto_request: &navitia_proto::Request) -> Result<(), Error> {
-    if let Some(deadline_str) = &proto_request.deadline {
-        let datetime_result = NaiveDateTime::parse_from_str(deadline_str, "%Y%m%dT%H%M%S,%f");
-        match datetime_result {
-            Ok(datetime) => {
-                let now = Utc::now().naive_utc();
-                if now > datetime {
-                    return Err(format_err!("Deadline reached."));
-                }
-            }
-            Err(err) => {
-                warn!(
-                    "Could not parse deadline string {}. Error : {}",
-                    deadline_str, err
-                );
-            }
+    if let Some(deadline) = parse_deadline(proto_request) {
+        let now = Utc::now().naive_utc();
+        if now > deadline {
+            return Err(format_err!("Deadline reached."));
         }
     }
     Ok(())
 }
 
+fn parse_deadline(proto_request: &navitia_proto::Request) -> Option<NaiveDateTime> {
+    let deadline_str = proto_request.deadline.as_ref()?;
+    let datetime_result = NaiveDateTime::parse_from_str(deadline_str, "%Y%m%dT%H%M%S,%f");
+    match datetime_result {
+        Ok(datetime) => Some(datetime),
+        Err(err) => {
+            warn!(
+                "Could not parse deadline string {}. Error : {}",
+                deadline_str, err
+            );
+            None
+        }
+    }
+}
+
+// the computation will be interrupted when the deadline of the request is reached
+fn make_cancellation(proto_request: &navitia_proto::Request) -> Cancellation {
+    let has_deadline = parse_deadline(proto_request).and_then(|deadline| {
+        let millis_since_epoch = u64::try_from(deadline.timestamp_millis()).ok()?;
+        Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(millis_since_epoch))
+    });
+    match has_deadline {
+        Some(deadline) => Cancellation::none().with_deadline(deadline),
+        None => Cancellation::none(),
+    }
+}
+
+type JourneysSolveResult = (
+    RequestInput,
+    Vec<loki::response::Response>,
+    ComputeStatus,
+    Vec<NoJourneyReason>,
+);
+
+#[allow(clippy::too_many_arguments)]
 fn solve_journeys(
     journey_request: &navitia_proto::JourneysRequest,
     data: &TransitData,
     model: &ModelRefs<'_>,
     solver: &mut Solver,
     default_request_params: &config::RequestParams,
-) -> Result<(RequestInput, Vec<loki::response::Response>), Error> {
+    request_id: &str,
+    search_traces: &SharedSearchTraces,
+    explain_no_journey: bool,
+    cancellation: &Cancellation,
+) -> Result<JourneysSolveResult, Error> {
     // println!("{:#?}", journey_request);
+    let request_input = make_request_input(journey_request, default_request_params)?;
+
+    let data_filters = make_data_filters(journey_request, model);
+
+    let datetime_represent = make_datetime_represent(journey_request);
+
+    let datetimes = journey_request
+        .datetimes
+        .iter()
+        .map(|timestamp| parse_timestamp(*timestamp))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let min_nb_of_journeys = journey_request
+        .min_nb_journeys
+        .and_then(|nb_of_journeys| usize::try_from(nb_of_journeys).ok())
+        .unwrap_or(0);
+
+    // without a timeframe, more journeys are looked for
+    // within the max journey duration of the first datetime
+    let horizon = journey_request
+        .timeframe_duration
+        .and_then(|seconds_i32| PositiveDuration::try_from(seconds_i32).ok())
+        .unwrap_or(request_input.max_journey_duration);
+
+    let solution = solver.solve_journey_request_at_datetimes(
+        data,
+        model,
+        &request_input,
+        &datetimes,
+        min_nb_of_journeys,
+        horizon,
+        data_filters,
+        datetime_represent,
+        cancellation,
+    )?;
+    metrics::observe_engine_stats(&solution.stats);
+    if let Some(trace) = solution.trace {
+        match search_traces.lock() {
+            Ok(mut search_traces) => search_traces.insert(request_id, trace),
+            Err(err) => error!(
+                "Could not keep the search trace of request '{}' : {}",
+                request_id, err
+            ),
+        }
+    }
+    for response in &solution.journeys {
+        debug!("{}", response.print(model)?);
+    }
+
+    let no_journey_reasons = if explain_no_journey
+        && solution.journeys.is_empty()
+        && solution.status == ComputeStatus::Complete
+    {
+        solver.explain_no_journey(
+            data,
+            model,
+            &request_input,
+            make_data_filters(journey_request, model),
+            datetime_represent,
+        )?
+    } else {
+        Vec::new()
+    };
+
+    Ok((
+        request_input,
+        solution.journeys,
+        solution.status,
+        no_journey_reasons,
+    ))
+}
+
+fn solve_isochrone(
+    journey_request: &navitia_proto::JourneysRequest,
+    data: &TransitData,
+    model: &ModelRefs<'_>,
+    solver: &mut Solver,
+    default_request_params: &config::RequestParams,
+) -> Result<navitia_proto::Response, Error> {
+    let request_input = make_request_input(journey_request, default_request_params)?;
+
+    let data_filters = make_data_filters(journey_request, model);
+
+    let datetime_represent = make_datetime_represent(journey_request);
+
+    let responses = solver.solve_isochrone(
+        data,
+        model,
+        &request_input,
+        data_filters,
+        datetime_represent,
+    )?;
+    response::make_isochrone_proto_response(&request_input, &responses, datetime_represent, model)
+}
+
+fn make_request_input(
+    journey_request: &navitia_proto::JourneysRequest,
+    default_request_params: &config::RequestParams,
+) -> Result<RequestInput, Error> {
     let departures_stop_and_fallback_duration =
         parse_input_stop(journey_request.origin.as_slice(), "departure");
 
@@ -422,11 +629,7 @@ fn solve_journeys(
         .datetimes
         .first()
         .ok_or_else(|| format_err!("No departure datetime provided."))?;
-    let departure_timestamp_i64 = i64::try_from(*departure_timestamp_u64).with_context(|| {
-        format!("The departure datetime {departure_timestamp_u64} cannot be converted to a valid i64 timestamp.")
-    })?;
-    let departure_datetime = loki::NaiveDateTime::from_timestamp_opt(departure_timestamp_i64, 0)
-        .with_context(|| format!("Invalid departure timestamp {}", departure_timestamp_i64))?;
+    let departure_datetime = parse_timestamp(*departure_timestamp_u64)?;
 
     let max_journey_duration = u32::try_from(journey_request.max_duration)
         .map(|duration| PositiveDuration::from_hms(0, 0, duration))
@@ -448,26 +651,6 @@ fn solve_journeys(
         default_request_params.max_nb_of_legs
     });
 
-    let must_be_wheelchair_accessible = journey_request.wheelchair.unwrap_or(false);
-    let must_be_bike_accessible = journey_request.bike_in_pt.unwrap_or(false);
-
-    let forbidden_filters = journey_request
-        .forbidden_uris
-        .iter()
-        .filter_map(|forbidden_uri| parse_filter(model, forbidden_uri, "forbidden_uri[]"));
-
-    let allowed_filters = journey_request
-        .allowed_id
-        .iter()
-        .filter_map(|forbidden_uri| parse_filter(model, forbidden_uri, "forbidden_uri[]"));
-
-    let data_filters = Filters::new(
-        forbidden_filters,
-        allowed_filters,
-        must_be_wheelchair_accessible,
-        must_be_bike_accessible,
-    );
-
     let real_time_level = if journey_request.realtime_level.is_some() {
         match journey_request.realtime_level() {
             navitia_proto::RtLevel::BaseSchedule => RealTimeLevel::Base,
@@ -479,16 +662,6 @@ fn solve_journeys(
         RealTimeLevel::RealTime
     };
 
-    let comparator_type = if journey_request.criteria.is_some() {
-        match journey_request.criteria() {
-            navitia_proto::Criteria::Classic => ComparatorType::Basic,
-            navitia_proto::Criteria::Robustness => ComparatorType::Robustness,
-            navitia_proto::Criteria::Occupancy => ComparatorType::Occupancy,
-        }
-    } else {
-        ComparatorType::Basic
-    };
-
     let leg_arrival_penalty = journey_request
         .arrival_transfer_penalty
         .and_then(|seconds_i32| PositiveDuration::try_from(seconds_i32).ok())
@@ -499,35 +672,104 @@ fn solve_journeys(
         .and_then(|seconds_i32| PositiveDuration::try_from(seconds_i32).ok())
         .unwrap_or(default_request_params.leg_walking_penalty);
 
+    let walking_speed_factor = journey_request
+        .streetnetwork_params
+        .as_ref()
+        .and_then(|street_network_params| street_network_params.walking_speed)
+        .filter(|walking_speed| walking_speed.is_finite() && *walking_speed > 0.0)
+        .map(|walking_speed| walking_speed / REFERENCE_WALKING_SPEED)
+        .unwrap_or(default_request_params.walking_speed_factor);
+
+    // the criteria asked in the request take precedence
+    // over the default ones.
+    // The navitia-proto Criteria has no value for co2, so when the configured
+    // criteria contain co2, it is compared on top of the criteria of the request
+    let comparator = if journey_request.criteria.is_some() {
+        let comparator_type = match journey_request.criteria() {
+            navitia_proto::Criteria::Classic => ComparatorType::Basic,
+            navitia_proto::Criteria::Robustness => ComparatorType::Robustness,
+            navitia_proto::Criteria::Occupancy => ComparatorType::Occupancy,
+        };
+        let configures_co2 = default_request_params
+            .criteria
+            .as_ref()
+            .is_some_and(|comparator| comparator.criteria().contains(&Criterion::Co2));
+        if configures_co2 {
+            comparator_type.comparator().with_criterion(Criterion::Co2)
+        } else {
+            comparator_type.comparator()
+        }
+    } else {
+        default_request_params.comparator(ComparatorType::Basic)
+    };
+
     let request_input = RequestInput {
         datetime: departure_datetime,
         departures_stop_and_fallback_duration,
         arrivals_stop_and_fallback_duration,
+        // the JourneysRequest of navitia-proto has no field for via stops yet,
+        // so vias can only be requested through the library and the stop_areas cli
+        via_stops_and_stay_duration: Vec::new(),
         leg_arrival_penalty,
         leg_walking_penalty,
         max_nb_of_legs,
         max_journey_duration,
+        max_walking_duration: default_request_params.max_walking_duration,
+        max_transfer_walking_duration: default_request_params.max_transfer_walking_duration,
+        walking_speed_factor,
+        max_waiting_duration: default_request_params.max_waiting_duration,
+        max_initial_waiting_duration: default_request_params.max_initial_waiting_duration,
         too_late_threshold: default_request_params.too_late_threshold,
         real_time_level,
+        lower_bound_pruning: default_request_params.lower_bound_pruning,
+        pareto_slack_duration: default_request_params.pareto_slack_duration,
+        pareto_slack_percentage: default_request_params.pareto_slack_percentage,
+        tighten_journeys: default_request_params.tighten_journeys,
+        comparator,
+        // the JourneysRequest of navitia-proto has no field for preferences
+        preferences: Vec::new(),
     };
+    Ok(request_input)
+}
 
-    let datetime_represent = match journey_request.clockwise {
+fn parse_timestamp(timestamp_u64: u64) -> Result<NaiveDateTime, Error> {
+    let timestamp_i64 = i64::try_from(timestamp_u64).with_context(|| {
+        format!("The datetime {timestamp_u64} cannot be converted to a valid i64 timestamp.")
+    })?;
+    NaiveDateTime::from_timestamp_opt(timestamp_i64, 0)
+        .with_context(|| format!("Invalid timestamp {}", timestamp_i64))
+}
+
+fn make_data_filters<'a>(
+    journey_request: &'a navitia_proto::JourneysRequest,
+    model: &ModelRefs<'_>,
+) -> Option<Filters<'a>> {
+    let must_be_wheelchair_accessible = journey_request.wheelchair.unwrap_or(false);
+    let must_be_bike_accessible = journey_request.bike_in_pt.unwrap_or(false);
+
+    let forbidden_filters = journey_request
+        .forbidden_uris
+        .iter()
+        .filter_map(|forbidden_uri| parse_filter(model, forbidden_uri, "forbidden_uri[]"));
+
+    let allowed_filters = journey_request
+        .allowed_id
+        .iter()
+        .filter_map(|forbidden_uri| parse_filter(model, forbidden_uri, "forbidden_uri[]"));
+
+    Filters::new(
+        forbidden_filters,
+        allowed_filters,
+        must_be_wheelchair_accessible,
+        must_be_bike_accessible,
+    )
+}
+
+fn make_datetime_represent(journey_request: &navitia_proto::JourneysRequest) -> DateTimeRepresent {
+    match journey_request.clockwise {
         true => DateTimeRepresent::Departure,
         false => DateTimeRepresent::Arrival,
-    };
-
-    let responses = solver.solve_journey_request(
-        data,
-        model,
-        &request_input,
-        data_filters,
-        comparator_type,
-        datetime_represent,
-    )?;
-    for response in &responses {
-        debug!("{}", response.print(model)?);
     }
-    Ok((request_input, responses))
 }
 
 fn parse_input_stop(
@@ -565,7 +807,7 @@ fn parse_input_stop(
 }
 
 fn make_proto_response(
-    solve_result: Result<(RequestInput, Vec<loki::Response>), Error>,
+    solve_result: Result<JourneysSolveResult, Error>,
     model: &ModelRefs<'_>,
 ) -> navitia_proto::Response {
     match solve_result {
@@ -573,7 +815,29 @@ fn make_proto_response(
             error!("Error while solving request : {:?}", err);
             make_error_response(&err)
         }
-        Ok((request_input, journeys)) => {
+        Ok((request_input, journeys, ComputeStatus::Interrupted, _)) => {
+            metrics::count_interrupted_journeys();
+            let error = format_err!(
+                "Deadline reached during computation. {} partial journeys were found.",
+                journeys.len()
+            );
+            warn!("{}", error);
+            let response_result = response::make_response(&request_input, journeys, model);
+            match response_result {
+                Result::Err(err) => {
+                    error!(
+                        "Error while encoding protobuf response for request : {:?}",
+                        err
+                    );
+                    make_error_response(&error)
+                }
+                Ok(mut resp) => {
+                    resp.error = make_error_response(&error).error;
+                    resp
+                }
+            }
+        }
+        Ok((request_input, journeys, ComputeStatus::Complete, no_journey_reasons)) => {
             let response_result = response::make_response(&request_input, journeys, model);
             match response_result {
                 Result::Err(err) => {
@@ -583,8 +847,11 @@ fn make_proto_response(
                     );
                     make_error_response(&err)
                 }
-                Ok(resp) => {
+                Ok(mut resp) => {
                     // trace!("{:#?}", resp);
+                    if !no_journey_reasons.is_empty() {
+                        resp.error = Some(make_no_solution_error(&no_journey_reasons));
+                    }
                     resp
                 }
             }
@@ -592,6 +859,18 @@ fn make_proto_response(
     }
 }
 
+fn make_no_solution_error(no_journey_reasons: &[NoJourneyReason]) -> navitia_proto::Error {
+    let message = no_journey_reasons
+        .iter()
+        .map(|reason| reason.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut proto_error = navitia_proto::Error::default();
+    proto_error.set_id(navitia_proto::error::ErrorId::NoSolution);
+    proto_error.message = Some(format!("No journey found. {}", message));
+    proto_error
+}
+
 fn make_error_response(error: &Error) -> navitia_proto::Response {
     let mut proto_response = navitia_proto::Response::default();
     proto_response.set_response_type(navitia_proto::ResponseType::NoSolution);
@@ -37,7 +37,10 @@ use hyper::{
 use tokio::sync::{mpsc, oneshot};
 use tracing::{error, info};
 
-use crate::{metrics, server_config::http_params::HttpParams, status_worker::Status};
+use crate::{
+    metrics, search_traces::SharedSearchTraces, server_config::http_params::HttpParams,
+    status_worker::Status,
+};
 
 pub struct HttpToStatusChannel {
     // http worker will send a oneshot::Sender through `status_request_receiver`
@@ -54,11 +57,14 @@ pub struct HttpWorker {
     // on the oneshot::Receiver
     status_request_sender: mpsc::Sender<oneshot::Sender<Status>>,
     shutdown_sender: mpsc::Sender<()>,
+
+    search_traces: SharedSearchTraces,
 }
 
 impl HttpWorker {
     pub fn new(
         http_params: HttpParams,
+        search_traces: SharedSearchTraces,
         shutdown_sender: mpsc::Sender<()>,
     ) -> (Self, HttpToStatusChannel) {
         let (status_request_sender, status_request_receiver) =
@@ -68,6 +74,7 @@ impl HttpWorker {
             http_params,
             status_request_sender,
             shutdown_sender,
+            search_traces,
         };
         let chan = HttpToStatusChannel {
             status_request_receiver,
@@ -103,6 +110,7 @@ impl HttpWorker {
             // Each connection could send multiple requests, so
             // the `Service` needs a clone to handle later requests.
             let status_request_sender = self.status_request_sender.clone();
+            let search_traces = self.search_traces.clone();
 
             async move {
                 // This is the `Service` that will handle the connection.
@@ -113,6 +121,7 @@ impl HttpWorker {
                         http_request,
                         timeout_duration,
                         status_request_sender.clone(),
+                        search_traces.clone(),
                     )
                 }))
             }
@@ -135,6 +144,7 @@ async fn handle_http_request(
     http_request: Request<Body>,
     timeout: tokio::time::Duration,
     status_request_sender: mpsc::Sender<oneshot::Sender<Status>>,
+    search_traces: SharedSearchTraces,
 ) -> Result<Response<Body>, hyper::http::Error> {
     let start_time = SystemTime::now();
     match (http_request.method(), http_request.uri().path()) {
@@ -187,6 +197,27 @@ async fn handle_http_request(
             }
         },
 
+        // GET /trace/<request_id> returns a json containing the search trace
+        //  of the journeys request with id <request_id>,
+        //  and 404 if this trace is not (or no longer) available
+        (&Method::GET, path) if path.starts_with("/trace/") => {
+            let request_id = path.strip_prefix("/trace/").unwrap_or_default();
+            match handle_trace_request(request_id, &search_traces) {
+                Ok(Some(bytes)) => Response::builder()
+                    .status(StatusCode::OK)
+                    .body(Body::from(bytes)),
+                Ok(None) => Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Body::empty()),
+                Err(err) => {
+                    error!("Http /trace request failed : {:#}", err);
+                    Response::builder()
+                        .status(StatusCode::INTERNAL_SERVER_ERROR)
+                        .body(Body::empty())
+                }
+            }
+        }
+
         // Return the 404 Not Found for other routes.
         _ => {
             info!(
@@ -220,6 +251,19 @@ async fn handle_status_request(
     serde_json::to_vec_pretty(&status).context("Could not serialize status to json")
 }
 
+fn handle_trace_request(
+    request_id: &str,
+    search_traces: &SharedSearchTraces,
+) -> Result<Option<Vec<u8>>, Error> {
+    let search_traces = search_traces
+        .lock()
+        .map_err(|err| format_err!("Could not acquire lock on search traces : {}", err))?;
+    search_traces
+        .get(request_id)
+        .map(|trace| serde_json::to_vec_pretty(trace).context("Could not serialize trace to json"))
+        .transpose()
+}
+
 async fn handle_health_request(
     timeout: tokio::time::Duration,
     status_request_sender: mpsc::Sender<oneshot::Sender<Status>>,
@@ -59,6 +59,7 @@ pub mod data_downloader;
 pub mod http_worker;
 pub mod load_balancer;
 pub mod master_worker;
+pub mod search_traces;
 pub mod status_worker;
 pub mod zmq_worker;
 
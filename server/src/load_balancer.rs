@@ -48,6 +48,7 @@ use tokio::{runtime::Builder, sync::mpsc};
 use crate::{
     compute_worker::ComputeWorker,
     master_worker::DataAndModels,
+    search_traces::SharedSearchTraces,
     zmq_worker::{LoadBalancerToZmqChannels, RequestMessage, ResponseMessage},
 };
 
@@ -96,10 +97,14 @@ pub enum LoadBalancerOrder {
 }
 
 impl LoadBalancer {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         data_and_models: Arc<RwLock<DataAndModels>>,
         nb_workers: u16,
+        nb_threads_per_request: u16,
+        explain_no_journey: bool,
         default_request_params: &config::RequestParams,
+        search_traces: SharedSearchTraces,
         zmq_channels: LoadBalancerToZmqChannels,
         shutdown_sender: mpsc::Sender<()>,
     ) -> Result<(Self, LoadBalancerChannels), Error> {
@@ -118,7 +123,10 @@ impl LoadBalancer {
             let (worker, request_channel) = ComputeWorker::new(
                 worker_id,
                 data_and_models.clone(),
+                usize::from(nb_threads_per_request),
+                explain_no_journey,
                 default_request_params.clone(),
+                search_traces.clone(),
                 workers_response_sender.clone(),
             );
             let _thread_handle = builder.spawn(move || worker.run())?;
@@ -40,12 +40,12 @@ use loki_launch::loki::{
     tracing::{error, info},
     TransitData,
 };
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use tokio::{runtime::Builder, signal, sync::mpsc};
 
 use crate::{
     data_worker::DataWorker, http_worker::HttpWorker, load_balancer::LoadBalancer, metrics,
-    status_worker::StatusWorker, zmq_worker::ZmqWorker, ServerConfig,
+    search_traces::SearchTraces, status_worker::StatusWorker, zmq_worker::ZmqWorker, ServerConfig,
 };
 
 pub type DataAndModels = Option<(TransitData, BaseModel, RealTimeModel)>;
@@ -61,6 +61,9 @@ impl MasterWorker {
         // DataWorker will take care of reading data from disk
         let data_and_models = Arc::new(RwLock::new(None));
 
+        // filled by the compute workers, read by the http worker
+        let search_traces = Arc::new(Mutex::new(SearchTraces::new(config.nb_of_search_traces)));
+
         let (shutdown_sender, shutdown_receiver) = mpsc::channel(1);
 
         // Zmq worker
@@ -70,15 +73,21 @@ impl MasterWorker {
         let _zmq_handle = zmq_worker.run_in_a_thread()?;
 
         // http worker
-        let (http_worker, http_to_status_channel) =
-            HttpWorker::new(config.http.clone(), shutdown_sender.clone());
+        let (http_worker, http_to_status_channel) = HttpWorker::new(
+            config.http.clone(),
+            search_traces.clone(),
+            shutdown_sender.clone(),
+        );
         let _http_worker_handle = http_worker.run_in_a_thread()?;
 
         // LoadBalancer
         let (load_balancer, load_balancer_channels) = LoadBalancer::new(
             data_and_models.clone(),
             config.nb_workers,
+            config.nb_threads_per_request,
+            config.explain_no_journey,
             &config.default_request_params,
+            search_traces,
             load_balancer_to_zmq_channels,
             shutdown_sender.clone(),
         )?;
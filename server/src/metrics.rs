@@ -28,12 +28,16 @@
 // https://groups.google.com/d/forum/navitia
 // www.navitia.io
 use anyhow::{bail, Context};
+use loki_launch::loki::EngineStats;
 use std::time::SystemTime;
 use tracing::{error, info};
 
-use prometheus::{self, process_collector::ProcessCollector, Histogram, HistogramOpts, Registry};
+use prometheus::{
+    self, process_collector::ProcessCollector, Histogram, HistogramOpts, IntCounter, Registry,
+};
 
 use lazy_static::lazy_static;
+use num_traits::cast::ToPrimitive;
 
 lazy_static! {
     static ref METRICS: Option<Metrics> = create_metrics();
@@ -42,16 +46,24 @@ lazy_static! {
 struct Metrics {
     registry: Registry,
     journeys_durations: Histogram,
+    isochrone_durations: Histogram,
     places_nearby_durations: Histogram,
     next_departures_arrivals_durations: Histogram,
     zmq_status_durations: Histogram,
     http_status_durations: Histogram,
     reload_durations: Histogram,
     realtime_ingestion_durations: Histogram,
+    interrupted_journeys: IntCounter,
+    engine_rounds: Histogram,
+    engine_missions_scanned: Histogram,
+    engine_trips_boarded: Histogram,
+    engine_journeys_tree_size: Histogram,
+    engine_max_front_size: Histogram,
 }
 
 pub enum Metric {
     Journeys,
+    Isochrone,
     PlacesNearby,
     NextDeparturesArrivals,
     ZmqStatus,
@@ -69,6 +81,7 @@ fn create_metrics() -> Option<Metrics> {
         .map_err(|err| error!("Failed to create prometheus registry {:?}", err))
         .ok()?;
     let journeys_durations = create_journeys_durations_histogram(&registry)?;
+    let isochrone_durations = create_isochrone_durations_histogram(&registry)?;
     let places_nearby_durations = create_places_nearby_durations_histogram(&registry)?;
     let next_departures_arrivals_durations =
         create_next_departures_arrivals_durations_histogram(&registry)?;
@@ -76,6 +89,12 @@ fn create_metrics() -> Option<Metrics> {
     let http_status_durations = create_http_status_histogram(&registry)?;
     let reload_durations = create_reload_histogram(&registry)?;
     let realtime_ingestion_durations = create_realtime_ingestion_histogram(&registry)?;
+    let interrupted_journeys = create_interrupted_journeys_counter(&registry)?;
+    let engine_rounds = create_engine_rounds_histogram(&registry)?;
+    let engine_missions_scanned = create_engine_missions_scanned_histogram(&registry)?;
+    let engine_trips_boarded = create_engine_trips_boarded_histogram(&registry)?;
+    let engine_journeys_tree_size = create_engine_journeys_tree_size_histogram(&registry)?;
+    let engine_max_front_size = create_engine_max_front_size_histogram(&registry)?;
 
     let process_metrics = ProcessCollector::for_self();
     registry
@@ -87,12 +106,19 @@ fn create_metrics() -> Option<Metrics> {
     Some(Metrics {
         registry,
         journeys_durations,
+        isochrone_durations,
         places_nearby_durations,
         next_departures_arrivals_durations,
         zmq_status_durations,
         http_status_durations,
         reload_durations,
         realtime_ingestion_durations,
+        interrupted_journeys,
+        engine_rounds,
+        engine_missions_scanned,
+        engine_trips_boarded,
+        engine_journeys_tree_size,
+        engine_max_front_size,
     })
 }
 
@@ -120,6 +146,13 @@ fn create_journeys_durations_histogram(registry: &Registry) -> Option<Histogram>
     register_histogram(registry, name, help, buckets)
 }
 
+fn create_isochrone_durations_histogram(registry: &Registry) -> Option<Histogram> {
+    let name = "isochrone_durations";
+    let help = "durations (in seconds) for handling isochrone requests";
+    let buckets = vec![0.01, 0.05, 0.1, 0.2, 0.4, 1.0, 5.0];
+    register_histogram(registry, name, help, buckets)
+}
+
 fn create_places_nearby_durations_histogram(registry: &Registry) -> Option<Histogram> {
     let name = "places_nearby_durations";
     let help = "durations (in seconds) for handling places nearby requests";
@@ -162,6 +195,61 @@ fn create_realtime_ingestion_histogram(registry: &Registry) -> Option<Histogram>
     register_histogram(registry, name, help, buckets)
 }
 
+fn create_interrupted_journeys_counter(registry: &Registry) -> Option<IntCounter> {
+    let name = "interrupted_journeys";
+    let help = "number of journeys requests interrupted because their deadline was reached";
+    let counter = IntCounter::new(name, help)
+        .map_err(|err| error!("Failed to create {} counter {:?}", name, err))
+        .ok()?;
+    registry
+        .register(Box::new(counter.clone()))
+        .map_err(|err| error!("Failed to register {} counter {:?}", name, err))
+        .ok()?;
+    Some(counter)
+}
+
+fn create_engine_rounds_histogram(registry: &Registry) -> Option<Histogram> {
+    let name = "engine_rounds";
+    let help = "number of rounds performed by the engine for a journeys request";
+    let buckets = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 8.0, 10.0, 15.0, 20.0];
+    register_histogram(registry, name, help, buckets)
+}
+
+fn create_engine_missions_scanned_histogram(registry: &Registry) -> Option<Histogram> {
+    let name = "engine_missions_scanned";
+    let help = "number of missions scanned by the engine for a journeys request";
+    let buckets = vec![10.0, 100.0, 1_000.0, 5_000.0, 10_000.0, 50_000.0, 100_000.0];
+    register_histogram(registry, name, help, buckets)
+}
+
+fn create_engine_trips_boarded_histogram(registry: &Registry) -> Option<Histogram> {
+    let name = "engine_trips_boarded";
+    let help = "number of trips boarded by the engine for a journeys request";
+    let buckets = vec![10.0, 100.0, 1_000.0, 10_000.0, 100_000.0, 1_000_000.0];
+    register_histogram(registry, name, help, buckets)
+}
+
+fn create_engine_journeys_tree_size_histogram(registry: &Registry) -> Option<Histogram> {
+    let name = "engine_journeys_tree_size";
+    let help = "size of the journeys tree built by the engine for a journeys request";
+    let buckets = vec![
+        100.0,
+        1_000.0,
+        10_000.0,
+        100_000.0,
+        1_000_000.0,
+        10_000_000.0,
+    ];
+    register_histogram(registry, name, help, buckets)
+}
+
+fn create_engine_max_front_size_histogram(registry: &Registry) -> Option<Histogram> {
+    let name = "engine_max_front_size";
+    let help = "size of the largest pareto front built by the engine for a journeys request";
+    let buckets = vec![1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 500.0];
+    register_histogram(registry, name, help, buckets)
+}
+
 pub fn observe(metric: Metric, time: SystemTime) {
     let metrics: &Metrics = match *METRICS {
         Some(ref metrics) => metrics,
@@ -176,6 +264,7 @@ pub fn observe(metric: Metric, time: SystemTime) {
     let duration_f64 = duration.as_secs_f64();
     let histogram = match metric {
         Metric::Journeys => &metrics.journeys_durations,
+        Metric::Isochrone => &metrics.isochrone_durations,
         Metric::PlacesNearby => &metrics.places_nearby_durations,
         Metric::NextDeparturesArrivals => &metrics.next_departures_arrivals_durations,
         Metric::ZmqStatus => &metrics.zmq_status_durations,
@@ -186,6 +275,35 @@ pub fn observe(metric: Metric, time: SystemTime) {
     histogram.observe(duration_f64);
 }
 
+pub fn observe_engine_stats(stats: &EngineStats) {
+    let metrics: &Metrics = match *METRICS {
+        Some(ref metrics) => metrics,
+        None => {
+            return;
+        }
+    };
+    let to_f64 = |value: usize| value.to_f64().unwrap_or(f64::MAX);
+    metrics.engine_rounds.observe(to_f64(stats.nb_of_rounds));
+    metrics
+        .engine_missions_scanned
+        .observe(to_f64(stats.nb_of_missions_scanned));
+    metrics
+        .engine_trips_boarded
+        .observe(to_f64(stats.nb_of_trips_boarded));
+    metrics
+        .engine_journeys_tree_size
+        .observe(to_f64(stats.journeys_tree_size));
+    metrics
+        .engine_max_front_size
+        .observe(to_f64(stats.max_front_size()));
+}
+
+pub fn count_interrupted_journeys() {
+    if let Some(metrics) = &*METRICS {
+        metrics.interrupted_journeys.inc();
+    }
+}
+
 pub fn export_metrics() -> Result<String, anyhow::Error> {
     let metrics: &Metrics = match *METRICS {
         Some(ref metrics) => metrics,
@@ -37,9 +37,11 @@
 use crate::navitia_proto::{self};
 use std::collections::HashSet;
 
+use loki_launch::datetime::DateTimeRepresent;
 use loki_launch::loki::{
     self,
     chrono::NaiveTime,
+    isochrone::IsochroneResponse,
     models::{
         real_time_disruption::{
             chaos_disruption::{
@@ -58,6 +60,7 @@ use loki_launch::loki::{
 };
 
 use loki::{
+    fares::{compute_fare, Cost},
     response::{TransferSection, VehicleSection, WaitingSection},
     tracing::error,
 };
@@ -94,22 +97,82 @@ pub fn make_response(
     journeys: Vec<loki::Response>,
     model: &ModelRefs<'_>,
 ) -> Result<navitia_proto::Response, Error> {
+    let mut tickets = Vec::new();
     let mut proto = navitia_proto::Response {
         journeys: journeys
             .iter()
             .enumerate()
-            .map(|(idx, journey)| make_journey(request_input, journey, idx, model))
+            .map(|(idx, journey)| make_journey(request_input, journey, idx, model, &mut tickets))
             .collect::<Result<Vec<_>, _>>()?,
         feed_publishers: make_feed_publishers(model),
         impacts: make_impacts(&journeys, model),
         ..Default::default()
     };
+    proto.tickets = tickets;
 
     proto.set_response_type(navitia_proto::ResponseType::ItineraryFound);
 
     Ok(proto)
 }
 
+/// Each reached stop is encoded as a journey without section,
+/// whose destination (resp. origin) is the reached stop when
+/// `datetime_represent` is `Departure` (resp. `Arrival`).
+pub fn make_isochrone_proto_response(
+    request_input: &RequestInput,
+    responses: &[IsochroneResponse],
+    datetime_represent: DateTimeRepresent,
+    model: &ModelRefs<'_>,
+) -> Result<navitia_proto::Response, Error> {
+    let mut proto = navitia_proto::Response {
+        journeys: responses
+            .iter()
+            .map(|response| {
+                make_isochrone_journey(request_input, response, datetime_represent, model)
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        feed_publishers: make_feed_publishers(model),
+        ..Default::default()
+    };
+
+    if proto.journeys.is_empty() {
+        proto.set_response_type(navitia_proto::ResponseType::NoSolution);
+    } else {
+        proto.set_response_type(navitia_proto::ResponseType::ItineraryFound);
+    }
+
+    Ok(proto)
+}
+
+fn make_isochrone_journey(
+    request_input: &RequestInput,
+    response: &IsochroneResponse,
+    datetime_represent: DateTimeRepresent,
+    model: &ModelRefs<'_>,
+) -> Result<navitia_proto::Journey, Error> {
+    let requested_datetime = &request_input.datetime;
+    let reached_stop = Some(make_stop_point_pt_object(&response.stop_point_idx, model)?);
+    let (departure_datetime, arrival_datetime, origin, destination) = match datetime_represent {
+        DateTimeRepresent::Departure => {
+            (requested_datetime, &response.datetime, None, reached_stop)
+        }
+        DateTimeRepresent::Arrival => (&response.datetime, requested_datetime, reached_stop, None),
+    };
+    let duration = (*arrival_datetime - *departure_datetime).num_seconds();
+
+    let proto = navitia_proto::Journey {
+        duration: Some(i32::try_from(duration)?),
+        nb_transfers: Some(i32::from(response.nb_of_legs.saturating_sub(1))),
+        departure_date_time: Some(to_u64_timestamp(departure_datetime)?),
+        arrival_date_time: Some(to_u64_timestamp(arrival_datetime)?),
+        requested_date_time: Some(to_u64_timestamp(requested_datetime)?),
+        origin,
+        destination,
+        ..Default::default()
+    };
+    Ok(proto)
+}
+
 fn make_impacts(journeys: &[loki::Response], model: &ModelRefs<'_>) -> Vec<navitia_proto::Impact> {
     let mut chaos_impacts = HashSet::new();
     let mut kirin_disruptions = HashSet::new();
@@ -265,6 +328,7 @@ fn make_journey(
     journey: &loki::Response,
     journey_id: usize,
     model: &ModelRefs<'_>,
+    tickets: &mut Vec<navitia_proto::Ticket>,
 ) -> Result<navitia_proto::Journey, Error> {
     // we have one section for the first vehicle,
     // and then for each connection, the 3 sections : transfer, waiting, vehicle
@@ -330,9 +394,68 @@ fn make_journey(
 
     proto.co2_emission = compute_journey_co2_emission(proto.sections.as_slice());
 
+    if !model.base.fare_data().is_empty() {
+        proto.fare = Some(make_fare(journey, journey_id, model, tickets));
+    }
+
     Ok(proto)
 }
 
+// Tickets are stored in the response, and referenced by their id in each journey fare.
+// The i-th public transport section of a journey has the id `section_{journey_id}_{3*i}`
+// (see make_journey()).
+fn make_fare(
+    journey: &loki::Response,
+    journey_id: usize,
+    model: &ModelRefs<'_>,
+    tickets: &mut Vec<navitia_proto::Ticket>,
+) -> navitia_proto::Fare {
+    let fare = compute_fare(journey, model);
+    let section_ids = |sections: &[usize]| -> Vec<String> {
+        sections
+            .iter()
+            .map(|section_idx| format!("section_{}_{}", journey_id, 3 * section_idx))
+            .collect()
+    };
+    let mut ticket_ids = Vec::with_capacity(fare.tickets.len() + 1);
+    for (ticket_idx, ticket) in fare.tickets.iter().enumerate() {
+        let ticket_id = format!("ticket_{}_{}", journey_id, ticket_idx);
+        tickets.push(navitia_proto::Ticket {
+            id: ticket_id.clone(),
+            name: Some(ticket.ticket_name.clone()),
+            found: Some(true),
+            cost: Some(make_cost(&ticket.cost)),
+            section_id: section_ids(&ticket.sections),
+            source_id: Some(ticket.ticket_id.clone()),
+            ..Default::default()
+        });
+        ticket_ids.push(ticket_id);
+    }
+    if !fare.is_complete() {
+        let ticket_id = format!("unknown_ticket_{}", journey_id);
+        tickets.push(navitia_proto::Ticket {
+            id: ticket_id.clone(),
+            name: Some("unknown ticket".to_string()),
+            found: Some(false),
+            section_id: section_ids(&fare.unpriced_sections),
+            ..Default::default()
+        });
+        ticket_ids.push(ticket_id);
+    }
+    navitia_proto::Fare {
+        total: fare.total().as_ref().map(make_cost),
+        found: Some(fare.is_complete()),
+        ticket_id: ticket_ids,
+    }
+}
+
+fn make_cost(cost: &Cost) -> navitia_proto::Cost {
+    navitia_proto::Cost {
+        value: cost.value.to_f64(),
+        currency: Some(cost.currency.clone()),
+    }
+}
+
 fn make_transfer_section(
     transfer_section: &TransferSection,
     model: &ModelRefs<'_>,
@@ -0,0 +1,81 @@
+// Copyright  (C) 2022, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use loki_launch::loki::SearchTrace;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// The traces of the last journeys requests, identified by their request id.
+///
+/// Filled by the compute workers, and read by the http worker.
+pub type SharedSearchTraces = Arc<Mutex<SearchTraces>>;
+
+pub struct SearchTraces {
+    capacity: usize,
+    // the oldest trace is at the front
+    traces: VecDeque<(String, SearchTrace)>,
+}
+
+impl SearchTraces {
+    /// At most `capacity` traces are kept. With a `capacity` of 0,
+    /// no trace is recorded.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            traces: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Keeps `trace` for the request with id `request_id`,
+    /// and forgets the oldest trace when `capacity` is reached.
+    pub fn insert(&mut self, request_id: &str, trace: SearchTrace) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.traces.retain(|(id, _)| id != request_id);
+        if self.traces.len() >= self.capacity {
+            self.traces.pop_front();
+        }
+        self.traces.push_back((request_id.to_string(), trace));
+    }
+
+    pub fn get(&self, request_id: &str) -> Option<&SearchTrace> {
+        self.traces
+            .iter()
+            .find(|(id, _)| id == request_id)
+            .map(|(_, trace)| trace)
+    }
+}
@@ -80,6 +80,24 @@ pub struct ServerConfig {
     #[serde(default = "default_nb_workers")]
     pub nb_workers: u16,
 
+    /// number of threads used by each worker to solve a journeys request
+    #[serde(default = "default_nb_threads_per_request")]
+    pub nb_threads_per_request: u16,
+
+    /// number of journeys requests whose search trace is kept in memory,
+    /// and can be retreived on the http route `/trace/<request_id>`.
+    /// Recording the traces slows down the journeys requests.
+    /// Defaults to 0, which disables the recording.
+    #[serde(default)]
+    pub nb_of_search_traces: usize,
+
+    /// when a journeys request has no solution, diagnose why
+    /// and give the reasons in the error message of the response.
+    /// The diagnosis solves the request again several times.
+    /// Defaults to false.
+    #[serde(default)]
+    pub explain_no_journey: bool,
+
     // param to load data from either local file or S3
     pub data_source: DataSourceParams,
 
@@ -117,6 +135,9 @@ impl ServerConfig {
             rabbitmq: RabbitMqParams::default(),
             chaos: None,
             nb_workers: default_nb_workers(),
+            nb_threads_per_request: default_nb_threads_per_request(),
+            nb_of_search_traces: 0,
+            explain_no_journey: false,
         }
     }
 
@@ -141,6 +162,16 @@ impl ServerConfig {
 
         let nb_workers = parse_env_var("LOKI_NB_WORKERS", default_nb_workers(), u16::from_str);
 
+        let nb_threads_per_request = parse_env_var(
+            "LOKI_NB_THREADS_PER_REQUEST",
+            default_nb_threads_per_request(),
+            u16::from_str,
+        );
+
+        let nb_of_search_traces = parse_env_var("LOKI_NB_OF_SEARCH_TRACES", 0, usize::from_str);
+
+        let explain_no_journey = parse_env_var("LOKI_EXPLAIN_NO_JOURNEY", false, bool::from_str);
+
         let data_source = DataSourceParams::new_from_env_vars()
             .context("Could not read DataSourceParams from env vars")?;
 
@@ -161,6 +192,9 @@ impl ServerConfig {
             input_data_type,
             default_transfer_duration,
             nb_workers,
+            nb_threads_per_request,
+            nb_of_search_traces,
+            explain_no_journey,
             data_source,
             default_request_params,
             rabbitmq,
@@ -174,6 +208,10 @@ pub fn default_nb_workers() -> u16 {
     1
 }
 
+pub fn default_nb_threads_per_request() -> u16 {
+    1
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -47,6 +47,8 @@ pub struct HttpParams {
     /// will provide two routes
     /// - http://0.0.0.0:3000/status
     /// - http://0.0.0.0:3000/health
+    /// and, when `nb_of_search_traces` is positive,
+    /// - http://0.0.0.0:3000/trace/<request_id>
     #[serde(default = "default_http_address")]
     pub http_address: std::net::SocketAddr,
 
@@ -34,10 +34,20 @@
 // https://groups.google.com/d/forum/navitia
 // www.navitia.io
 
+pub mod cancellation;
+
+pub mod connection_scan;
+
+pub mod engine_stats;
+
 mod journeys_tree;
 
 pub mod multicriteria_raptor;
 
+pub mod search_trace;
+
+pub mod trip_based;
+
 mod pareto_front;
 
 pub mod engine_interface;
@@ -0,0 +1,599 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use std::{collections::HashMap, hash::Hash, time::Instant};
+
+use crate::{
+    engine::{
+        cancellation::{Cancellation, ComputeStatus},
+        engine_interface::{
+            ConnectionLeg, DepartureLeg, Journey, RequestDebug, RequestTypes, RequestWithIters,
+        },
+        engine_stats::EngineStats,
+    },
+    request::generic_request::Criteria,
+    time::{PositiveDuration, SecondsSinceDatasetUTCStart},
+    transit_data::data_interface::DataWithIters,
+    RealTimeLevel,
+};
+use tracing::{debug, trace};
+
+/// Connections are collected and scanned by slices of this duration,
+/// so that the connections departing after the earliest arrival are never collected.
+const SLICE_DURATION: PositiveDuration = PositiveDuration::from_hms(1, 0, 0);
+
+/// Number of connections scanned between two checks of the `Cancellation`.
+const CANCELLATION_CHECK_PERIOD: usize = 10_000;
+
+/// A Connection Scan Algorithm engine, that computes the journey
+/// with the earliest arrival time.
+///
+/// The vehicles are split in elementary connections, i.e. the ride of a trip
+/// from a position to the next one on its mission.
+/// These connections are scanned by increasing departure time, which is
+/// much faster than `MultiCriteriaRaptor` when only the arrival time matters.
+///
+/// The `Request` is used to board, ride, debark, transfer and arrive,
+/// and to check the validity of partial journeys, but only `Criteria::time()`
+/// is used to compare them. So at most one journey is obtained,
+/// and it is not Pareto-optimal with respect to the other criteria.
+/// Stay-in trips are not handled.
+///
+/// The connections are not precomputed with the data : each computation collects
+/// the connections of its slices by looking for the trips departing from every position
+/// of every mission, so its cost grows with the size of the dataset, whatever the request.
+/// On a large dataset, this collection can take most of the computation.
+/// Its duration is reported in `EngineStats::init_duration`.
+pub struct ConnectionScan<T: RequestTypes> {
+    connections: Vec<Connection<T>>,
+
+    trip_ids: HashMap<T::Trip, usize>, // map a `trip` to an index in `onboards`
+    onboards: Vec<Option<Onboard<T>>>, // map a trip id to the criteria onboard this trip
+
+    waits: Vec<Option<Wait<T>>>, // map a `stop` to the best criteria to board at this stop
+    debarks: Vec<Option<T::Criteria>>, // map a `stop` to the best criteria after a debark at this stop
+
+    arrivals: Vec<(usize, T::Arrival)>, // stop id of each arrival
+    is_arrival_stop: Vec<bool>,         // map a `stop` to true if an arrival can be made there
+
+    best_arrival: Option<(T::Arrival, Leg, T::Criteria)>,
+
+    results: Vec<Journey<T>>,
+
+    stats: EngineStats,
+}
+
+// The ride of `trip` from `position` to `next_position`.
+struct Connection<T: RequestTypes> {
+    trip_id: usize,
+    trip: T::Trip,
+    position: T::Position,
+    next_position: T::Position,
+    from_stop_id: usize,
+    to_stop: T::Stop,
+    departure_time: SecondsSinceDatasetUTCStart,
+    arrival_time: SecondsSinceDatasetUTCStart,
+}
+
+// A trip boarded at `board_connection`, and ridden until `debark_connection`.
+#[derive(Debug, Clone, Copy)]
+struct Leg {
+    board_connection: usize,
+    debark_connection: usize,
+}
+
+enum WaitOrigin<T: RequestTypes> {
+    Departure(T::Departure),
+    Transfer(T::Transfer, Leg),
+}
+
+impl<T: RequestTypes> Clone for WaitOrigin<T> {
+    fn clone(&self) -> Self {
+        match self {
+            WaitOrigin::Departure(departure) => WaitOrigin::Departure(departure.clone()),
+            WaitOrigin::Transfer(transfer, leg) => WaitOrigin::Transfer(transfer.clone(), *leg),
+        }
+    }
+}
+
+struct Wait<T: RequestTypes> {
+    criteria: T::Criteria,
+    origin: WaitOrigin<T>,
+}
+
+impl<T: RequestTypes> Clone for Wait<T> {
+    fn clone(&self) -> Self {
+        Self {
+            criteria: self.criteria.clone(),
+            origin: self.origin.clone(),
+        }
+    }
+}
+
+struct Onboard<T: RequestTypes> {
+    // the criteria at the arrival at the last connection scanned
+    criteria: T::Criteria,
+    board_connection: usize,
+    origin: WaitOrigin<T>,
+}
+
+impl<T> ConnectionScan<T>
+where
+    T: RequestTypes<Criteria = Criteria>,
+    T::Trip: Hash + Eq,
+{
+    pub fn new(nb_of_stops: usize) -> Self {
+        let mut engine = Self {
+            connections: Vec::new(),
+            trip_ids: HashMap::new(),
+            onboards: Vec::new(),
+            waits: Vec::new(),
+            debarks: Vec::new(),
+            arrivals: Vec::new(),
+            is_arrival_stop: Vec::new(),
+            best_arrival: None,
+            results: Vec::new(),
+            stats: EngineStats::default(),
+        };
+        engine.resize(nb_of_stops);
+        engine
+    }
+
+    fn resize(&mut self, nb_of_stops: usize) {
+        self.waits.resize(nb_of_stops, None);
+        self.debarks.resize(nb_of_stops, None);
+        self.is_arrival_stop.resize(nb_of_stops, false);
+    }
+
+    fn clear(&mut self) {
+        self.connections.clear();
+        self.trip_ids.clear();
+        self.onboards.clear();
+        self.waits.iter_mut().for_each(|wait| *wait = None);
+        self.debarks.iter_mut().for_each(|debark| *debark = None);
+        self.arrivals.clear();
+        self.is_arrival_stop
+            .iter_mut()
+            .for_each(|is_arrival| *is_arrival = false);
+        self.best_arrival = None;
+        self.results.clear();
+        self.stats = EngineStats::default();
+    }
+
+    /// Computes the journey with the earliest arrival time among the ones
+    /// that depart during the `max_journey_duration` following the earliest departure of `pt`.
+    ///
+    /// `cancellation` is checked periodically. When it is triggered, the search stops,
+    /// `responses()` provides the journey found so far, if any,
+    /// and `ComputeStatus::Interrupted` is returned.
+    pub fn compute<R, Data>(
+        &mut self,
+        pt: &R,
+        data: &Data,
+        real_time_level: RealTimeLevel,
+        max_journey_duration: PositiveDuration,
+        cancellation: &Cancellation,
+    ) -> ComputeStatus
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug,
+        Data: DataWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Transfer = T::Transfer,
+        >,
+    {
+        self.clear();
+        self.resize(pt.nb_of_stops());
+
+        let init_start = Instant::now();
+        let has_first_departure_time = self.init_with_departures_and_arrivals(pt);
+        self.stats.init_duration += init_start.elapsed();
+
+        let mut status = ComputeStatus::Complete;
+        if let Some(first_departure_time) = has_first_departure_time {
+            let max_departure_time = first_departure_time + max_journey_duration;
+            let mut slice_start = first_departure_time;
+            while slice_start <= max_departure_time && !self.is_slice_useless(slice_start) {
+                if cancellation.is_cancelled() {
+                    status = ComputeStatus::Interrupted;
+                    break;
+                }
+                // the end of the slice is included in trips_departing_between()
+                let slice_end = (slice_start + SLICE_DURATION
+                    - PositiveDuration::from_hms(0, 0, 1))
+                .min(max_departure_time);
+
+                let collect_start = Instant::now();
+                let first_new_connection = self.connections.len();
+                self.collect_connections(data, real_time_level, slice_start, slice_end);
+                self.stats.init_duration += collect_start.elapsed();
+
+                let scan_start = Instant::now();
+                let slice_status = self.scan(pt, first_new_connection, cancellation);
+                self.stats.ride_duration += scan_start.elapsed();
+                self.stats.nb_of_rounds += 1;
+                if slice_status == ComputeStatus::Interrupted {
+                    status = ComputeStatus::Interrupted;
+                    break;
+                }
+
+                slice_start = slice_end + PositiveDuration::from_hms(0, 0, 1);
+            }
+        }
+
+        let results_start = Instant::now();
+        self.fill_results();
+        self.stats.results_duration += results_start.elapsed();
+
+        debug!(
+            "Connection scan : {} connections scanned in {} slices",
+            self.stats.nb_of_connections_scanned, self.stats.nb_of_rounds
+        );
+
+        status
+    }
+
+    // Returns the earliest time at which a vehicle can be boarded
+    // at a departure, if any.
+    fn init_with_departures_and_arrivals<R>(
+        &mut self,
+        pt: &R,
+    ) -> Option<SecondsSinceDatasetUTCStart>
+    where
+        R: RequestWithIters<
+            Stop = T::Stop,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug,
+    {
+        let mut first_departure_time = None;
+        for departure in pt.departures() {
+            let (stop, criteria) = pt.depart(&departure);
+            if !pt.is_valid(&criteria) {
+                continue;
+            }
+            trace!(
+                "Departure from {} at {:?}",
+                pt.stop_name(&stop),
+                criteria.time()
+            );
+            let time = criteria.time();
+            let stop_id = pt.stop_id(&stop);
+            let is_better = self.waits[stop_id]
+                .as_ref()
+                .is_none_or(|wait| time < wait.criteria.time());
+            if is_better {
+                self.waits[stop_id] = Some(Wait {
+                    criteria,
+                    origin: WaitOrigin::Departure(departure),
+                });
+            }
+            first_departure_time = Some(match first_departure_time {
+                Some(first_time) if first_time <= time => first_time,
+                _ => time,
+            });
+        }
+
+        for arrival in pt.arrivals() {
+            let stop_id = pt.stop_id(&pt.arrival_stop(&arrival));
+            self.is_arrival_stop[stop_id] = true;
+            self.arrivals.push((stop_id, arrival));
+        }
+
+        first_departure_time
+    }
+
+    // A slice is useless when its connections depart after the best arrival found so far.
+    fn is_slice_useless(&self, slice_start: SecondsSinceDatasetUTCStart) -> bool {
+        self.best_arrival
+            .as_ref()
+            .is_some_and(|(_, _, criteria)| criteria.time() <= slice_start)
+    }
+
+    // Appends to `self.connections` the connections that depart between
+    // `from_time` and `until_time`, sorted by increasing departure time.
+    // This looks for the trips departing from every position of every mission,
+    // so it is the most expensive part of a computation on a large dataset.
+    fn collect_connections<Data>(
+        &mut self,
+        data: &Data,
+        real_time_level: RealTimeLevel,
+        from_time: SecondsSinceDatasetUTCStart,
+        until_time: SecondsSinceDatasetUTCStart,
+    ) where
+        Data: DataWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Transfer = T::Transfer,
+        >,
+    {
+        let first_new_connection = self.connections.len();
+        for mission in data.missions() {
+            let mut position = data.first_on_mission(&mission);
+            while let Some(next_position) = data.next_on_mission(&position, &mission) {
+                let from_stop_id = data.stop_id(&data.stop_of(&position, &mission));
+                let to_stop = data.stop_of(&next_position, &mission);
+                let trips = data.trips_departing_between(
+                    from_time,
+                    until_time,
+                    &mission,
+                    &position,
+                    real_time_level,
+                );
+                for trip in trips {
+                    let nb_of_trips = self.trip_ids.len();
+                    let trip_id = *self.trip_ids.entry(trip.clone()).or_insert(nb_of_trips);
+                    self.connections.push(Connection {
+                        trip_id,
+                        departure_time: data.departure_time_of(&trip, &position),
+                        arrival_time: data.arrival_time_of(&trip, &next_position),
+                        trip,
+                        position: position.clone(),
+                        next_position: next_position.clone(),
+                        from_stop_id,
+                        to_stop: to_stop.clone(),
+                    });
+                }
+                position = next_position;
+            }
+        }
+        self.onboards.resize_with(self.trip_ids.len(), || None);
+
+        // the arrival time is used to break ties, so that a connection with a zero ride duration
+        // is scanned before the connections that can be boarded after it
+        self.connections[first_new_connection..].sort_by(|a, b| {
+            (a.departure_time, a.arrival_time).cmp(&(b.departure_time, b.arrival_time))
+        });
+    }
+
+    fn scan<R>(
+        &mut self,
+        pt: &R,
+        first_connection: usize,
+        cancellation: &Cancellation,
+    ) -> ComputeStatus
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug,
+    {
+        for connection_idx in first_connection..self.connections.len() {
+            let connection = &self.connections[connection_idx];
+            if self.is_slice_useless(connection.departure_time) {
+                break;
+            }
+            let nb_scanned = connection_idx - first_connection;
+            if nb_scanned > 0
+                && nb_scanned.is_multiple_of(CANCELLATION_CHECK_PERIOD)
+                && cancellation.is_cancelled()
+            {
+                return ComputeStatus::Interrupted;
+            }
+            self.stats.nb_of_connections_scanned += 1;
+
+            let trip_id = connection.trip_id;
+            let new_onboard = match &self.onboards[trip_id] {
+                Some(onboard) => {
+                    let criteria =
+                        pt.ride(&connection.trip, &connection.position, &onboard.criteria);
+                    Some(Onboard {
+                        criteria,
+                        board_connection: onboard.board_connection,
+                        origin: onboard.origin.clone(),
+                    })
+                }
+                None => self.waits[connection.from_stop_id]
+                    .as_ref()
+                    .and_then(|wait| {
+                        let criteria = pt.board_and_ride(
+                            &connection.position,
+                            &connection.trip,
+                            &wait.criteria,
+                        )?;
+                        Some(Onboard {
+                            criteria,
+                            board_connection: connection_idx,
+                            origin: wait.origin.clone(),
+                        })
+                    }),
+            };
+            // an invalid ride leaves `self.onboards[trip_id]` untouched,
+            // since the legs debarked earlier from this trip still need its origin
+            let Some(onboard) = new_onboard.filter(|onboard| pt.is_valid(&onboard.criteria)) else {
+                continue;
+            };
+            if self.onboards[trip_id].is_none() {
+                self.stats.nb_of_trips_boarded += 1;
+            }
+
+            let leg = Leg {
+                board_connection: onboard.board_connection,
+                debark_connection: connection_idx,
+            };
+            let has_debark = pt.debark(
+                &connection.trip,
+                &connection.next_position,
+                &onboard.criteria,
+            );
+            let to_stop = connection.to_stop.clone();
+            self.onboards[trip_id] = Some(onboard);
+            if let Some(debark_criteria) = has_debark {
+                self.debark(pt, &to_stop, debark_criteria, leg);
+            }
+        }
+        ComputeStatus::Complete
+    }
+
+    fn debark<R>(&mut self, pt: &R, stop: &T::Stop, criteria: T::Criteria, leg: Leg)
+    where
+        R: RequestWithIters<
+            Stop = T::Stop,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+    {
+        let stop_id = pt.stop_id(stop);
+        let is_better = self.debarks[stop_id]
+            .as_ref()
+            .is_none_or(|old_criteria| criteria.time() < old_criteria.time());
+        if !is_better || !pt.is_valid(&criteria) {
+            return;
+        }
+
+        if self.is_arrival_stop[stop_id] {
+            for (arrival_stop_id, arrival) in &self.arrivals {
+                if *arrival_stop_id != stop_id {
+                    continue;
+                }
+                let Some(arrival_criteria) = pt.arrive(arrival, &criteria) else {
+                    continue;
+                };
+                let is_better = self
+                    .best_arrival
+                    .as_ref()
+                    .is_none_or(|(_, _, best)| arrival_criteria.time() < best.time());
+                if is_better && pt.is_valid(&arrival_criteria) {
+                    self.best_arrival = Some((arrival.clone(), leg, arrival_criteria));
+                }
+            }
+        }
+
+        for (to_stop, transfer_criteria, transfer) in pt.transfers_at(stop, &criteria) {
+            let to_stop_id = pt.stop_id(&to_stop);
+            let is_better = self.waits[to_stop_id]
+                .as_ref()
+                .is_none_or(|wait| transfer_criteria.time() < wait.criteria.time());
+            if is_better && pt.is_valid(&transfer_criteria) {
+                self.waits[to_stop_id] = Some(Wait {
+                    criteria: transfer_criteria,
+                    origin: WaitOrigin::Transfer(transfer, leg),
+                });
+            }
+        }
+
+        self.debarks[stop_id] = Some(criteria);
+    }
+
+    fn fill_results(&mut self) {
+        let Some((arrival, last_leg, criteria_at_arrival)) = self.best_arrival.clone() else {
+            return;
+        };
+
+        let mut connection_legs = Vec::new();
+        let mut leg = last_leg;
+        let departure_leg = loop {
+            let board_connection = &self.connections[leg.board_connection];
+            let debark_connection = &self.connections[leg.debark_connection];
+            let trip = board_connection.trip.clone();
+            let board_position = board_connection.position.clone();
+            let debark_position = debark_connection.next_position.clone();
+            // a trip that has been ridden is always onboard
+            let origin = &self.onboards[board_connection.trip_id]
+                .as_ref()
+                .unwrap()
+                .origin;
+            match origin {
+                WaitOrigin::Departure(departure) => {
+                    break DepartureLeg {
+                        departure: departure.clone(),
+                        trip,
+                        board_position,
+                        debark_position,
+                    };
+                }
+                WaitOrigin::Transfer(transfer, previous_leg) => {
+                    connection_legs.push(ConnectionLeg {
+                        transfer: transfer.clone(),
+                        trip,
+                        board_position,
+                        debark_position,
+                    });
+                    leg = *previous_leg;
+                }
+            }
+        };
+        connection_legs.reverse();
+
+        self.results.push(Journey {
+            departure_leg,
+            connection_legs,
+            arrival,
+            criteria_at_arrival,
+        });
+    }
+
+    pub fn responses(&self) -> impl Iterator<Item = &Journey<T>> {
+        self.results.iter()
+    }
+
+    pub fn nb_of_journeys(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Statistics on the last computation.
+    ///
+    /// `nb_of_rounds` counts the slices of connections that have been scanned.
+    pub fn stats(&self) -> &EngineStats {
+        &self.stats
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    filters::VehiclePenalties,
     models::ModelRefs,
     response,
     transit_data::data_interface::{self, RealTimeLevel},
@@ -19,6 +20,25 @@ pub trait Request: RequestTypes {
     /// Returns `true` if `lower` is better or equivalent to `upper`
     fn is_lower(&self, lower: &Self::Criteria, upper: &Self::Criteria) -> bool;
 
+    /// Returns `true` if `upper` is worse than `lower`, but close enough to `lower`
+    /// to be kept as an alternative.
+    ///
+    /// A `ParetoFront` discards `upper` only when `is_lower(lower, upper)` is `true`
+    /// and `is_within_slack(lower, upper)` is `false`.
+    fn is_within_slack(&self, lower: &Self::Criteria, upper: &Self::Criteria) -> bool;
+
+    /// Returns the key used to sort the elements of a `ParetoFront`.
+    ///
+    /// Must be consistent with `is_lower` : whenever `is_lower(lower, upper)` is `true`,
+    /// `front_key(lower) <= front_key(upper)` must hold. This allows a `ParetoFront`
+    /// to look for dominating (resp. dominated) elements only among the elements
+    /// with a lower (resp. higher) key.
+    ///
+    /// The key must not depend on the datetime of the request, since the fronts
+    /// are kept between the iterations of a range search, which use
+    /// requests with different datetimes.
+    fn front_key(&self, criteria: &Self::Criteria) -> i64;
+
     fn can_be_discarded(
         &self,
         partial_journey_criteria: &Self::Criteria,
@@ -36,6 +56,20 @@ pub trait Request: RequestTypes {
     /// The more `criteria` you can eliminate in this way, the better the engine will perform.
     fn is_valid(&self, criteria: &Self::Criteria) -> bool;
 
+    /// Returns `Some(lower_bound)` where `lower_bound` is lower (in the sense of `is_lower`)
+    /// than the criteria of any journey obtained by extending a partial journey
+    /// that is at `stop` with `criteria`, up to one of the arrivals.
+    ///
+    /// The engine discards `criteria` whenever `lower_bound` is invalid or cannot
+    /// beat the journeys already found.
+    ///
+    /// Returns `None` if no such bound is available.
+    fn lower_bound_at_arrival(
+        &self,
+        stop: &Self::Stop,
+        criteria: &Self::Criteria,
+    ) -> Option<Self::Criteria>;
+
     /// Returns `Some(arrival_criteria)` if `trip` can be boarded
     /// when being at `position` with `waiting_criteria`.
     /// In this case, `arrival_criteria` is the criteria obtained by :
@@ -141,9 +175,12 @@ pub trait Request: RequestTypes {
     /// along with the initial `Criteria`
     fn depart(&self, departure: &Self::Departure) -> (Self::Stop, Self::Criteria);
 
-    /// Returns the criteria obtained after performing `arrival`
-    /// while being at `arrival_stop(arrival)` with `criteria`.
-    fn arrive(&self, arrival: &Self::Arrival, criteria: &Self::Criteria) -> Self::Criteria;
+    /// Returns `Some(arrival_criteria)`, where `arrival_criteria` is the criteria obtained
+    /// after performing `arrival` while being at `arrival_stop(arrival)` with `criteria`.
+    ///
+    /// Returns None if a journey cannot end with `arrival` when being there with `criteria`.
+    /// For example, when the journey did not stay at all the requested via stops.
+    fn arrive(&self, arrival: &Self::Arrival, criteria: &Self::Criteria) -> Option<Self::Criteria>;
 
     /// The stop at which this arrival can be made
     fn arrival_stop(&self, arrival: &Self::Arrival) -> Self::Stop;
@@ -253,12 +290,46 @@ pub struct RequestInput {
     pub datetime: NaiveDateTime,
     pub departures_stop_and_fallback_duration: Vec<(InputStop, PositiveDuration)>,
     pub arrivals_stop_and_fallback_duration: Vec<(InputStop, PositiveDuration)>,
+    /// The stops where the journeys must stay, in the order of the journey,
+    /// along with the minimum duration of each stay.
+    ///
+    /// A stay can happen between two vehicles, after debarking at the via stop
+    /// or walking to it.
+    /// Only handled by `MultiCriteriaRaptor` on journeys requests.
+    pub via_stops_and_stay_duration: Vec<(InputStop, PositiveDuration)>,
     pub leg_arrival_penalty: PositiveDuration,
     pub leg_walking_penalty: PositiveDuration,
     pub max_nb_of_legs: u8,
     pub max_journey_duration: PositiveDuration,
+    /// Maximum walking duration in a journey, fallbacks and transfers included.
+    pub max_walking_duration: PositiveDuration,
+    /// Maximum walking duration of each transfer of a journey.
+    pub max_transfer_walking_duration: PositiveDuration,
+    /// The walking speed of the traveler, relative to the one used to compute
+    /// the transfer durations of the data : with a factor of 0.5, every transfer
+    /// walks twice as long. Must be positive.
+    ///
+    /// The trip transfers used by `TripBased` are computed with the walking speed of the data.
+    pub walking_speed_factor: f64,
+    /// Maximum duration of a wait for a vehicle, after debarking from another one
+    /// (before boarding another one for an arrive before request).
+    pub max_waiting_duration: PositiveDuration,
+    /// Maximum duration between `datetime` and the departure of the first vehicle
+    /// (the arrival of the last vehicle for an arrive before request).
+    /// Not limited when `None`.
+    pub max_initial_waiting_duration: Option<PositiveDuration>,
     pub too_late_threshold: PositiveDuration,
     pub real_time_level: RealTimeLevel,
+    pub lower_bound_pruning: bool,
+    pub pareto_slack_duration: PositiveDuration,
+    pub pareto_slack_percentage: u32,
+    /// After a departure after search, replace each journey by the one that departs
+    /// the latest while arriving at the same time, found by an arrival before search.
+    pub tighten_journeys: bool,
+    pub comparator: Comparator,
+    /// Soft preferences on the lines, networks and modes of the vehicles,
+    /// that penalize or favor the boarding of the matched vehicles.
+    pub preferences: Vec<Preference>,
 }
 
 pub trait RequestIO<'data, 'model, Data: data_interface::Data>: Request {
@@ -266,6 +337,7 @@ pub trait RequestIO<'data, 'model, Data: data_interface::Data>: Request {
         model: &'model ModelRefs<'model>,
         transit_data: &'data Data,
         request_input: &RequestInput,
+        vehicle_penalties: &'data VehiclePenalties,
     ) -> Result<Self, BadRequest>
     where
         Self: Sized;
@@ -325,6 +397,8 @@ pub enum BadRequest {
     RequestedDatetime,
     NoValidDepartureStop,
     NoValidArrivalStop,
+    NoValidViaStop,
+    WalkingSpeedFactor,
 }
 impl std::error::Error for BadRequest {}
 
@@ -332,7 +406,7 @@ use std::fmt::{self, Debug};
 
 use chrono::NaiveDateTime;
 
-use crate::PositiveDuration;
+use crate::{filters::Preference, request::comparator::Comparator, PositiveDuration};
 
 impl fmt::Display for BadRequest {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -347,6 +421,12 @@ impl fmt::Display for BadRequest {
             BadRequest::NoValidArrivalStop => {
                 write!(f, "No valid arrival stop among the provided ones.")
             }
+            BadRequest::NoValidViaStop => {
+                write!(f, "A via stop is not valid.")
+            }
+            BadRequest::WalkingSpeedFactor => {
+                write!(f, "The walking speed factor must be a positive number.")
+            }
         }
     }
 }
@@ -0,0 +1,113 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use std::{fmt, time::Duration};
+
+/// Describes the shape of the search performed by `MultiCriteriaRaptor`
+/// (or `ConnectionScan`) during its last computation.
+#[derive(Debug, Clone, Default)]
+pub struct EngineStats {
+    pub nb_of_rounds: usize,
+    /// Number of missions ridden, summed over all rounds.
+    /// A mission ridden in two different rounds is counted twice.
+    pub nb_of_missions_scanned: usize,
+    /// Number of new boardings added to the journeys tree.
+    pub nb_of_trips_boarded: usize,
+    /// Number of labels discarded thanks to `Request::lower_bound_at_arrival()`.
+    pub nb_of_lower_bound_prunings: usize,
+    /// Number of connections scanned by `ConnectionScan`.
+    pub nb_of_connections_scanned: usize,
+    pub max_wait_front_size: usize,
+    pub max_debark_front_size: usize,
+    pub max_board_front_size: usize,
+    pub arrive_front_size: usize,
+    pub journeys_tree_size: usize,
+
+    /// Time spent to initialize the fronts with departures.
+    pub init_duration: Duration,
+    /// Time spent to ride missions.
+    pub ride_duration: Duration,
+    /// Time spent to perform transfers and arrivals.
+    pub transfers_duration: Duration,
+    /// Time spent to save new fronts and to identify missions to ride.
+    pub fronts_update_duration: Duration,
+    /// Time spent to build the journeys from the journeys tree.
+    pub results_duration: Duration,
+}
+
+impl EngineStats {
+    pub fn max_front_size(&self) -> usize {
+        self.max_wait_front_size
+            .max(self.max_debark_front_size)
+            .max(self.max_board_front_size)
+            .max(self.arrive_front_size)
+    }
+
+    pub fn total_duration(&self) -> Duration {
+        self.init_duration
+            + self.ride_duration
+            + self.transfers_duration
+            + self.fronts_update_duration
+            + self.results_duration
+    }
+}
+
+impl fmt::Display for EngineStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rounds : {}, missions scanned : {}, trips boarded : {}, lower bound prunings : {}, \
+            connections scanned : {}, tree size : {}, \
+            max front sizes (wait/debark/board/arrive) : {}/{}/{}/{}, \
+            durations in ms (init/ride/transfers/fronts/results) : {}/{}/{}/{}/{}",
+            self.nb_of_rounds,
+            self.nb_of_missions_scanned,
+            self.nb_of_trips_boarded,
+            self.nb_of_lower_bound_prunings,
+            self.nb_of_connections_scanned,
+            self.journeys_tree_size,
+            self.max_wait_front_size,
+            self.max_debark_front_size,
+            self.max_board_front_size,
+            self.arrive_front_size,
+            self.init_duration.as_millis(),
+            self.ride_duration.as_millis(),
+            self.transfers_duration.as_millis(),
+            self.fronts_update_duration.as_millis(),
+            self.results_duration.as_millis(),
+        )
+    }
+}
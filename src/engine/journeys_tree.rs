@@ -155,6 +155,15 @@ impl<PT: RequestTypes> JourneysTree<PT> {
         }
     }
 
+    pub fn nb_of_arrives(&self) -> usize {
+        self.arrives.len()
+    }
+
+    /// Returns true if `arrive` was created after the `nb_of_arrives`th call to `arrive()`
+    pub fn is_arrive_created_after(&self, arrive: &Arrive, nb_of_arrives: usize) -> bool {
+        arrive.id >= nb_of_arrives
+    }
+
     pub fn size(&self) -> usize {
         self.waits.len() + self.debarks.len() + self.boards.len() + self.arrives.len()
     }
@@ -34,14 +34,19 @@
 // https://groups.google.com/d/forum/navitia
 // www.navitia.io
 
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Instant};
 
 use crate::engine::{
+    cancellation::{Cancellation, ComputeStatus},
     engine_interface::{Journey, RequestDebug, RequestTypes, RequestWithIters},
+    engine_stats::EngineStats,
     journeys_tree::JourneysTree,
     pareto_front::{ArriveFront, BoardFront, DebarkFront, ParetoFront, WaitFront},
+    search_trace::{self, SearchTrace, TraceDecision, TraceEvent, TraceEventKind},
 };
-use tracing::trace;
+use tracing::{debug, trace};
+
+mod parallel_ride;
 
 pub struct MultiCriteriaRaptor<T: RequestTypes> {
     journeys_tree: JourneysTree<T>,
@@ -64,8 +69,14 @@ pub struct MultiCriteriaRaptor<T: RequestTypes> {
     arrive_front: ArriveFront<T>,
 
     results: Vec<Journey<T>>,
+    nb_of_results: usize,
 
     nb_of_rounds: usize,
+
+    stats: EngineStats,
+
+    // None when the trace recording is disabled
+    trace: Option<SearchTrace>,
 }
 
 impl<T> MultiCriteriaRaptor<T>
@@ -94,13 +105,37 @@ where
             arrive_front: ArriveFront::<T>::new(),
 
             results: Vec::new(),
+            nb_of_results: 0,
 
             nb_of_rounds: 0,
+            stats: EngineStats::default(),
+
+            trace: None,
         }
     }
 
     pub fn nb_of_journeys(&self) -> usize {
-        self.arrive_front.len()
+        self.nb_of_results
+    }
+
+    /// Enables (or disables) the recording of a `SearchTrace` during the next computations.
+    ///
+    /// Recording slows down the search, and while it is enabled,
+    /// `compute_in_parallel()` rides the missions in the calling thread.
+    pub fn set_trace_recording(&mut self, record: bool) {
+        match (record, &self.trace) {
+            (true, None) => self.trace = Some(SearchTrace::default()),
+            (false, Some(_)) => self.trace = None,
+            _ => (),
+        }
+    }
+
+    /// The trace of the last computation, if the recording is enabled.
+    ///
+    /// During a range search, the trace accumulates
+    /// all iterations since `init_range()`.
+    pub fn trace(&self) -> Option<&SearchTrace> {
+        self.trace.as_ref()
     }
 
     fn resize(&mut self, nb_of_stops: usize, nb_of_missions: usize) {
@@ -128,17 +163,253 @@ where
             Transfer = T::Transfer,
         >,
         R: RequestDebug,
+    {
+        self.compute_with_cancellation(pt, &Cancellation::none());
+    }
+
+    /// Same as `compute()`, but `cancellation` is checked between two rounds.
+    /// When `cancellation` is triggered, the search stops and `responses()` provides the
+    /// journeys found so far, and `ComputeStatus::Interrupted` is returned.
+    pub fn compute_with_cancellation<R>(
+        &mut self,
+        pt: &R,
+        cancellation: &Cancellation,
+    ) -> ComputeStatus
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug,
+    {
+        self.compute_with_ride(pt, cancellation, Self::ride)
+    }
+
+    /// Same as `compute_with_cancellation()`, but within each round, the missions to ride
+    /// are split between `nb_of_threads` threads.
+    ///
+    /// The debarks found by each thread are merged in the order of the missions,
+    /// so the journeys obtained do not depend on `nb_of_threads`, and are the same
+    /// as the ones provided by `compute_with_cancellation()`.
+    pub fn compute_in_parallel<R>(
+        &mut self,
+        pt: &R,
+        nb_of_threads: usize,
+        cancellation: &Cancellation,
+    ) -> ComputeStatus
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug + Sync,
+        T::Mission: Sync,
+        T::Position: Send + Sync,
+        T::Stop: Send,
+        T::Trip: Send,
+        T::Criteria: Send + Sync,
+    {
+        if nb_of_threads <= 1 || self.trace.is_some() {
+            return self.compute_with_cancellation(pt, cancellation);
+        }
+        self.compute_with_ride(pt, cancellation, |engine: &mut Self, pt: &R| {
+            engine.ride_in_parallel(pt, nb_of_threads)
+        })
+    }
+
+    fn compute_with_ride<R, Ride>(
+        &mut self,
+        pt: &R,
+        cancellation: &Cancellation,
+        ride: Ride,
+    ) -> ComputeStatus
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug,
+        Ride: Fn(&mut Self, &R),
     {
         self.clear();
         self.resize(pt.nb_of_stops(), pt.nb_of_missions());
 
+        debug_assert!(self.journeys_tree.is_empty());
+        let init_start = Instant::now();
         self.init_with_departures(pt);
 
         self.identify_missions_with_new_waits(pt);
+        self.stats.init_duration += init_start.elapsed();
 
         debug_assert!(!self.missions_with_new_wait.is_empty());
 
+        let status = self.perform_rounds(pt, cancellation, ride);
+
+        self.fill_results(0);
+
+        self.update_stats_at_end_of_compute();
+
+        status
+    }
+
+    /// Prepare the engine for a range search, i.e. a sequence of calls
+    /// to `compute_range_iteration()` with requests that differ only by their datetime.
+    pub fn init_range(&mut self) {
+        self.clear();
+    }
+
+    /// Performs one iteration of a range (rRAPTOR-like) search.
+    ///
+    /// Contrary to `compute()`, the fronts obtained by the previous iterations
+    /// are not cleared. Hence the requests must be provided from the "hardest"
+    /// to the "easiest" one, i.e. by decreasing departure datetime for a depart after request,
+    /// and by increasing arrival datetime for an arrive before request.
+    /// This way, the labels found by previous iterations are still valid and
+    /// can be used to prune the search.
+    ///
+    /// After this call, `responses()` provides only the journeys found
+    /// during this iteration.
+    ///
+    /// As in `compute_with_cancellation()`, `cancellation` is checked between two rounds,
+    /// and `ComputeStatus::Interrupted` is returned when it is triggered.
+    pub fn compute_range_iteration<R>(
+        &mut self,
+        pt: &R,
+        cancellation: &Cancellation,
+    ) -> ComputeStatus
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug,
+    {
+        self.resize(pt.nb_of_stops(), pt.nb_of_missions());
+        self.nb_of_rounds = 0;
+
+        let first_new_arrive = self.journeys_tree.nb_of_arrives();
+
+        let init_start = Instant::now();
+        self.init_with_departures(pt);
+
+        self.identify_missions_with_new_waits(pt);
+        self.stats.init_duration += init_start.elapsed();
+
+        let status = self.perform_rounds(pt, cancellation, Self::ride);
+
+        self.fill_results(first_new_arrive);
+
+        self.update_stats_at_end_of_compute();
+
+        status
+    }
+
+    /// Explores every stop reachable from the departures of `pt`.
+    ///
+    /// No journey is built : the arrivals of `pt` are not used
+    /// to prune the search, and `responses()` will be empty after this call.
+    /// Use `criteria_at_stop()` to obtain the labels found at each stop.
+    pub fn compute_one_to_all<R>(&mut self, pt: &R)
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug,
+    {
+        self.clear();
+        self.resize(pt.nb_of_stops(), pt.nb_of_missions());
+
+        let init_start = Instant::now();
+        self.init_with_departures(pt);
+
+        self.identify_missions_with_new_waits(pt);
+        self.stats.init_duration += init_start.elapsed();
+
+        self.perform_rounds(pt, &Cancellation::none(), Self::ride);
+
+        self.update_stats_at_end_of_compute();
+    }
+
+    /// Returns the criteria of all labels obtained at the stop with id `stop_id`,
+    /// either upon debarking from a vehicle, or upon waiting at the stop
+    /// after a departure or a transfer.
+    pub fn criteria_at_stop(&self, stop_id: usize) -> impl Iterator<Item = &T::Criteria> {
+        let debarks = self
+            .debark_fronts
+            .get(stop_id)
+            .into_iter()
+            .flat_map(|front| front.iter().map(|(_, criteria)| criteria));
+        let waits = self
+            .wait_fronts
+            .get(stop_id)
+            .into_iter()
+            .flat_map(|front| front.iter().map(|(_, criteria)| criteria));
+        debarks.chain(waits)
+    }
+
+    fn perform_rounds<R, Ride>(
+        &mut self,
+        pt: &R,
+        cancellation: &Cancellation,
+        ride: Ride,
+    ) -> ComputeStatus
+    where
+        Ride: Fn(&mut Self, &R),
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug,
+    {
+        let mut status = ComputeStatus::Complete;
         while !self.missions_with_new_wait.is_empty() {
+            if cancellation.is_cancelled() {
+                debug!(
+                    "Computation interrupted after {} rounds. Tree size {}, arrived {}",
+                    self.nb_of_rounds,
+                    self.tree_size(),
+                    self.arrive_front.len()
+                );
+                status = ComputeStatus::Interrupted;
+                break;
+            }
             let nb_new_wait: usize = self.new_wait_fronts.iter().map(ParetoFront::len).sum();
             trace!(
                 "Round {}, nb of missions {}, new_wait {}",
@@ -152,20 +423,45 @@ where
                 self.arrive_front.len()
             );
 
+            self.stats.nb_of_missions_scanned += self.missions_with_new_wait.len();
+
+            if let Some(trace) = &mut self.trace {
+                trace.start_round(self.nb_of_rounds);
+            }
+
+            let fronts_update_start = Instant::now();
             self.save_and_clear_new_debarks(pt);
+            self.stats.fronts_update_duration += fronts_update_start.elapsed();
 
-            self.ride(pt);
+            let ride_start = Instant::now();
+            ride(self, pt);
+            self.stats.ride_duration += ride_start.elapsed();
 
+            let fronts_update_start = Instant::now();
             self.save_and_clear_new_waits(pt);
+            self.stats.fronts_update_duration += fronts_update_start.elapsed();
 
+            let transfers_start = Instant::now();
             self.perform_transfers_and_arrivals(pt);
+            self.stats.transfers_duration += transfers_start.elapsed();
 
+            let fronts_update_start = Instant::now();
             self.identify_missions_with_new_waits(pt);
+            self.stats.fronts_update_duration += fronts_update_start.elapsed();
 
             self.nb_of_rounds += 1;
+            self.stats.nb_of_rounds += 1;
         }
 
-        self.fill_results();
+        // the debarks of the last round are kept, so that they
+        // can be used by a subsequent range iteration
+        self.save_and_clear_new_debarks(pt);
+
+        if let Some(trace) = &mut self.trace {
+            trace.end_rounds();
+        }
+
+        status
     }
 
     fn clear(&mut self) {
@@ -200,8 +496,24 @@ where
 
         // we don't clear self.results so as to not release the memory
         // allocated for connections in a Journey
+        self.nb_of_results = 0;
 
         self.nb_of_rounds = 0;
+
+        self.stats = EngineStats::default();
+
+        if let Some(trace) = &mut self.trace {
+            trace.clear();
+        }
+    }
+
+    fn update_stats_at_end_of_compute(&mut self) {
+        let max_wait_front_size = self.wait_fronts.iter().map(ParetoFront::len).max();
+        let max_debark_front_size = self.debark_fronts.iter().map(ParetoFront::len).max();
+        self.stats.max_wait_front_size = max_wait_front_size.unwrap_or(0);
+        self.stats.max_debark_front_size = max_debark_front_size.unwrap_or(0);
+        self.stats.arrive_front_size = self.arrive_front.len();
+        self.stats.journeys_tree_size = self.journeys_tree.size();
     }
 
     // fill new_waiting_fronts with journeys departures
@@ -219,7 +531,6 @@ where
         >,
         R: RequestDebug,
     {
-        debug_assert!(self.journeys_tree.is_empty());
         debug_assert!(self
             .new_wait_fronts
             .iter()
@@ -230,14 +541,44 @@ where
         // TODO : check that all departure stops are distincts
         for departure in pt.departures() {
             let (stop, criteria) = pt.depart(&departure);
-            let journey = self.journeys_tree.depart(&departure);
             let stop_id = pt.stop_id(&stop);
 
+            // during a range search, the wait fronts contains the waits
+            // obtained in previous iterations
+            let wait_front = &mut self.wait_fronts[stop_id];
+            if wait_front.dominates(&criteria, pt) {
+                search_trace::record(&mut self.trace, || {
+                    TraceEvent::new(
+                        TraceEventKind::Departure,
+                        pt.stop_name(&stop),
+                        &criteria,
+                        TraceDecision::DominatedByFront,
+                    )
+                });
+                continue;
+            }
+            wait_front.remove_elements_dominated_by(&criteria, pt);
+
+            let journey = self.journeys_tree.depart(&departure);
             let new_wait_front = &mut self.new_wait_fronts[stop_id];
             if new_wait_front.is_empty() {
                 self.stops_with_new_wait.push(stop.clone());
             }
 
+            search_trace::record(&mut self.trace, || {
+                let decision = if new_wait_front.dominates(&criteria, pt) {
+                    TraceDecision::DominatedByFront
+                } else {
+                    TraceDecision::Kept
+                };
+                TraceEvent::new(
+                    TraceEventKind::Departure,
+                    pt.stop_name(&stop),
+                    &criteria,
+                    decision,
+                )
+            });
+
             new_wait_front.add(journey, criteria, pt);
             trace!("Adding departure at {}", pt.stop_name(&stop));
         }
@@ -357,14 +698,31 @@ where
                     for ((ref board, ref trip), ref board_criteria) in self.board_front.iter() {
                         let has_new_debark_criteria = pt.debark(trip, &position, board_criteria);
                         if let Some(new_debark_criteria) = has_new_debark_criteria {
-                            if debark_front.dominates(&new_debark_criteria, pt) {
-                                continue;
-                            }
-                            if new_debark_front.dominates(&new_debark_criteria, pt) {
+                            if debark_front.dominates(&new_debark_criteria, pt)
+                                || new_debark_front.dominates(&new_debark_criteria, pt)
+                            {
+                                search_trace::record(&mut self.trace, || {
+                                    TraceEvent::new(
+                                        TraceEventKind::Debark,
+                                        pt.stop_name(&stop),
+                                        &new_debark_criteria,
+                                        TraceDecision::DominatedByFront,
+                                    )
+                                    .with_trip(pt.trip_name(trip))
+                                });
                                 continue;
                             }
                             let new_debark = self.journeys_tree.debark(*board, &position);
                             debark_front.remove_elements_dominated_by(&new_debark_criteria, pt);
+                            search_trace::record(&mut self.trace, || {
+                                TraceEvent::new(
+                                    TraceEventKind::Debark,
+                                    pt.stop_name(&stop),
+                                    &new_debark_criteria,
+                                    TraceDecision::Kept,
+                                )
+                                .with_trip(pt.trip_name(trip))
+                            });
                             new_debark_front.add_and_remove_elements_dominated(
                                 new_debark,
                                 new_debark_criteria,
@@ -391,9 +749,10 @@ where
 
                 // if there is no next stop on the route
                 // there is no need to the update onboard front
-                if has_position.is_none() {
-                    continue;
-                }
+                let next_stop = match &has_position {
+                    Some(next_position) => pt.stop_of(next_position, mission),
+                    None => continue,
+                };
 
                 // board and ride new waitings and put them into new_onboard_front
                 {
@@ -401,29 +760,40 @@ where
                     let new_wait_front = &self.new_wait_fronts[stop_id];
                     for (ref wait, ref wait_criteria) in new_wait_front.iter() {
                         // trace!("Trying to board");
-                        if let Some((trip, new_board_criteria)) =
-                            pt.best_trip_to_board(&position, mission, wait_criteria)
-                        {
+                        let has_board = pt.best_trip_to_board(&position, mission, wait_criteria);
+                        if has_board.is_none() {
+                            search_trace::record(&mut self.trace, || {
+                                TraceEvent::new(
+                                    TraceEventKind::Board,
+                                    pt.stop_name(&stop),
+                                    wait_criteria,
+                                    TraceDecision::NoTripToBoard,
+                                )
+                            });
+                        }
+                        if let Some((trip, new_board_criteria)) = has_board {
                             // trace!("Try to board at stop {} from {:?} into mission {}", pt.position_name(&position, mission), wait, pt.mission_name(&mission));
                             // trace!("New Board : \n{:#?} \n {:#?}", trip, new_board_criteria);
-                            if !pt.is_valid(&new_board_criteria) {
-                                // trace!("    invalid");
-                                continue;
-                            }
-                            if self.arrive_front.dominates(&new_board_criteria, pt) {
-                                // trace!("    arrive is better");
-                                continue;
-                            }
-                            if self.new_board_front.dominates(&new_board_criteria, pt) {
-                                // trace!("    new_board_front is better");
-                                continue;
-                            }
-                            if self.can_be_discarded(&new_board_criteria, pt) {
-                                // trace!("    new_board_front is better");
+                            let has_rejection =
+                                self.onboard_rejection(&next_stop, &new_board_criteria, pt);
+                            if let Some(rejection) = has_rejection {
+                                if rejection == TraceDecision::LowerBoundPruned {
+                                    self.stats.nb_of_lower_bound_prunings += 1;
+                                }
+                                search_trace::record(&mut self.trace, || {
+                                    TraceEvent::new(
+                                        TraceEventKind::Board,
+                                        pt.stop_name(&stop),
+                                        &new_board_criteria,
+                                        rejection,
+                                    )
+                                    .with_trip(pt.trip_name(&trip))
+                                });
                                 continue;
                             }
 
                             let new_board = self.journeys_tree.board(*wait, &trip, &position);
+                            self.stats.nb_of_trips_boarded += 1;
                             trace!(
                                 "    New board {:?} at stop {} into trip {}, parent {:?}",
                                 new_board,
@@ -431,6 +801,15 @@ where
                                 pt.trip_name(&trip),
                                 wait
                             );
+                            search_trace::record(&mut self.trace, || {
+                                TraceEvent::new(
+                                    TraceEventKind::Board,
+                                    pt.stop_name(&stop),
+                                    &new_board_criteria,
+                                    TraceDecision::Kept,
+                                )
+                                .with_trip(pt.trip_name(&trip))
+                            });
                             self.new_board_front.add_and_remove_elements_dominated(
                                 (new_board, trip),
                                 new_board_criteria,
@@ -445,16 +824,21 @@ where
                 {
                     for ((board, trip), criteria) in self.board_front.iter() {
                         let new_criteria = pt.ride(trip, &position, criteria);
-                        if !pt.is_valid(&new_criteria) {
-                            continue;
-                        }
-                        if self.arrive_front.dominates(&new_criteria, pt) {
-                            continue;
-                        }
-                        if self.new_board_front.dominates(&new_criteria, pt) {
-                            continue;
-                        }
-                        if self.can_be_discarded(&new_criteria, pt) {
+                        if let Some(rejection) =
+                            self.onboard_rejection(&next_stop, &new_criteria, pt)
+                        {
+                            if rejection == TraceDecision::LowerBoundPruned {
+                                self.stats.nb_of_lower_bound_prunings += 1;
+                            }
+                            search_trace::record(&mut self.trace, || {
+                                TraceEvent::new(
+                                    TraceEventKind::Ride,
+                                    pt.stop_name(&stop),
+                                    &new_criteria,
+                                    rejection,
+                                )
+                                .with_trip(pt.trip_name(trip))
+                            });
                             continue;
                         }
 
@@ -466,6 +850,8 @@ where
                     }
                 }
                 self.board_front.replace_with(&mut self.new_board_front);
+                self.stats.max_board_front_size =
+                    self.stats.max_board_front_size.max(self.board_front.len());
             }
         }
     }
@@ -532,11 +918,33 @@ where
             let stop_id = pt.stop_id(&stop);
             let new_debark_front = &self.new_debark_fronts[stop_id];
             for (debark, criteria) in new_debark_front.iter() {
-                let arrive_criteria = pt.arrive(&arrival, criteria);
-                if self.can_be_discarded(&arrive_criteria, pt) {
+                let Some(arrive_criteria) = pt.arrive(&arrival, criteria) else {
+                    search_trace::record(&mut self.trace, || {
+                        TraceEvent::new(
+                            TraceEventKind::Arrival,
+                            pt.stop_name(&stop),
+                            criteria,
+                            TraceDecision::Invalid,
+                        )
+                    });
                     continue;
-                }
-                if self.arrive_front.dominates(&arrive_criteria, pt) {
+                };
+                let has_rejection = if self.can_be_discarded(&arrive_criteria, pt) {
+                    Some(TraceDecision::DiscardedByArrivals)
+                } else if self.arrive_front.dominates(&arrive_criteria, pt) {
+                    Some(TraceDecision::DominatedByArrivals)
+                } else {
+                    None
+                };
+                search_trace::record(&mut self.trace, || {
+                    TraceEvent::new(
+                        TraceEventKind::Arrival,
+                        pt.stop_name(&stop),
+                        &arrive_criteria,
+                        has_rejection.unwrap_or(TraceDecision::Kept),
+                    )
+                });
+                if has_rejection.is_some() {
                     continue;
                 }
                 self.arrive_front
@@ -566,25 +974,40 @@ where
                 // we perform all transfers from the `debarked` path
                 for (arrival_stop, arrival_criteria, transfer) in pt.transfers_at(stop, criteria) {
                     let arrival_id = pt.stop_id(&arrival_stop);
-                    if self.can_be_discarded(&arrival_criteria, pt) {
+                    let wait_front = &self.wait_fronts[arrival_id];
+                    let new_wait_front = &self.new_wait_fronts[arrival_id];
+                    let has_rejection = if self.can_be_discarded(&arrival_criteria, pt) {
+                        Some(TraceDecision::DiscardedByArrivals)
+                    } else if self.cannot_improve_arrive_front(&arrival_stop, &arrival_criteria, pt)
+                    {
+                        self.stats.nb_of_lower_bound_prunings += 1;
+                        Some(TraceDecision::LowerBoundPruned)
+                    } else if !pt.is_valid(&arrival_criteria) {
+                        Some(TraceDecision::Invalid)
+                    } else if self.arrive_front.dominates(&arrival_criteria, pt) {
+                        Some(TraceDecision::DominatedByArrivals)
+                    } else if wait_front.dominates(&arrival_criteria, pt)
+                        || new_wait_front.dominates(&arrival_criteria, pt)
+                    {
+                        Some(TraceDecision::DominatedByFront)
+                    } else {
+                        None
+                    };
+                    search_trace::record(&mut self.trace, || {
+                        TraceEvent::new(
+                            TraceEventKind::Transfer,
+                            pt.stop_name(stop),
+                            &arrival_criteria,
+                            has_rejection.unwrap_or(TraceDecision::Kept),
+                        )
+                        .with_to_stop(pt.stop_name(&arrival_stop))
+                    });
+                    if has_rejection.is_some() {
                         continue;
                     }
                     let wait_front = &mut self.wait_fronts[arrival_id];
                     let new_wait_front = &mut self.new_wait_fronts[arrival_id];
 
-                    if !pt.is_valid(&arrival_criteria) {
-                        continue;
-                    }
-                    if self.arrive_front.dominates(&arrival_criteria, pt) {
-                        continue;
-                    }
-                    if wait_front.dominates(&arrival_criteria, pt) {
-                        continue;
-                    }
-                    if new_wait_front.dominates(&arrival_criteria, pt) {
-                        continue;
-                    }
-
                     if new_wait_front.is_empty() {
                         self.stops_with_new_wait.push(arrival_stop.clone());
                     }
@@ -604,7 +1027,15 @@ where
         }
     }
 
-    fn can_be_discarded<R>(&self, partial_journey_criteria: &T::Criteria, pt: &R) -> bool
+    // returns why a label onboard a trip, that will next reach `next_stop`,
+    // should not be added to `new_board_front`,
+    // or `None` if it should be added
+    fn onboard_rejection<R>(
+        &self,
+        next_stop: &T::Stop,
+        onboard_criteria: &T::Criteria,
+        pt: &R,
+    ) -> Option<TraceDecision>
     where
         R: RequestWithIters<
             Position = T::Position,
@@ -617,12 +1048,59 @@ where
             Transfer = T::Transfer,
         >,
     {
-        for (_, complete_journey_criteria) in self.arrive_front.iter() {
-            if pt.can_be_discarded(partial_journey_criteria, complete_journey_criteria) {
-                return true;
-            }
+        if !pt.is_valid(onboard_criteria) {
+            return Some(TraceDecision::Invalid);
+        }
+        if self.arrive_front.dominates(onboard_criteria, pt) {
+            return Some(TraceDecision::DominatedByArrivals);
+        }
+        if self.new_board_front.dominates(onboard_criteria, pt) {
+            return Some(TraceDecision::DominatedByFront);
+        }
+        if self.can_be_discarded(onboard_criteria, pt) {
+            return Some(TraceDecision::DiscardedByArrivals);
         }
-        false
+        if self.cannot_improve_arrive_front(next_stop, onboard_criteria, pt) {
+            return Some(TraceDecision::LowerBoundPruned);
+        }
+        None
+    }
+
+    fn can_be_discarded<R>(&self, partial_journey_criteria: &T::Criteria, pt: &R) -> bool
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+    {
+        can_be_discarded(&self.arrive_front, partial_journey_criteria, pt)
+    }
+
+    fn cannot_improve_arrive_front<R>(
+        &self,
+        stop: &T::Stop,
+        partial_journey_criteria: &T::Criteria,
+        pt: &R,
+    ) -> bool
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+    {
+        cannot_improve_arrive_front(&self.arrive_front, stop, partial_journey_criteria, pt)
     }
 
     // tranfer `new_waiting_fronts` into `waiting_fronts`
@@ -670,8 +1148,19 @@ where
         self.missions_with_new_wait.clear();
     }
 
-    fn fill_results(&mut self) {
-        for (idx, (arrived, criteria)) in self.arrive_front.iter().enumerate() {
+    // fill `results` with the journeys of `arrive_front`
+    // that were created after the `first_arrive`th arrive in `journeys_tree`
+    fn fill_results(&mut self, first_arrive: usize) {
+        let results_start = Instant::now();
+        self.nb_of_results = 0;
+        for (arrived, criteria) in self.arrive_front.iter() {
+            if !self
+                .journeys_tree
+                .is_arrive_created_after(arrived, first_arrive)
+            {
+                continue;
+            }
+            let idx = self.nb_of_results;
             if idx < self.results.len() {
                 let journey_to_fill = &mut self.results[idx];
                 self.journeys_tree
@@ -680,12 +1169,13 @@ where
                 let new_journey = self.journeys_tree.create_journey(*arrived, criteria);
                 self.results.push(new_journey);
             }
+            self.nb_of_results += 1;
         }
+        self.stats.results_duration += results_start.elapsed();
     }
 
     pub fn responses(&self) -> impl Iterator<Item = &Journey<T>> {
-        let nb_of_journeys = self.nb_of_journeys();
-        (0..nb_of_journeys).map(move |idx| &self.results[idx])
+        self.results[..self.nb_of_results].iter()
     }
 
     pub fn tree_size(&self) -> usize {
@@ -695,4 +1185,50 @@ where
     pub fn nb_of_rounds(&self) -> usize {
         self.nb_of_rounds
     }
+
+    /// Statistics on the last computation.
+    ///
+    /// During a range search, the statistics are accumulated
+    /// over all iterations since `init_range()`.
+    pub fn stats(&self) -> &EngineStats {
+        &self.stats
+    }
+}
+
+fn can_be_discarded<T, R>(
+    arrive_front: &ArriveFront<T>,
+    partial_journey_criteria: &T::Criteria,
+    pt: &R,
+) -> bool
+where
+    T: RequestTypes,
+    R: RequestWithIters<Stop = T::Stop, Criteria = T::Criteria>,
+{
+    for (_, complete_journey_criteria) in arrive_front.iter() {
+        if pt.can_be_discarded(partial_journey_criteria, complete_journey_criteria) {
+            return true;
+        }
+    }
+    false
+}
+
+// returns `true` if `pt.lower_bound_at_arrival()` shows that no journey
+// extending `partial_journey_criteria` from `stop` can be valid
+// and added to `arrive_front`
+fn cannot_improve_arrive_front<T, R>(
+    arrive_front: &ArriveFront<T>,
+    stop: &T::Stop,
+    partial_journey_criteria: &T::Criteria,
+    pt: &R,
+) -> bool
+where
+    T: RequestTypes,
+    R: RequestWithIters<Stop = T::Stop, Criteria = T::Criteria>,
+{
+    let Some(lower_bound) = pt.lower_bound_at_arrival(stop, partial_journey_criteria) else {
+        return false;
+    };
+    !pt.is_valid(&lower_bound)
+        || arrive_front.dominates(&lower_bound, pt)
+        || can_be_discarded(arrive_front, &lower_bound, pt)
 }
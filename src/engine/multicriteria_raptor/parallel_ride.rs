@@ -0,0 +1,323 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use std::{fmt::Debug, thread};
+
+use super::{can_be_discarded, cannot_improve_arrive_front, MultiCriteriaRaptor};
+use crate::engine::{
+    engine_interface::{RequestDebug, RequestTypes, RequestWithIters},
+    journeys_tree::{Board, Wait},
+    pareto_front::{ArriveFront, DebarkFront, ParetoFront, WaitFront},
+};
+use crate::transit_data::data_interface::TransitTypes;
+
+// Fronts read by the threads riding missions.
+// They are not modified while the missions are ridden.
+struct RideInput<'engine, T: RequestTypes> {
+    mission_has_new_wait: &'engine [Option<T::Position>],
+    new_wait_fronts: &'engine [WaitFront<T>],
+    debark_fronts: &'engine [DebarkFront<T>],
+    arrive_front: &'engine ArriveFront<T>,
+}
+
+// Boards and debarks found by a thread riding a chunk of missions.
+//
+// Since the journeys tree cannot be shared between threads, a board
+// is identified by its index in `boards`, and is inserted in the journeys tree
+// only when one of its debarks is kept.
+struct RideOutput<T: RequestTypes> {
+    boards: Vec<(Wait, T::Trip, T::Position)>,
+    // in the order they were found
+    debarks: Vec<NewDebark<T>>,
+    max_board_front_size: usize,
+    nb_of_lower_bound_prunings: usize,
+}
+
+struct NewDebark<T: RequestTypes> {
+    // index in `RideOutput::boards`
+    board: usize,
+    position: T::Position,
+    stop: T::Stop,
+    criteria: T::Criteria,
+}
+
+type LocalBoardFront<T> = ParetoFront<(usize, <T as TransitTypes>::Trip), T>;
+
+impl<T> MultiCriteriaRaptor<T>
+where
+    T: RequestTypes,
+    T::Criteria: Debug,
+{
+    // Same as `ride()`, but `missions_with_new_wait` are split in chunks
+    // that are ridden by `nb_of_threads` threads.
+    // The outputs of the chunks are then merged in the order of `missions_with_new_wait`,
+    // so that the fronts obtained do not depend on the number of threads.
+    pub(super) fn ride_in_parallel<R>(&mut self, pt: &R, nb_of_threads: usize)
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug + Sync,
+        T::Mission: Sync,
+        T::Position: Send + Sync,
+        T::Stop: Send,
+        T::Trip: Send,
+        T::Criteria: Send + Sync,
+    {
+        debug_assert!(!self.missions_with_new_wait.is_empty());
+        debug_assert!(self.stops_with_new_debark.is_empty());
+        debug_assert!(self
+            .new_debark_fronts
+            .iter()
+            .all(|front| { front.is_empty() }));
+
+        let nb_of_missions = self.missions_with_new_wait.len();
+        let chunk_size = (nb_of_missions + nb_of_threads - 1) / nb_of_threads.max(1);
+
+        let input = RideInput {
+            mission_has_new_wait: &self.mission_has_new_wait,
+            new_wait_fronts: &self.new_wait_fronts,
+            debark_fronts: &self.debark_fronts,
+            arrive_front: &self.arrive_front,
+        };
+        let missions = &self.missions_with_new_wait;
+
+        let outputs: Vec<RideOutput<T>> = thread::scope(|scope| {
+            let input = &input;
+            let handles: Vec<_> = missions
+                .chunks(chunk_size.max(1))
+                .map(|chunk| scope.spawn(move || ride_missions(input, chunk, pt)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("A thread riding missions panicked."))
+                .collect()
+        });
+
+        for output in outputs {
+            self.merge_ride_output(output, pt);
+        }
+    }
+
+    // insert the debarks found by a thread into `new_debark_fronts`
+    // exactly as `ride()` would have done
+    fn merge_ride_output<R>(&mut self, output: RideOutput<T>, pt: &R)
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+    {
+        let mut boards_in_tree: Vec<Option<Board>> = vec![None; output.boards.len()];
+
+        for NewDebark {
+            board: local_board,
+            position,
+            stop,
+            criteria,
+        } in output.debarks
+        {
+            let stop_id = pt.stop_id(&stop);
+            let debark_front = &mut self.debark_fronts[stop_id];
+            let new_debark_front = &mut self.new_debark_fronts[stop_id];
+            if debark_front.dominates(&criteria, pt) {
+                continue;
+            }
+            if new_debark_front.dominates(&criteria, pt) {
+                continue;
+            }
+            let journeys_tree = &mut self.journeys_tree;
+            let board = *boards_in_tree[local_board].get_or_insert_with(|| {
+                let (wait, trip, board_position) = &output.boards[local_board];
+                journeys_tree.board(*wait, trip, board_position)
+            });
+            let new_debark = journeys_tree.debark(board, &position);
+            debark_front.remove_elements_dominated_by(&criteria, pt);
+            if new_debark_front.is_empty() {
+                self.stops_with_new_debark.push(stop);
+            }
+            new_debark_front.add_and_remove_elements_dominated(new_debark, criteria, pt);
+        }
+
+        self.stats.nb_of_trips_boarded += boards_in_tree.iter().flatten().count();
+        self.stats.nb_of_lower_bound_prunings += output.nb_of_lower_bound_prunings;
+        self.stats.max_board_front_size = self
+            .stats
+            .max_board_front_size
+            .max(output.max_board_front_size);
+    }
+}
+
+// ride `missions` as `MultiCriteriaRaptor::ride()` does, but without
+// modifying the journeys tree nor the debark fronts.
+// A debark is kept in the output only if it is not dominated by
+// the `debark_fronts` of `input`. The check against the new debarks
+// is done when merging the outputs.
+fn ride_missions<T, R>(input: &RideInput<'_, T>, missions: &[T::Mission], pt: &R) -> RideOutput<T>
+where
+    T: RequestTypes,
+    R: RequestWithIters<
+        Position = T::Position,
+        Mission = T::Mission,
+        Stop = T::Stop,
+        Trip = T::Trip,
+        Departure = T::Departure,
+        Arrival = T::Arrival,
+        Criteria = T::Criteria,
+        Transfer = T::Transfer,
+    >,
+{
+    let mut output = RideOutput {
+        boards: Vec::new(),
+        debarks: Vec::new(),
+        max_board_front_size: 0,
+        nb_of_lower_bound_prunings: 0,
+    };
+    let mut board_front = LocalBoardFront::<T>::new();
+    let mut new_board_front = LocalBoardFront::<T>::new();
+
+    for mission in missions {
+        let mut has_position = input.mission_has_new_wait[pt.mission_id(mission)].clone();
+
+        board_front.clear();
+
+        while let Some(position) = has_position {
+            let stop = pt.stop_of(&position, mission);
+            let stop_id = pt.stop_id(&stop);
+
+            let debark_front = &input.debark_fronts[stop_id];
+            for ((board, trip), board_criteria) in board_front.iter() {
+                if let Some(new_debark_criteria) = pt.debark(trip, &position, board_criteria) {
+                    if debark_front.dominates(&new_debark_criteria, pt) {
+                        continue;
+                    }
+                    output.debarks.push(NewDebark {
+                        board: *board,
+                        position: position.clone(),
+                        stop: stop.clone(),
+                        criteria: new_debark_criteria,
+                    });
+                }
+            }
+
+            has_position = pt.next_on_mission(&position, mission);
+
+            let next_stop = match &has_position {
+                Some(next_position) => pt.stop_of(next_position, mission),
+                None => continue,
+            };
+
+            new_board_front.clear();
+            for (wait, wait_criteria) in input.new_wait_fronts[stop_id].iter() {
+                if let Some((trip, new_board_criteria)) =
+                    pt.best_trip_to_board(&position, mission, wait_criteria)
+                {
+                    if !pt.is_valid(&new_board_criteria) {
+                        continue;
+                    }
+                    if input.arrive_front.dominates(&new_board_criteria, pt) {
+                        continue;
+                    }
+                    if new_board_front.dominates(&new_board_criteria, pt) {
+                        continue;
+                    }
+                    if can_be_discarded(input.arrive_front, &new_board_criteria, pt) {
+                        continue;
+                    }
+                    if cannot_improve_arrive_front(
+                        input.arrive_front,
+                        &next_stop,
+                        &new_board_criteria,
+                        pt,
+                    ) {
+                        output.nb_of_lower_bound_prunings += 1;
+                        continue;
+                    }
+
+                    let new_board = output.boards.len();
+                    output.boards.push((*wait, trip.clone(), position.clone()));
+                    new_board_front.add_and_remove_elements_dominated(
+                        (new_board, trip),
+                        new_board_criteria,
+                        pt,
+                    );
+                }
+            }
+
+            for ((board, trip), criteria) in board_front.iter() {
+                let new_criteria = pt.ride(trip, &position, criteria);
+                if !pt.is_valid(&new_criteria) {
+                    continue;
+                }
+                if input.arrive_front.dominates(&new_criteria, pt) {
+                    continue;
+                }
+                if new_board_front.dominates(&new_criteria, pt) {
+                    continue;
+                }
+                if can_be_discarded(input.arrive_front, &new_criteria, pt) {
+                    continue;
+                }
+                if cannot_improve_arrive_front(input.arrive_front, &next_stop, &new_criteria, pt) {
+                    output.nb_of_lower_bound_prunings += 1;
+                    continue;
+                }
+
+                new_board_front.add_and_remove_elements_dominated(
+                    (*board, trip.clone()),
+                    new_criteria,
+                    pt,
+                );
+            }
+            board_front.replace_with(&mut new_board_front);
+            output.max_board_front_size = output.max_board_front_size.max(board_front.len());
+        }
+    }
+    output
+}
@@ -44,8 +44,23 @@ use crate::{
 
 use std::{fmt::Debug, slice::Iter as SliceIter};
 
+const SMALL_FRONT_SIZE: usize = 8;
+
+/// A set of elements, none of which is dominated by another one.
+///
+/// Once the front grows beyond `SMALL_FRONT_SIZE` elements, its elements are kept
+/// sorted by increasing `request.front_key()`.
+/// Since an element can only be dominated by an element with a lower or equal key,
+/// checking for dominance then only scans the part of the front
+/// on the relevant side of the key of the new criteria.
+/// Smaller fronts are scanned entirely, which is cheaper than computing the keys.
+///
+/// `add_unchecked()` does not know the request, so it appends to an unsorted tail,
+/// which is scanned entirely and sorted in by the next `remove_elements_dominated_by()`.
 pub struct ParetoFront<ItemData, T: RequestTypes> {
     elements: Vec<(ItemData, T::Criteria)>,
+    // `front_key()` of the sorted elements `elements[..keys.len()]`
+    keys: Vec<i64>,
 }
 
 impl<ItemData, T: RequestTypes> Debug for ParetoFront<ItemData, T>
@@ -69,6 +84,7 @@ impl<ItemData: Clone, T: RequestTypes> Clone for ParetoFront<ItemData, T> {
     fn clone(&self) -> Self {
         ParetoFront {
             elements: self.elements.clone(),
+            keys: self.keys.clone(),
         }
     }
 }
@@ -77,11 +93,12 @@ impl<ItemData: Clone, T: RequestTypes> ParetoFront<ItemData, T> {
     pub fn new() -> Self {
         Self {
             elements: Vec::new(),
+            keys: Vec::new(),
         }
     }
 
     pub fn replace_with(&mut self, other: &mut Self) {
-        std::mem::swap(&mut self.elements, &mut other.elements);
+        std::mem::swap(self, other);
     }
 
     pub fn len(&self) -> usize {
@@ -90,6 +107,7 @@ impl<ItemData: Clone, T: RequestTypes> ParetoFront<ItemData, T> {
 
     pub fn clear(&mut self) {
         self.elements.clear();
+        self.keys.clear();
     }
 
     pub fn is_empty(&self) -> bool {
@@ -100,12 +118,18 @@ impl<ItemData: Clone, T: RequestTypes> ParetoFront<ItemData, T> {
     where
         R: Request<Criteria = T::Criteria>,
     {
-        for (_, ref old_criteria) in &self.elements {
-            if request.is_lower(old_criteria, criteria) {
-                return true;
-            }
-        }
-        false
+        let nb_sorted = self.keys.len();
+        let end = if nb_sorted > 0 {
+            let key = request.front_key(criteria);
+            // only the sorted elements with a key lower or equal to `key` can dominate `criteria`
+            self.keys.partition_point(|&old_key| old_key <= key)
+        } else {
+            0
+        };
+        self.elements[..end]
+            .iter()
+            .chain(&self.elements[nb_sorted..])
+            .any(|(_, old_criteria)| is_dominated_by(request, old_criteria, criteria))
     }
 
     pub fn add_unchecked(&mut self, item_data: ItemData, criteria: T::Criteria) {
@@ -116,8 +140,18 @@ impl<ItemData: Clone, T: RequestTypes> ParetoFront<ItemData, T> {
     where
         R: Request<Criteria = T::Criteria>,
     {
-        self.elements
-            .retain(|(_, old_criteria)| !request.is_lower(criteria, old_criteria));
+        if self.elements.len() > SMALL_FRONT_SIZE || !self.keys.is_empty() {
+            self.sort_tail(request);
+            let key = request.front_key(criteria);
+            // only the elements with a key greater or equal to `key` can be dominated by `criteria`
+            let start = self.keys.partition_point(|&old_key| old_key < key);
+            self.retain_from(start, |old_criteria| {
+                !is_dominated_by(request, criteria, old_criteria)
+            });
+        } else {
+            self.elements
+                .retain(|(_, old_criteria)| !is_dominated_by(request, criteria, old_criteria));
+        }
     }
 
     pub fn remove_elements_that_can_be_discarded_by<R>(
@@ -127,8 +161,9 @@ impl<ItemData: Clone, T: RequestTypes> ParetoFront<ItemData, T> {
     ) where
         R: Request<Criteria = T::Criteria>,
     {
-        self.elements
-            .retain(|(_, old_criteria)| !request.can_be_discarded(old_criteria, criteria));
+        self.retain_from(0, |old_criteria| {
+            !request.can_be_discarded(old_criteria, criteria)
+        });
     }
 
     pub fn add_and_remove_elements_dominated<R>(
@@ -158,4 +193,51 @@ impl<ItemData: Clone, T: RequestTypes> ParetoFront<ItemData, T> {
     pub fn iter(&self) -> SliceIter<'_, (ItemData, T::Criteria)> {
         self.elements.iter()
     }
+
+    // Moves the elements of the unsorted tail to their place among the sorted elements.
+    fn sort_tail<R>(&mut self, request: &R)
+    where
+        R: Request<Criteria = T::Criteria>,
+    {
+        for idx in self.keys.len()..self.elements.len() {
+            let key = request.front_key(&self.elements[idx].1);
+            // insert after the elements with the same key, to keep the insertion order among them
+            let position = self.keys.partition_point(|&old_key| old_key <= key);
+            self.elements[position..=idx].rotate_right(1);
+            self.keys.insert(position, key);
+        }
+    }
+
+    // Removes the elements at positions `start..` for which `keep` returns false,
+    // preserving the order of the remaining elements and their keys.
+    fn retain_from<F>(&mut self, start: usize, mut keep: F)
+    where
+        F: FnMut(&T::Criteria) -> bool,
+    {
+        let nb_sorted = self.keys.len();
+        let mut nb_kept = start;
+        let mut nb_sorted_kept = start.min(nb_sorted);
+        for idx in start..self.elements.len() {
+            if keep(&self.elements[idx].1) {
+                self.elements.swap(nb_kept, idx);
+                if idx < nb_sorted {
+                    self.keys[nb_sorted_kept] = self.keys[idx];
+                    nb_sorted_kept += 1;
+                }
+                nb_kept += 1;
+            }
+        }
+        self.elements.truncate(nb_kept);
+        self.keys.truncate(nb_sorted_kept);
+    }
+}
+
+// `dominated` can be removed from a front that contains `dominating`
+// when it is not better, and not close enough to be kept as an alternative
+fn is_dominated_by<R: Request>(
+    request: &R,
+    dominating: &R::Criteria,
+    dominated: &R::Criteria,
+) -> bool {
+    request.is_lower(dominating, dominated) && !request.is_within_slack(dominating, dominated)
 }
@@ -0,0 +1,183 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use serde::Serialize;
+use std::fmt::Debug;
+
+/// Records the steps performed by `MultiCriteriaRaptor` during its last computation,
+/// along with the dominance decisions taken at each step.
+///
+/// Recording is disabled by default, and enabled with
+/// `MultiCriteriaRaptor::set_trace_recording()`.
+/// Stops, trips and positions are identified by the names
+/// provided by `RequestDebug`, and criteria by their `Debug` representation.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchTrace {
+    /// Departures, explored before the first round.
+    pub departures: Vec<TraceEvent>,
+    pub rounds: Vec<RoundTrace>,
+
+    // whether the events are recorded in the last round, or in `departures`
+    #[serde(skip)]
+    in_round: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RoundTrace {
+    pub round: usize,
+    pub events: Vec<TraceEvent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceEvent {
+    pub kind: TraceEventKind,
+    /// Stop where the event occurs.
+    pub stop: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trip: Option<String>,
+    /// Stop reached by a transfer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_stop: Option<String>,
+    pub criteria: String,
+    pub decision: TraceDecision,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceEventKind {
+    Departure,
+    Board,
+    /// Riding a boarded trip to the next position.
+    /// Only recorded when the label is dropped.
+    Ride,
+    Debark,
+    Transfer,
+    Arrival,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceDecision {
+    /// The label was added to its pareto front.
+    Kept,
+    /// The criteria are not valid for the request (too many legs, too long, ...).
+    Invalid,
+    /// No trip can be boarded.
+    NoTripToBoard,
+    /// The label is dominated by a journey that already reached an arrival.
+    DominatedByArrivals,
+    /// The label is dominated by a label of the pareto front it should be added to.
+    DominatedByFront,
+    /// The label can be discarded by a journey that already reached an arrival.
+    DiscardedByArrivals,
+    /// The lower bound at arrival shows that the label cannot lead to a useful journey.
+    LowerBoundPruned,
+}
+
+impl TraceEvent {
+    pub(crate) fn new<Criteria: Debug>(
+        kind: TraceEventKind,
+        stop: String,
+        criteria: &Criteria,
+        decision: TraceDecision,
+    ) -> Self {
+        Self {
+            kind,
+            stop,
+            trip: None,
+            to_stop: None,
+            criteria: format!("{:?}", criteria),
+            decision,
+        }
+    }
+
+    pub(crate) fn with_trip(mut self, trip: String) -> Self {
+        self.trip = Some(trip);
+        self
+    }
+
+    pub(crate) fn with_to_stop(mut self, to_stop: String) -> Self {
+        self.to_stop = Some(to_stop);
+        self
+    }
+}
+
+impl SearchTrace {
+    pub fn clear(&mut self) {
+        self.departures.clear();
+        self.rounds.clear();
+        self.in_round = false;
+    }
+
+    pub fn nb_of_events(&self) -> usize {
+        self.departures.len()
+            + self
+                .rounds
+                .iter()
+                .map(|round| round.events.len())
+                .sum::<usize>()
+    }
+
+    pub(crate) fn start_round(&mut self, round: usize) {
+        self.rounds.push(RoundTrace {
+            round,
+            events: Vec::new(),
+        });
+        self.in_round = true;
+    }
+
+    pub(crate) fn end_rounds(&mut self) {
+        self.in_round = false;
+    }
+
+    pub(crate) fn record(&mut self, event: TraceEvent) {
+        match self.rounds.last_mut() {
+            Some(round) if self.in_round => round.events.push(event),
+            _ => self.departures.push(event),
+        }
+    }
+}
+
+// records the event built by `make_event` when `trace` is recording,
+// so that nothing is computed otherwise
+pub(crate) fn record<F>(trace: &mut Option<SearchTrace>, make_event: F)
+where
+    F: FnOnce() -> TraceEvent,
+{
+    if let Some(trace) = trace {
+        trace.record(make_event());
+    }
+}
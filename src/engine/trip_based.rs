@@ -0,0 +1,545 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use std::{collections::HashMap, hash::Hash, time::Instant};
+
+use crate::{
+    engine::{
+        cancellation::{Cancellation, ComputeStatus},
+        engine_interface::{
+            ConnectionLeg, DepartureLeg, Journey, RequestDebug, RequestTypes, RequestWithIters,
+        },
+        engine_stats::EngineStats,
+    },
+    request::generic_request::Criteria,
+    time::SecondsSinceDatasetUTCStart,
+    transit_data::data_interface::DataWithIters,
+    RealTimeLevel,
+};
+use tracing::{debug, trace};
+
+/// Number of trip segments scanned between two checks of the `Cancellation`.
+const CANCELLATION_CHECK_PERIOD: usize = 1_000;
+
+/// A Trip-Based Routing engine, that computes the journeys with
+/// the earliest arrival time for each number of legs.
+///
+/// The transfers between trips are precomputed in the data
+/// (see `TransitData::compute_trip_transfers()`), so a search only
+/// has to ride trips and to follow these transfers, round after round.
+/// A trip is ridden at most once from each position, since being onboard
+/// earlier on the same trip, with fewer legs, is always better.
+///
+/// The `Request` is used to board, ride, debark, transfer and arrive,
+/// and to check the validity of partial journeys, but only `Criteria::time()`
+/// and `Criteria::nb_of_legs` are used to compare them.
+/// So the journeys obtained are Pareto-optimal with respect to
+/// the arrival time and the number of legs only.
+/// Stay-in trips are not handled.
+pub struct TripBased<T: RequestTypes> {
+    // the segments of trips to ride, in the order of the rounds
+    segments: Vec<Segment<T>>,
+    // map a trip to the earliest position at which it has been boarded
+    boarded: HashMap<T::Trip, T::Position>,
+
+    arrivals: Vec<(usize, T::Arrival)>, // stop id of each arrival
+    is_arrival_stop: Vec<bool>,         // map a `stop` to true if an arrival can be made there
+
+    // the best arrival time found so far
+    best_arrival_time: Option<SecondsSinceDatasetUTCStart>,
+    // the best arrival found in the current round, if it improves `best_arrival_time`
+    round_arrival: Option<ArrivalLabel<T>>,
+    // the arrivals that improved `best_arrival_time`, in increasing number of legs
+    arrival_labels: Vec<ArrivalLabel<T>>,
+
+    results: Vec<Journey<T>>,
+
+    stats: EngineStats,
+}
+
+// The ride of `trip` from `board_position`, until `end_position` (excluded)
+struct Segment<T: RequestTypes> {
+    trip: T::Trip,
+    board_position: T::Position,
+    end_position: Option<T::Position>,
+    // the criteria at the arrival at the position following `board_position`
+    criteria: T::Criteria,
+    origin: SegmentOrigin<T>,
+}
+
+enum SegmentOrigin<T: RequestTypes> {
+    Departure(T::Departure),
+    Transfer {
+        transfer: T::Transfer,
+        // the segment debarked before the transfer
+        segment: usize,
+        debark_position: T::Position,
+    },
+}
+
+struct ArrivalLabel<T: RequestTypes> {
+    arrival: T::Arrival,
+    segment: usize,
+    debark_position: T::Position,
+    criteria: T::Criteria,
+}
+
+impl<T> TripBased<T>
+where
+    T: RequestTypes<Criteria = Criteria>,
+    T::Trip: Hash + Eq,
+{
+    pub fn new(nb_of_stops: usize) -> Self {
+        let mut engine = Self {
+            segments: Vec::new(),
+            boarded: HashMap::new(),
+            arrivals: Vec::new(),
+            is_arrival_stop: Vec::new(),
+            best_arrival_time: None,
+            round_arrival: None,
+            arrival_labels: Vec::new(),
+            results: Vec::new(),
+            stats: EngineStats::default(),
+        };
+        engine.resize(nb_of_stops);
+        engine
+    }
+
+    fn resize(&mut self, nb_of_stops: usize) {
+        self.is_arrival_stop.resize(nb_of_stops, false);
+    }
+
+    fn clear(&mut self) {
+        self.segments.clear();
+        self.boarded.clear();
+        self.arrivals.clear();
+        self.is_arrival_stop
+            .iter_mut()
+            .for_each(|is_arrival| *is_arrival = false);
+        self.best_arrival_time = None;
+        self.round_arrival = None;
+        self.arrival_labels.clear();
+        self.results.clear();
+        self.stats = EngineStats::default();
+    }
+
+    /// Computes, for each number of legs, the journey with the earliest arrival time,
+    /// when it arrives earlier than the journeys with fewer legs.
+    ///
+    /// The transfers between trips are read from `data.trip_transfers_at()`,
+    /// so no journey with more than one leg is found if they have not been computed.
+    ///
+    /// `cancellation` is checked periodically. When it is triggered, the search stops,
+    /// `responses()` provides the journeys found so far,
+    /// and `ComputeStatus::Interrupted` is returned.
+    pub fn compute<R, Data>(
+        &mut self,
+        pt: &R,
+        data: &Data,
+        real_time_level: RealTimeLevel,
+        cancellation: &Cancellation,
+    ) -> ComputeStatus
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug,
+        Data: DataWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Transfer = T::Transfer,
+        >,
+    {
+        self.clear();
+        self.resize(pt.nb_of_stops());
+
+        let init_start = Instant::now();
+        self.init_with_departures_and_arrivals(pt);
+        self.stats.init_duration += init_start.elapsed();
+
+        let mut status = ComputeStatus::Complete;
+        let mut round_start = 0;
+        'rounds: while round_start < self.segments.len() {
+            let round_end = self.segments.len();
+            self.stats.nb_of_rounds += 1;
+
+            let ride_start = Instant::now();
+            for segment_idx in round_start..round_end {
+                let nb_scanned = segment_idx - round_start;
+                if nb_scanned.is_multiple_of(CANCELLATION_CHECK_PERIOD)
+                    && cancellation.is_cancelled()
+                {
+                    status = ComputeStatus::Interrupted;
+                    self.stats.ride_duration += ride_start.elapsed();
+                    break 'rounds;
+                }
+                self.scan_segment(pt, data, real_time_level, segment_idx);
+            }
+            self.stats.ride_duration += ride_start.elapsed();
+
+            if let Some(arrival_label) = self.round_arrival.take() {
+                self.arrival_labels.push(arrival_label);
+            }
+            round_start = round_end;
+        }
+        // an arrival found in an interrupted round is still a valid journey
+        if let Some(arrival_label) = self.round_arrival.take() {
+            self.arrival_labels.push(arrival_label);
+        }
+
+        let results_start = Instant::now();
+        self.fill_results();
+        self.stats.results_duration += results_start.elapsed();
+
+        debug!(
+            "Trip based : {} segments ridden in {} rounds",
+            self.segments.len(),
+            self.stats.nb_of_rounds
+        );
+
+        status
+    }
+
+    fn init_with_departures_and_arrivals<R>(&mut self, pt: &R)
+    where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        R: RequestDebug,
+    {
+        for arrival in pt.arrivals() {
+            let stop_id = pt.stop_id(&pt.arrival_stop(&arrival));
+            self.is_arrival_stop[stop_id] = true;
+            self.arrivals.push((stop_id, arrival));
+        }
+
+        for departure in pt.departures() {
+            let (stop, criteria) = pt.depart(&departure);
+            if !pt.is_valid(&criteria) {
+                continue;
+            }
+            trace!(
+                "Departure from {} at {:?}",
+                pt.stop_name(&stop),
+                criteria.time()
+            );
+            for (mission, position) in pt.missions_at(&stop) {
+                let has_trip = pt.best_trip_to_board(&position, &mission, &criteria);
+                if let Some((trip, onboard_criteria)) = has_trip {
+                    if pt.is_valid(&onboard_criteria) {
+                        self.board(
+                            pt,
+                            trip,
+                            position,
+                            onboard_criteria,
+                            SegmentOrigin::Departure(departure.clone()),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // Adds a segment that rides `trip` from `position`, unless `trip`
+    // has already been boarded at `position` or upstream.
+    fn board<R>(
+        &mut self,
+        pt: &R,
+        trip: T::Trip,
+        position: T::Position,
+        criteria: T::Criteria,
+        origin: SegmentOrigin<T>,
+    ) where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Trip = T::Trip,
+            Criteria = T::Criteria,
+        >,
+    {
+        let mission = pt.mission_of(&trip);
+        let end_position = match self.boarded.get(&trip) {
+            Some(boarded_position) => {
+                if !pt.is_upstream(&position, boarded_position, &mission) {
+                    return;
+                }
+                Some(boarded_position.clone())
+            }
+            None => None,
+        };
+        self.boarded.insert(trip.clone(), position.clone());
+        self.stats.nb_of_trips_boarded += 1;
+        self.segments.push(Segment {
+            trip,
+            board_position: position,
+            end_position,
+            criteria,
+            origin,
+        });
+    }
+
+    // A partial journey is useless when it arrives after the best arrival found so far,
+    // since it cannot have fewer legs.
+    fn is_useless(&self, criteria: &T::Criteria) -> bool {
+        self.best_arrival_time
+            .is_some_and(|best_arrival_time| criteria.time() >= best_arrival_time)
+    }
+
+    fn scan_segment<R, Data>(
+        &mut self,
+        pt: &R,
+        data: &Data,
+        real_time_level: RealTimeLevel,
+        segment_idx: usize,
+    ) where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        Data: DataWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Transfer = T::Transfer,
+        >,
+    {
+        self.stats.nb_of_missions_scanned += 1;
+        let segment = &self.segments[segment_idx];
+        let trip = segment.trip.clone();
+        let end_position = segment.end_position.clone();
+        let mut criteria = segment.criteria.clone();
+        let mission = pt.mission_of(&trip);
+        let Some(mut position) = pt.next_on_mission(&segment.board_position, &mission) else {
+            return;
+        };
+        loop {
+            // the positions from `end_position` are ridden by another segment
+            if let Some(end_position) = &end_position {
+                if !pt.is_upstream(&position, end_position, &mission) {
+                    break;
+                }
+            }
+            if self.is_useless(&criteria) {
+                break;
+            }
+            if let Some(debark_criteria) = pt.debark(&trip, &position, &criteria) {
+                if pt.is_valid(&debark_criteria) {
+                    self.debark(
+                        pt,
+                        data,
+                        real_time_level,
+                        segment_idx,
+                        &trip,
+                        &position,
+                        &mission,
+                        debark_criteria,
+                    );
+                }
+            }
+            let Some(next_position) = pt.next_on_mission(&position, &mission) else {
+                break;
+            };
+            criteria = pt.ride(&trip, &position, &criteria);
+            position = next_position;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn debark<R, Data>(
+        &mut self,
+        pt: &R,
+        data: &Data,
+        real_time_level: RealTimeLevel,
+        segment_idx: usize,
+        trip: &T::Trip,
+        position: &T::Position,
+        mission: &T::Mission,
+        criteria: T::Criteria,
+    ) where
+        R: RequestWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Departure = T::Departure,
+            Arrival = T::Arrival,
+            Criteria = T::Criteria,
+            Transfer = T::Transfer,
+        >,
+        Data: DataWithIters<
+            Position = T::Position,
+            Mission = T::Mission,
+            Stop = T::Stop,
+            Trip = T::Trip,
+            Transfer = T::Transfer,
+        >,
+    {
+        let stop = pt.stop_of(position, mission);
+        let stop_id = pt.stop_id(&stop);
+
+        if self.is_arrival_stop[stop_id] {
+            for (arrival_stop_id, arrival) in &self.arrivals {
+                if *arrival_stop_id != stop_id {
+                    continue;
+                }
+                let Some(arrival_criteria) = pt.arrive(arrival, &criteria) else {
+                    continue;
+                };
+                if pt.is_valid(&arrival_criteria) && !self.is_useless(&arrival_criteria) {
+                    self.best_arrival_time = Some(arrival_criteria.time());
+                    self.round_arrival = Some(ArrivalLabel {
+                        arrival: arrival.clone(),
+                        segment: segment_idx,
+                        debark_position: position.clone(),
+                        criteria: arrival_criteria,
+                    });
+                }
+            }
+        }
+
+        let transfer_start = Instant::now();
+        for (_, transfer_criteria, transfer) in pt.transfers_at(&stop, &criteria) {
+            if !pt.is_valid(&transfer_criteria) || self.is_useless(&transfer_criteria) {
+                continue;
+            }
+            let trip_transfers = data.trip_transfers_at(trip, position, &transfer, real_time_level);
+            for (_, board_position, board_trip) in trip_transfers {
+                let has_criteria =
+                    pt.board_and_ride(&board_position, &board_trip, &transfer_criteria);
+                let Some(onboard_criteria) = has_criteria else {
+                    continue;
+                };
+                if pt.is_valid(&onboard_criteria) && !self.is_useless(&onboard_criteria) {
+                    self.board(
+                        pt,
+                        board_trip,
+                        board_position,
+                        onboard_criteria,
+                        SegmentOrigin::Transfer {
+                            transfer: transfer.clone(),
+                            segment: segment_idx,
+                            debark_position: position.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        self.stats.transfers_duration += transfer_start.elapsed();
+    }
+
+    fn fill_results(&mut self) {
+        for arrival_label in &self.arrival_labels {
+            let mut connection_legs = Vec::new();
+            let mut segment_idx = arrival_label.segment;
+            let mut debark_position = arrival_label.debark_position.clone();
+            let departure_leg = loop {
+                let segment = &self.segments[segment_idx];
+                let trip = segment.trip.clone();
+                let board_position = segment.board_position.clone();
+                match &segment.origin {
+                    SegmentOrigin::Departure(departure) => {
+                        break DepartureLeg {
+                            departure: departure.clone(),
+                            trip,
+                            board_position,
+                            debark_position,
+                        };
+                    }
+                    SegmentOrigin::Transfer {
+                        transfer,
+                        segment: previous_segment,
+                        debark_position: previous_debark_position,
+                    } => {
+                        connection_legs.push(ConnectionLeg {
+                            transfer: transfer.clone(),
+                            trip,
+                            board_position,
+                            debark_position,
+                        });
+                        segment_idx = *previous_segment;
+                        debark_position = previous_debark_position.clone();
+                    }
+                }
+            };
+            connection_legs.reverse();
+
+            self.results.push(Journey {
+                departure_leg,
+                connection_legs,
+                arrival: arrival_label.arrival.clone(),
+                criteria_at_arrival: arrival_label.criteria.clone(),
+            });
+        }
+    }
+
+    pub fn responses(&self) -> impl Iterator<Item = &Journey<T>> {
+        self.results.iter()
+    }
+
+    pub fn nb_of_journeys(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Statistics on the last computation.
+    ///
+    /// `nb_of_missions_scanned` counts the segments of trips that have been ridden.
+    pub fn stats(&self) -> &EngineStats {
+        &self.stats
+    }
+}
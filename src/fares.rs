@@ -0,0 +1,471 @@
+// Copyright  (C) 2022, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+//! Pricing of journeys with the fare data of the dataset.
+//!
+//! A journey is priced with the tickets described by `transit_model`
+//! (tickets, ticket uses, ticket prices, perimeters and restrictions).
+//! A ticket use may cover several consecutive public transport sections
+//! of a journey, as long as the lines and networks used are in its perimeter,
+//! the number of transfers and the time limits are respected, and the origin
+//! and destination of the covered sections match its restrictions (if any).
+//! We choose the combination of tickets that prices as many sections as
+//! possible and, among them, the cheapest one.
+//! Since amounts in different currencies cannot be compared, no section is priced
+//! when the tickets that can cover the sections of a journey are priced in several currencies.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use transit_model::objects::{ObjectType, PerimeterAction, RestrictionType};
+
+use crate::{
+    models::{ModelRefs, StopPointIdx, VehicleJourneyIdx},
+    response::VehicleSection,
+    Response,
+};
+
+/// Fare data of a dataset, indexed by ticket use.
+#[derive(Debug, Default)]
+pub struct FareData {
+    ticket_uses: Vec<TicketUseData>,
+}
+
+#[derive(Debug)]
+struct TicketUseData {
+    ticket_id: String,
+    ticket_name: String,
+    max_transfers: Option<u32>,
+    boarding_time_limit: Option<u32>,
+    alighting_time_limit: Option<u32>,
+    included_lines: HashSet<String>,
+    included_networks: HashSet<String>,
+    excluded_lines: HashSet<String>,
+    excluded_networks: HashSet<String>,
+    // (origin fare zone, destination fare zone)
+    zone_restrictions: Vec<(String, String)>,
+    // (origin stop area, destination stop area)
+    origin_destination_restrictions: Vec<(String, String)>,
+    prices: Vec<TicketPriceData>,
+}
+
+#[derive(Debug)]
+struct TicketPriceData {
+    validity_start: NaiveDate,
+    validity_end: NaiveDate,
+    cost: Cost,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cost {
+    pub value: Decimal,
+    pub currency: String,
+}
+
+/// A ticket bought to cover some public transport sections of a journey.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FareTicket {
+    pub ticket_id: String,
+    pub ticket_name: String,
+    pub cost: Cost,
+    // indices of the public transport sections covered by this ticket :
+    // 0 is the first vehicle, and `i` is the vehicle of the `i-1`th connection
+    pub sections: Vec<usize>,
+}
+
+/// The tickets needed to travel along a journey.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Fare {
+    pub tickets: Vec<FareTicket>,
+    // indices of the public transport sections not covered by any ticket
+    pub unpriced_sections: Vec<usize>,
+}
+
+impl Fare {
+    /// Returns true when every public transport section is covered by a ticket.
+    pub fn is_complete(&self) -> bool {
+        self.unpriced_sections.is_empty()
+    }
+
+    /// The total cost of the tickets.
+    ///
+    /// Returns `None` when some section could not be priced,
+    /// or when the tickets are not priced in the same currency.
+    pub fn total(&self) -> Option<Cost> {
+        if !self.is_complete() {
+            return None;
+        }
+        let mut tickets = self.tickets.iter();
+        let mut total = tickets.next()?.cost.clone();
+        for ticket in tickets {
+            if ticket.cost.currency != total.currency {
+                return None;
+            }
+            total.value += ticket.cost.value;
+        }
+        Some(total)
+    }
+
+    /// The ticket covering the public transport section `section_idx`, if any.
+    pub fn section_ticket(&self, section_idx: usize) -> Option<&FareTicket> {
+        self.tickets
+            .iter()
+            .find(|ticket| ticket.sections.contains(&section_idx))
+    }
+}
+
+impl FareData {
+    pub fn new(collections: &transit_model::model::Collections) -> Self {
+        let mut ticket_uses: Vec<TicketUseData> = Vec::new();
+        let mut ticket_use_pos: HashMap<&str, usize> = HashMap::new();
+
+        for ticket_use in collections.ticket_uses.values() {
+            let ticket_name = collections
+                .tickets
+                .get(&ticket_use.ticket_id)
+                .map(|ticket| ticket.name.clone())
+                .unwrap_or_else(|| ticket_use.ticket_id.clone());
+            let prices = collections
+                .ticket_prices
+                .values()
+                .filter(|price| price.ticket_id == ticket_use.ticket_id)
+                .map(|price| TicketPriceData {
+                    validity_start: price.ticket_validity_start,
+                    validity_end: price.ticket_validity_end,
+                    cost: Cost {
+                        value: price.price,
+                        currency: price.currency.clone(),
+                    },
+                })
+                .collect();
+            ticket_use_pos.insert(ticket_use.id.as_str(), ticket_uses.len());
+            ticket_uses.push(TicketUseData {
+                ticket_id: ticket_use.ticket_id.clone(),
+                ticket_name,
+                max_transfers: ticket_use.max_transfers,
+                boarding_time_limit: ticket_use.boarding_time_limit,
+                alighting_time_limit: ticket_use.alighting_time_limit,
+                included_lines: HashSet::new(),
+                included_networks: HashSet::new(),
+                excluded_lines: HashSet::new(),
+                excluded_networks: HashSet::new(),
+                zone_restrictions: Vec::new(),
+                origin_destination_restrictions: Vec::new(),
+                prices,
+            });
+        }
+
+        for perimeter in collections.ticket_use_perimeters.values() {
+            let Some(&pos) = ticket_use_pos.get(perimeter.ticket_use_id.as_str()) else {
+                continue;
+            };
+            let ticket_use = &mut ticket_uses[pos];
+            let set = match (&perimeter.object_type, &perimeter.perimeter_action) {
+                (ObjectType::Line, PerimeterAction::Included) => &mut ticket_use.included_lines,
+                (ObjectType::Network, PerimeterAction::Included) => {
+                    &mut ticket_use.included_networks
+                }
+                (ObjectType::Line, PerimeterAction::Excluded) => &mut ticket_use.excluded_lines,
+                (ObjectType::Network, PerimeterAction::Excluded) => {
+                    &mut ticket_use.excluded_networks
+                }
+                (object_type, _) => {
+                    tracing::debug!(
+                        "Ticket use {} has a perimeter on an unsupported object type {:?}",
+                        perimeter.ticket_use_id,
+                        object_type
+                    );
+                    continue;
+                }
+            };
+            set.insert(perimeter.object_id.clone());
+        }
+
+        for restriction in collections.ticket_use_restrictions.values() {
+            let Some(&pos) = ticket_use_pos.get(restriction.ticket_use_id.as_str()) else {
+                continue;
+            };
+            let ticket_use = &mut ticket_uses[pos];
+            let origin_destination = (
+                restriction.use_origin.clone(),
+                restriction.use_destination.clone(),
+            );
+            match restriction.restriction_type {
+                RestrictionType::Zone => ticket_use.zone_restrictions.push(origin_destination),
+                RestrictionType::OriginDestination => ticket_use
+                    .origin_destination_restrictions
+                    .push(origin_destination),
+            }
+        }
+
+        Self { ticket_uses }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticket_uses.is_empty()
+    }
+
+    pub fn nb_of_ticket_uses(&self) -> usize {
+        self.ticket_uses.len()
+    }
+}
+
+// What we need to know about a public transport section to price it.
+struct SectionInfo<'model> {
+    line_id: Option<&'model str>,
+    network_id: Option<&'model str>,
+    from_fare_zone: Option<&'model str>,
+    to_fare_zone: Option<&'model str>,
+    from_stop_area: Option<&'model str>,
+    to_stop_area: Option<&'model str>,
+    board_datetime: NaiveDateTime,
+    debark_datetime: NaiveDateTime,
+}
+
+impl TicketUseData {
+    fn accepts(&self, section: &SectionInfo) -> bool {
+        let line_excluded = section
+            .line_id
+            .is_some_and(|line| self.excluded_lines.contains(line));
+        let network_excluded = section
+            .network_id
+            .is_some_and(|network| self.excluded_networks.contains(network));
+        if line_excluded || network_excluded {
+            return false;
+        }
+        if self.included_lines.is_empty() && self.included_networks.is_empty() {
+            return true;
+        }
+        let line_included = section
+            .line_id
+            .is_some_and(|line| self.included_lines.contains(line));
+        let network_included = section
+            .network_id
+            .is_some_and(|network| self.included_networks.contains(network));
+        line_included || network_included
+    }
+
+    // Can this ticket use cover all `sections` with a single ticket ?
+    // `sections` should be accepted by this ticket use.
+    fn can_cover(&self, sections: &[SectionInfo]) -> bool {
+        let (Some(first), Some(last)) = (sections.first(), sections.last()) else {
+            return false;
+        };
+        let nb_of_transfers = sections.len() - 1;
+        if let Some(max_transfers) = self.max_transfers {
+            if nb_of_transfers > max_transfers as usize {
+                return false;
+            }
+        }
+        let within_limit = |datetime: NaiveDateTime, limit: Option<u32>| {
+            limit.is_none_or(|limit| {
+                (datetime - first.board_datetime).num_seconds() <= i64::from(limit)
+            })
+        };
+        if !within_limit(last.board_datetime, self.boarding_time_limit)
+            || !within_limit(last.debark_datetime, self.alighting_time_limit)
+        {
+            return false;
+        }
+
+        if self.zone_restrictions.is_empty() && self.origin_destination_restrictions.is_empty() {
+            return true;
+        }
+        let matches = |restrictions: &[(String, String)],
+                       origin: Option<&str>,
+                       destination: Option<&str>| {
+            restrictions.iter().any(|(use_origin, use_destination)| {
+                origin == Some(use_origin.as_str()) && destination == Some(use_destination.as_str())
+            })
+        };
+        matches(
+            &self.zone_restrictions,
+            first.from_fare_zone,
+            last.to_fare_zone,
+        ) || matches(
+            &self.origin_destination_restrictions,
+            first.from_stop_area,
+            last.to_stop_area,
+        )
+    }
+
+    fn cost_at(&self, date: NaiveDate) -> Option<&Cost> {
+        self.prices
+            .iter()
+            .find(|price| price.validity_start <= date && date <= price.validity_end)
+            .map(|price| &price.cost)
+    }
+}
+
+fn section_info<'model>(
+    section: &VehicleSection,
+    response: &Response,
+    model: &'model ModelRefs<'model>,
+) -> SectionInfo<'model> {
+    let vehicle_journey_idx = &section.vehicle_journey;
+    let (line_id, network_id) = match vehicle_journey_idx {
+        VehicleJourneyIdx::Base(idx) => (model.base.line_name(*idx), model.base.network_name(*idx)),
+        VehicleJourneyIdx::New(_) => (None, None),
+    };
+    let stop_point_at = |stoptime_idx| {
+        model.stop_point_at(
+            vehicle_journey_idx,
+            stoptime_idx,
+            section.day_for_vehicle_journey,
+            response.real_time_level,
+        )
+    };
+    let from_stop = stop_point_at(section.from_stoptime_idx);
+    let to_stop = stop_point_at(section.to_stoptime_idx);
+    let fare_zone = |stop: &Option<StopPointIdx>| stop.as_ref().and_then(|s| model.fare_zone_id(s));
+    let stop_area = |stop: &Option<StopPointIdx>| stop.as_ref().map(|s| model.stop_area_id(s));
+    SectionInfo {
+        line_id,
+        network_id,
+        from_fare_zone: fare_zone(&from_stop),
+        to_fare_zone: fare_zone(&to_stop),
+        from_stop_area: stop_area(&from_stop),
+        to_stop_area: stop_area(&to_stop),
+        board_datetime: section.from_datetime,
+        debark_datetime: section.to_datetime,
+    }
+}
+
+// The best way to price the sections starting at some index.
+#[derive(Clone)]
+struct Pricing {
+    nb_of_unpriced_sections: usize,
+    value: Decimal,
+    // the ticket use covering the first sections, and the index of
+    // the first section not covered by it,
+    // or None if the first section is not priced
+    first_step: Option<(usize, usize)>,
+}
+
+impl Pricing {
+    fn is_better_than(&self, other: &Self) -> bool {
+        (self.nb_of_unpriced_sections, self.value) < (other.nb_of_unpriced_sections, other.value)
+    }
+}
+
+/// Computes the cheapest combination of tickets for `response`.
+pub fn compute_fare(response: &Response, model: &ModelRefs<'_>) -> Fare {
+    let fare_data = model.base.fare_data();
+    let sections: Vec<SectionInfo> = std::iter::once(&response.first_vehicle)
+        .chain(response.connections.iter().map(|(_, _, vehicle)| vehicle))
+        .map(|section| section_info(section, response, model))
+        .collect();
+    let nb_of_sections = sections.len();
+    let date = response.first_vehicle.from_datetime.date();
+
+    let mut currencies = fare_data
+        .ticket_uses
+        .iter()
+        .filter(|ticket_use| sections.iter().any(|section| ticket_use.accepts(section)))
+        .filter_map(|ticket_use| ticket_use.cost_at(date))
+        .map(|cost| cost.currency.as_str());
+    if let Some(currency) = currencies.next() {
+        if let Some(other_currency) = currencies.find(|other| *other != currency) {
+            tracing::debug!(
+                "Cannot price a journey with tickets in {} and in {}",
+                currency,
+                other_currency
+            );
+            return Fare {
+                tickets: Vec::new(),
+                unpriced_sections: (0..nb_of_sections).collect(),
+            };
+        }
+    }
+
+    // best[i] is the best pricing of sections[i..]
+    let mut best: Vec<Pricing> = vec![
+        Pricing {
+            nb_of_unpriced_sections: 0,
+            value: Decimal::ZERO,
+            first_step: None,
+        };
+        nb_of_sections + 1
+    ];
+    for start in (0..nb_of_sections).rev() {
+        let mut best_from_start = Pricing {
+            nb_of_unpriced_sections: best[start + 1].nb_of_unpriced_sections + 1,
+            value: best[start + 1].value,
+            first_step: None,
+        };
+        for (ticket_use_pos, ticket_use) in fare_data.ticket_uses.iter().enumerate() {
+            let Some(cost) = ticket_use.cost_at(date) else {
+                continue;
+            };
+            for end in (start + 1)..=nb_of_sections {
+                if !ticket_use.accepts(&sections[end - 1]) {
+                    break;
+                }
+                if !ticket_use.can_cover(&sections[start..end]) {
+                    continue;
+                }
+                let candidate = Pricing {
+                    nb_of_unpriced_sections: best[end].nb_of_unpriced_sections,
+                    value: best[end].value + cost.value,
+                    first_step: Some((ticket_use_pos, end)),
+                };
+                if candidate.is_better_than(&best_from_start) {
+                    best_from_start = candidate;
+                }
+            }
+        }
+        best[start] = best_from_start;
+    }
+
+    let mut fare = Fare::default();
+    let mut start = 0;
+    while start < nb_of_sections {
+        match best[start].first_step {
+            Some((ticket_use_pos, end)) => {
+                let ticket_use = &fare_data.ticket_uses[ticket_use_pos];
+                // the cost exists since this ticket use was chosen
+                if let Some(cost) = ticket_use.cost_at(date) {
+                    fare.tickets.push(FareTicket {
+                        ticket_id: ticket_use.ticket_id.clone(),
+                        ticket_name: ticket_use.ticket_name.clone(),
+                        cost: cost.clone(),
+                        sections: (start..end).collect(),
+                    });
+                }
+                start = end;
+            }
+            None => {
+                fare.unpriced_sections.push(start);
+                start += 1;
+            }
+        }
+    }
+    fare
+}
@@ -38,17 +38,22 @@ use crate::{
     models::{
         base_model::{
             EquipmentPropertyKey, VehicleJourneyPropertyKey, PREFIX_ID_COMMERCIAL_MODE,
-            PREFIX_ID_LINE, PREFIX_ID_NETWORK, PREFIX_ID_PHYSICAL_MODE, PREFIX_ID_ROUTE,
-            PREFIX_ID_STOP_AREA, PREFIX_ID_STOP_POINT,
+            PREFIX_ID_COMPANY, PREFIX_ID_CONTRIBUTOR, PREFIX_ID_DATASET, PREFIX_ID_EQUIPMENT,
+            PREFIX_ID_HEADSIGN, PREFIX_ID_LINE, PREFIX_ID_NETWORK, PREFIX_ID_PHYSICAL_MODE,
+            PREFIX_ID_ROUTE, PREFIX_ID_STOP_AREA, PREFIX_ID_STOP_POINT, PREFIX_ID_TRIP_SHORT_NAME,
+            PREFIX_ID_VEHICLE_JOURNEY, PREFIX_ID_VEHICLE_JOURNEY_PROPERTY,
         },
         ModelRefs, StopPointIdx, VehicleJourneyIdx,
     },
+    time::PositiveDuration,
     tracing::warn,
 };
 
 pub enum StopFilter<'a> {
     StopPoint(&'a str),
     StopArea(&'a str),
+    /// The stop points that have this equipment.
+    Equipment(EquipmentPropertyKey),
 }
 
 pub enum VehicleFilter<'a> {
@@ -57,6 +62,14 @@ pub enum VehicleFilter<'a> {
     Network(&'a str),
     PhysicalMode(&'a str),
     CommercialMode(&'a str),
+    VehicleJourney(&'a str),
+    Company(&'a str),
+    Dataset(&'a str),
+    Contributor(&'a str),
+    TripShortName(&'a str),
+    Headsign(&'a str),
+    /// The vehicle journeys that have this property.
+    Property(VehicleJourneyPropertyKey),
 }
 
 impl<'a> VehicleFilter<'a> {
@@ -82,6 +95,29 @@ impl<'a> VehicleFilter<'a> {
                 let vj_commercial_mode_id = model.commercial_mode_name(idx);
                 vj_commercial_mode_id == *commercial_mode_id
             }
+            VehicleFilter::VehicleJourney(vehicle_journey_id) => {
+                let vj_id = model.vehicle_journey_name(idx);
+                vj_id == *vehicle_journey_id
+            }
+            VehicleFilter::Company(company_id) => {
+                let vj_company_id = model.company_name(idx);
+                vj_company_id == *company_id
+            }
+            VehicleFilter::Dataset(dataset_id) => {
+                let vj_dataset_id = model.dataset_name(idx);
+                vj_dataset_id == *dataset_id
+            }
+            VehicleFilter::Contributor(contributor_id) => {
+                let vj_contributor_id = model.contributor_name(idx);
+                vj_contributor_id == *contributor_id
+            }
+            VehicleFilter::TripShortName(trip_short_name) => {
+                model.base_trip_short_name(idx) == Some(*trip_short_name)
+            }
+            VehicleFilter::Headsign(headsign) => model.base_headsign(idx) == Some(*headsign),
+            VehicleFilter::Property(property_key) => {
+                model.vehicle_journey_property(idx, *property_key)
+            }
         }
     }
 }
@@ -91,6 +127,7 @@ impl<'a> StopFilter<'a> {
         match self {
             StopFilter::StopPoint(stop_point_id) => *stop_point_id == model.stop_point_id(idx),
             StopFilter::StopArea(stop_area_id) => *stop_area_id == model.stop_area_id(idx),
+            StopFilter::Equipment(property_key) => model.stop_point_property(idx, *property_key),
         }
     }
 }
@@ -105,6 +142,8 @@ pub struct Filters<'a> {
     forbidden_vehicles: Vec<VehicleFilter<'a>>,
     allowed_stops: Vec<StopFilter<'a>>,
     forbidden_stops: Vec<StopFilter<'a>>,
+    vehicle_expression: Option<Expression<VehicleFilter<'a>>>,
+    stop_expression: Option<Expression<StopFilter<'a>>>,
     must_be_wheelchair_accessible: bool,
     must_be_bike_accessible: bool,
 }
@@ -132,6 +171,13 @@ impl<'a> Filters<'a> {
             return false;
         }
 
+        // if the expression does not hold, then the vehicle_journey is invalid
+        if let Some(expression) = &self.vehicle_expression {
+            if !expression.evaluate(&|filter: &VehicleFilter| filter.applies_on(idx, model)) {
+                return false;
+            }
+        }
+
         // if there is no allowed_filter, then the vehicle_journey is valid
         if self.allowed_vehicles.is_empty() {
             return true;
@@ -169,6 +215,13 @@ impl<'a> Filters<'a> {
         {
             return false;
         }
+        // if the expression does not hold, then the idx is invalid
+        if let Some(expression) = &self.stop_expression {
+            if !expression.evaluate(&|filter: &StopFilter| filter.applies_on(idx, model)) {
+                return false;
+            }
+        }
+
         // if there is no allowed_filter, then the idx is valid
         if self.allowed_stops.is_empty() {
             return true;
@@ -190,6 +243,24 @@ impl<'a> Filters<'a> {
         allowed_uri: impl Iterator<Item = Filter<'a>>,
         must_be_wheelchair_accessible: bool,
         must_be_bike_accessible: bool,
+    ) -> Option<Filters<'a>> {
+        Self::new_with_expression(
+            forbidden_uri,
+            allowed_uri,
+            None,
+            must_be_wheelchair_accessible,
+            must_be_bike_accessible,
+        )
+    }
+
+    /// Same as `new()`, but the vehicle journeys (or the stop points) must also
+    /// satisfy `has_expression`.
+    pub fn new_with_expression(
+        forbidden_uri: impl Iterator<Item = Filter<'a>>,
+        allowed_uri: impl Iterator<Item = Filter<'a>>,
+        has_expression: Option<FilterExpression<'a>>,
+        must_be_wheelchair_accessible: bool,
+        must_be_bike_accessible: bool,
     ) -> Option<Filters<'a>> {
         let (allowed_vehicle_filters, allowed_stop_filters) = {
             let mut allowed_vehicle_filters = Vec::new();
@@ -217,10 +288,18 @@ impl<'a> Filters<'a> {
             (forbidden_vehicle_filters, forbidden_stop_filters)
         };
 
+        let (vehicle_expression, stop_expression) = match has_expression {
+            Some(FilterExpression::Vehicle(expression)) => (Some(expression), None),
+            Some(FilterExpression::Stop(expression)) => (None, Some(expression)),
+            None => (None, None),
+        };
+
         let has_no_filter = allowed_stop_filters.is_empty()
             && allowed_vehicle_filters.is_empty()
             && forbidden_stop_filters.is_empty()
             && forbidden_vehicle_filters.is_empty()
+            && vehicle_expression.is_none()
+            && stop_expression.is_none()
             && !must_be_wheelchair_accessible
             && !must_be_bike_accessible;
 
@@ -232,6 +311,8 @@ impl<'a> Filters<'a> {
                 forbidden_stops: forbidden_stop_filters,
                 allowed_vehicles: allowed_vehicle_filters,
                 forbidden_vehicles: forbidden_vehicle_filters,
+                vehicle_expression,
+                stop_expression,
                 must_be_wheelchair_accessible,
                 must_be_bike_accessible,
             };
@@ -240,6 +321,223 @@ impl<'a> Filters<'a> {
     }
 }
 
+/// A boolean combination of filters.
+pub enum Expression<Atom> {
+    Atom(Atom),
+    Not(Box<Expression<Atom>>),
+    And(Box<Expression<Atom>>, Box<Expression<Atom>>),
+    Or(Box<Expression<Atom>>, Box<Expression<Atom>>),
+}
+
+impl<Atom> Expression<Atom> {
+    /// Returns whether the expression holds, given the value of each atom.
+    pub fn evaluate<AtomHolds>(&self, atom_holds: &AtomHolds) -> bool
+    where
+        AtomHolds: Fn(&Atom) -> bool,
+    {
+        match self {
+            Expression::Atom(atom) => atom_holds(atom),
+            Expression::Not(expression) => !expression.evaluate(atom_holds),
+            Expression::And(left, right) => left.evaluate(atom_holds) && right.evaluate(atom_holds),
+            Expression::Or(left, right) => left.evaluate(atom_holds) || right.evaluate(atom_holds),
+        }
+    }
+
+    fn all_atoms<Predicate>(&self, predicate: &Predicate) -> bool
+    where
+        Predicate: Fn(&Atom) -> bool,
+    {
+        match self {
+            Expression::Atom(atom) => predicate(atom),
+            Expression::Not(expression) => expression.all_atoms(predicate),
+            Expression::And(left, right) | Expression::Or(left, right) => {
+                left.all_atoms(predicate) && right.all_atoms(predicate)
+            }
+        }
+    }
+
+    // returns `None` as soon as `convert` fails on an atom
+    fn try_map<Other, Convert>(self, convert: &Convert) -> Option<Expression<Other>>
+    where
+        Convert: Fn(Atom) -> Option<Other>,
+    {
+        let result = match self {
+            Expression::Atom(atom) => Expression::Atom(convert(atom)?),
+            Expression::Not(expression) => Expression::Not(Box::new(expression.try_map(convert)?)),
+            Expression::And(left, right) => Expression::And(
+                Box::new(left.try_map(convert)?),
+                Box::new(right.try_map(convert)?),
+            ),
+            Expression::Or(left, right) => Expression::Or(
+                Box::new(left.try_map(convert)?),
+                Box::new(right.try_map(convert)?),
+            ),
+        };
+        Some(result)
+    }
+}
+
+/// A boolean expression over filters, like `network:A AND physical_mode:Bus`
+/// or `NOT (line:1 OR line:2)`.
+///
+/// An expression combines either vehicle filters or stop filters, but not both.
+pub enum FilterExpression<'a> {
+    Vehicle(Expression<VehicleFilter<'a>>),
+    Stop(Expression<StopFilter<'a>>),
+}
+
+#[derive(Debug)]
+pub enum FilterExpressionError {
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    InvalidFilter(String),
+    MixedStopAndVehicleFilters,
+}
+
+impl std::fmt::Display for FilterExpressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterExpressionError::UnexpectedEnd => {
+                write!(f, "The filter expression ends unexpectedly.")
+            }
+            FilterExpressionError::UnexpectedToken(token) => {
+                write!(f, "Unexpected {} in the filter expression.", token)
+            }
+            FilterExpressionError::InvalidFilter(filter) => {
+                write!(f, "Invalid filter {} in the filter expression.", filter)
+            }
+            FilterExpressionError::MixedStopAndVehicleFilters => write!(
+                f,
+                "A filter expression cannot combine stop filters with vehicle filters."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FilterExpressionError {}
+
+/// Parses a boolean expression over the filters accepted by `parse_filter()`.
+///
+/// The operators are `NOT`, `AND` and `OR`, by decreasing precedence,
+/// and parentheses can be used for grouping. Filters are separated
+/// from operators by whitespaces, and cannot contain whitespaces nor parentheses.
+pub fn parse_filter_expression<'a>(
+    model: &ModelRefs<'_>,
+    expression_str: &'a str,
+    filter_provenance: &str,
+) -> Result<FilterExpression<'a>, FilterExpressionError> {
+    let mut parser = ExpressionParser {
+        tokens: tokenize(expression_str).peekable(),
+        model,
+        filter_provenance,
+    };
+    let expression = parser.parse_or()?;
+    if let Some(token) = parser.tokens.next() {
+        return Err(FilterExpressionError::UnexpectedToken(token.to_string()));
+    }
+    let is_vehicle_expression =
+        expression.all_atoms(&|filter| matches!(filter, Filter::Vehicle(_)));
+    let has_expression = if is_vehicle_expression {
+        expression
+            .try_map(&|filter| match filter {
+                Filter::Vehicle(vehicle_filter) => Some(vehicle_filter),
+                Filter::Stop(_) => None,
+            })
+            .map(FilterExpression::Vehicle)
+    } else {
+        expression
+            .try_map(&|filter| match filter {
+                Filter::Stop(stop_filter) => Some(stop_filter),
+                Filter::Vehicle(_) => None,
+            })
+            .map(FilterExpression::Stop)
+    };
+    has_expression.ok_or(FilterExpressionError::MixedStopAndVehicleFilters)
+}
+
+// splits `expression_str` on whitespaces, with each parenthesis as a separate token
+fn tokenize(expression_str: &str) -> impl Iterator<Item = &str> {
+    expression_str
+        .split_whitespace()
+        .flat_map(|word| word.split_inclusive(['(', ')']))
+        .flat_map(|piece| match piece.strip_suffix(['(', ')']) {
+            Some(prefix) => [prefix, &piece[prefix.len()..]],
+            None => [piece, ""],
+        })
+        .filter(|token| !token.is_empty())
+}
+
+struct ExpressionParser<'a, 'model, 'provenance, Tokens: Iterator<Item = &'a str>> {
+    tokens: std::iter::Peekable<Tokens>,
+    model: &'model ModelRefs<'model>,
+    filter_provenance: &'provenance str,
+}
+
+impl<'a, 'model, 'provenance, Tokens> ExpressionParser<'a, 'model, 'provenance, Tokens>
+where
+    Tokens: Iterator<Item = &'a str>,
+{
+    // consumes the next token if it is the operator `keyword`
+    fn next_is(&mut self, keyword: &str) -> bool {
+        self.tokens
+            .next_if(|token| token.eq_ignore_ascii_case(keyword))
+            .is_some()
+    }
+
+    fn parse_or(&mut self) -> Result<Expression<Filter<'a>>, FilterExpressionError> {
+        let mut expression = self.parse_and()?;
+        while self.next_is("OR") {
+            let right = self.parse_and()?;
+            expression = Expression::Or(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn parse_and(&mut self) -> Result<Expression<Filter<'a>>, FilterExpressionError> {
+        let mut expression = self.parse_not()?;
+        while self.next_is("AND") {
+            let right = self.parse_not()?;
+            expression = Expression::And(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn parse_not(&mut self) -> Result<Expression<Filter<'a>>, FilterExpressionError> {
+        if self.next_is("NOT") {
+            let expression = self.parse_not()?;
+            return Ok(Expression::Not(Box::new(expression)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression<Filter<'a>>, FilterExpressionError> {
+        let token = self
+            .tokens
+            .next()
+            .ok_or(FilterExpressionError::UnexpectedEnd)?;
+        match token {
+            "(" => {
+                let expression = self.parse_or()?;
+                match self.tokens.next() {
+                    Some(")") => Ok(expression),
+                    Some(token) => Err(FilterExpressionError::UnexpectedToken(token.to_string())),
+                    None => Err(FilterExpressionError::UnexpectedEnd),
+                }
+            }
+            ")" => Err(FilterExpressionError::UnexpectedToken(token.to_string())),
+            _ if ["AND", "OR", "NOT"]
+                .iter()
+                .any(|keyword| token.eq_ignore_ascii_case(keyword)) =>
+            {
+                Err(FilterExpressionError::UnexpectedToken(token.to_string()))
+            }
+            _ => parse_filter(self.model, token, self.filter_provenance)
+                .map(Expression::Atom)
+                .ok_or_else(|| FilterExpressionError::InvalidFilter(token.to_string())),
+        }
+    }
+}
+
 pub fn parse_filter<'a>(
     model: &ModelRefs<'_>,
     filter_str: &'a str,
@@ -327,9 +625,219 @@ pub fn parse_filter<'a>(
         return None;
     }
 
+    if let Some(vehicle_journey_id) = filter_str.strip_prefix(PREFIX_ID_VEHICLE_JOURNEY) {
+        if model.contains_vehicle_journey_id(vehicle_journey_id) {
+            let filter = Filter::Vehicle(VehicleFilter::VehicleJourney(vehicle_journey_id));
+            return Some(filter);
+        }
+        warn!(
+            "Unknown vehicle_journey id {} in {} filter {}. I'll ignore it.",
+            vehicle_journey_id, filter_provenance, filter_str
+        );
+        return None;
+    }
+
+    if let Some(company_id) = filter_str.strip_prefix(PREFIX_ID_COMPANY) {
+        if model.contains_company_id(company_id) {
+            let filter = Filter::Vehicle(VehicleFilter::Company(company_id));
+            return Some(filter);
+        }
+        warn!(
+            "Unknown company id {} in {} filter {}. I'll ignore it.",
+            company_id, filter_provenance, filter_str
+        );
+        return None;
+    }
+
+    if let Some(dataset_id) = filter_str.strip_prefix(PREFIX_ID_DATASET) {
+        if model.contains_dataset_id(dataset_id) {
+            let filter = Filter::Vehicle(VehicleFilter::Dataset(dataset_id));
+            return Some(filter);
+        }
+        warn!(
+            "Unknown dataset id {} in {} filter {}. I'll ignore it.",
+            dataset_id, filter_provenance, filter_str
+        );
+        return None;
+    }
+
+    if let Some(contributor_id) = filter_str.strip_prefix(PREFIX_ID_CONTRIBUTOR) {
+        if model.contains_contributor_id(contributor_id) {
+            let filter = Filter::Vehicle(VehicleFilter::Contributor(contributor_id));
+            return Some(filter);
+        }
+        warn!(
+            "Unknown contributor id {} in {} filter {}. I'll ignore it.",
+            contributor_id, filter_provenance, filter_str
+        );
+        return None;
+    }
+
+    // trip short names and headsigns are free texts, so they are not checked against the model
+    if let Some(trip_short_name) = filter_str.strip_prefix(PREFIX_ID_TRIP_SHORT_NAME) {
+        let filter = Filter::Vehicle(VehicleFilter::TripShortName(trip_short_name));
+        return Some(filter);
+    }
+
+    if let Some(headsign) = filter_str.strip_prefix(PREFIX_ID_HEADSIGN) {
+        let filter = Filter::Vehicle(VehicleFilter::Headsign(headsign));
+        return Some(filter);
+    }
+
+    if let Some(property_name) = filter_str.strip_prefix(PREFIX_ID_VEHICLE_JOURNEY_PROPERTY) {
+        if let Some(property_key) = VehicleJourneyPropertyKey::from_name(property_name) {
+            let filter = Filter::Vehicle(VehicleFilter::Property(property_key));
+            return Some(filter);
+        }
+        warn!(
+            "Unknown vehicle_journey property {} in {} filter {}. I'll ignore it.",
+            property_name, filter_provenance, filter_str
+        );
+        return None;
+    }
+
+    if let Some(equipment_name) = filter_str.strip_prefix(PREFIX_ID_EQUIPMENT) {
+        if let Some(property_key) = EquipmentPropertyKey::from_name(equipment_name) {
+            let filter = Filter::Stop(StopFilter::Equipment(property_key));
+            return Some(filter);
+        }
+        warn!(
+            "Unknown equipment {} in {} filter {}. I'll ignore it.",
+            equipment_name, filter_provenance, filter_str
+        );
+        return None;
+    }
+
     warn!(
         "Invalid {} filter : {}. I'll ignore it.",
         filter_provenance, filter_str
     );
     None
 }
+
+/// A soft preference for the vehicles matched by `uri`, which uses the syntax
+/// of the forbidden and allowed uris, like `line:38` or `physical_mode:Tramway`.
+/// Only lines, routes, networks, physical and commercial modes can be used.
+///
+/// A positive `weight` is a number of seconds added to the arrival time of a journey
+/// each time it boards a matched vehicle, but at most the max journey duration of the request.
+/// A negative `weight` favors the matched vehicles : each time a journey boards one of them,
+/// `-weight` seconds are removed from the arrival time, but at most the leg arrival penalty
+/// of the request. Hence favoring a vehicle can only cancel the penalty of boarding it,
+/// and the journeys that board no favored vehicle are left unchanged.
+#[derive(Debug, Clone)]
+pub struct Preference {
+    pub uri: String,
+    pub weight: i32,
+}
+
+/// The weight of boarding each vehicle journey, obtained from a list of `Preference`.
+///
+/// When a vehicle journey is matched by several preferences, their weights are summed.
+///
+/// It should be filled once per query, with `fill()`, and then shared by
+/// all the requests solved for this query.
+#[derive(Debug, Clone, Default)]
+pub struct VehiclePenalties {
+    base_vehicle_journeys: Vec<i64>,
+    new_vehicle_journeys: Vec<i64>,
+}
+
+impl VehiclePenalties {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fill(&mut self, preferences: &[Preference], model: &ModelRefs<'_>) {
+        self.base_vehicle_journeys.clear();
+        self.new_vehicle_journeys.clear();
+
+        let weighted_filters: Vec<_> = preferences
+            .iter()
+            .filter_map(|preference| {
+                parse_preference(model, &preference.uri)
+                    .map(|filter| (filter, i64::from(preference.weight)))
+            })
+            .collect();
+        if weighted_filters.is_empty() {
+            return;
+        }
+        let weight_of = |idx: &VehicleJourneyIdx| -> i64 {
+            weighted_filters
+                .iter()
+                .filter(|(filter, _)| filter.applies_on(idx, model))
+                .map(|(_, weight)| weight)
+                .sum()
+        };
+
+        self.base_vehicle_journeys
+            .resize(model.nb_of_base_vehicle_journeys(), 0);
+        for idx in model.base_vehicle_journeys() {
+            self.base_vehicle_journeys[idx.get()] = weight_of(&VehicleJourneyIdx::Base(idx));
+        }
+        self.new_vehicle_journeys
+            .resize(model.nb_of_new_vehicle_journeys(), 0);
+        for idx in model.new_vehicle_journeys() {
+            self.new_vehicle_journeys[idx.idx] = weight_of(&VehicleJourneyIdx::New(idx));
+        }
+    }
+
+    /// Returns `true` when no vehicle journey is penalized or favored.
+    pub fn is_empty(&self) -> bool {
+        self.base_vehicle_journeys.is_empty() && self.new_vehicle_journeys.is_empty()
+    }
+
+    /// Returns the penalty and the bonus of boarding the vehicle journey.
+    ///
+    /// At most one of them is non-zero, the penalty is at most `max_penalty`
+    /// and the bonus is at most `max_bonus`.
+    pub fn penalty_and_bonus(
+        &self,
+        vehicle_journey_idx: &VehicleJourneyIdx,
+        max_penalty: PositiveDuration,
+        max_bonus: PositiveDuration,
+    ) -> (PositiveDuration, PositiveDuration) {
+        let has_weight = match vehicle_journey_idx {
+            VehicleJourneyIdx::Base(idx) => self.base_vehicle_journeys.get(idx.get()),
+            VehicleJourneyIdx::New(idx) => self.new_vehicle_journeys.get(idx.idx),
+        };
+        let weight = has_weight.copied().unwrap_or(0);
+        let seconds = u32::try_from(weight.unsigned_abs()).unwrap_or(u32::MAX);
+        let duration = PositiveDuration::from_hms(0, 0, seconds);
+        if weight >= 0 {
+            (duration.min(max_penalty), PositiveDuration::zero())
+        } else {
+            (PositiveDuration::zero(), duration.min(max_bonus))
+        }
+    }
+}
+
+pub fn parse_preference<'a>(
+    model: &ModelRefs<'_>,
+    preference_str: &'a str,
+) -> Option<VehicleFilter<'a>> {
+    match parse_filter(model, preference_str, "preference")? {
+        Filter::Vehicle(
+            vehicle_filter @ (VehicleFilter::Line(_)
+            | VehicleFilter::Route(_)
+            | VehicleFilter::Network(_)
+            | VehicleFilter::PhysicalMode(_)
+            | VehicleFilter::CommercialMode(_)),
+        ) => Some(vehicle_filter),
+        Filter::Vehicle(_) => {
+            warn!(
+                "Only lines, routes, networks, physical and commercial modes can be used \
+                as a preference, not {}. I'll ignore it.",
+                preference_str
+            );
+            None
+        }
+        Filter::Stop(_) => {
+            warn!(
+                "Stop filter {} cannot be used as a preference. I'll ignore it.",
+                preference_str
+            );
+            None
+        }
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use crate::{
+    models::{ModelRefs, StopPointIdx},
+    request::generic_request::{Criteria, RequestTypes},
+    time::SecondsSinceDatasetUTCStart,
+    transit_data::data_interface::Data as DataTrait,
+    MultiCriteriaRaptor,
+};
+use chrono::NaiveDateTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsochroneOn {
+    /// keep the earliest arrival time at each stop
+    ArrivalTimes,
+    /// keep the latest departure time from each stop
+    DepartureTimes,
+}
+
+#[derive(Debug, Clone)]
+pub struct IsochroneResponse {
+    pub stop_point_idx: StopPointIdx,
+    pub datetime: NaiveDateTime,
+    pub nb_of_legs: u8,
+}
+
+/// Extracts, for every stop reached by `engine` after a call to
+/// `MultiCriteriaRaptor::compute_one_to_all()`, the best datetime obtained at this stop,
+/// along with the smallest number of legs needed to obtain this datetime.
+///
+/// The responses are sorted from the best datetime to the worst one.
+pub fn isochrone_responses<Data>(
+    engine: &MultiCriteriaRaptor<RequestTypes>,
+    data: &Data,
+    model: &ModelRefs<'_>,
+    isochrone_on: IsochroneOn,
+) -> Vec<IsochroneResponse>
+where
+    Data: DataTrait<Stop = <RequestTypes as crate::TransitTypes>::Stop>,
+{
+    let is_better = |lhs: &Criteria, rhs: &Criteria| {
+        let (lhs_time, rhs_time) = (lhs.time(), rhs.time());
+        let strictly_better = match isochrone_on {
+            IsochroneOn::ArrivalTimes => lhs_time < rhs_time,
+            IsochroneOn::DepartureTimes => lhs_time > rhs_time,
+        };
+        strictly_better || (lhs_time == rhs_time && lhs.nb_of_legs() < rhs.nb_of_legs())
+    };
+
+    let stop_point_idxs = model
+        .base_stop_points()
+        .map(StopPointIdx::Base)
+        .chain(model.new_stops().map(StopPointIdx::New));
+
+    let mut responses = Vec::new();
+    for stop_point_idx in stop_point_idxs {
+        let stop = match data.stop_point_idx_to_stop(&stop_point_idx) {
+            Some(stop) => stop,
+            None => continue,
+        };
+        let stop_id = data.stop_id(&stop);
+        let mut best: Option<&Criteria> = None;
+        for criteria in engine.criteria_at_stop(stop_id) {
+            match best {
+                Some(best_criteria) if !is_better(criteria, best_criteria) => (),
+                _ => best = Some(criteria),
+            }
+        }
+        if let Some(criteria) = best {
+            responses.push((stop_point_idx, criteria.time(), criteria.nb_of_legs()));
+        }
+    }
+
+    match isochrone_on {
+        IsochroneOn::ArrivalTimes => responses.sort_by_key(|(_, time, _)| *time),
+        IsochroneOn::DepartureTimes => {
+            responses.sort_by_key(|(_, time, _)| std::cmp::Reverse(*time))
+        }
+    }
+
+    responses
+        .into_iter()
+        .map(
+            |(stop_point_idx, time, nb_of_legs): (_, SecondsSinceDatasetUTCStart, _)| {
+                IsochroneResponse {
+                    stop_point_idx,
+                    datetime: data.calendar().to_naive_datetime(time),
+                    nb_of_legs,
+                }
+            },
+        )
+        .collect()
+}
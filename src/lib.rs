@@ -39,6 +39,8 @@ extern crate static_assertions;
 mod engine;
 pub mod filters;
 pub mod geometry;
+pub mod isochrone;
+pub mod matrix;
 pub mod models;
 pub mod occupancy_data;
 pub mod places_nearby;
@@ -71,11 +73,25 @@ pub use engine::engine_interface::{
 
 pub use engine::multicriteria_raptor::MultiCriteriaRaptor;
 
+pub use engine::connection_scan::ConnectionScan;
+pub use engine::trip_based::TripBased;
+
+pub use engine::cancellation::{Cancellation, CancellationToken, ComputeStatus};
+
+pub use engine::engine_stats::EngineStats;
+
+pub use engine::search_trace::{
+    RoundTrace, SearchTrace, TraceDecision, TraceEvent, TraceEventKind,
+};
+
 pub mod response;
 
 pub type Response = response::Response;
 
 pub mod robustness;
 
+pub mod fares;
+pub use rust_decimal;
+
 #[macro_use]
 extern crate lazy_static;
@@ -0,0 +1,207 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use crate::{
+    engine::engine_interface::{BadRequest, InputStop, RequestInput},
+    filters::VehiclePenalties,
+    models::ModelRefs,
+    request::{
+        depart_after,
+        generic_request::{self, RequestTypes},
+    },
+    time::{PositiveDuration, SecondsSinceDatasetUTCStart},
+    transit_data::data_interface::{Data as DataTrait, DataWithIters},
+    MultiCriteriaRaptor,
+};
+use chrono::NaiveDateTime;
+use tracing::warn;
+
+pub type InputStops = Vec<(InputStop, PositiveDuration)>;
+
+#[derive(Debug, Clone)]
+pub struct MatrixCell {
+    pub arrival_datetime: NaiveDateTime,
+    pub duration: PositiveDuration,
+    pub nb_of_transfers: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatrixResponse {
+    nb_of_origins: usize,
+    nb_of_destinations: usize,
+    // cells of the origin with index `o` are stored
+    // at indices `o * nb_of_destinations..(o+1) * nb_of_destinations`
+    cells: Vec<Option<MatrixCell>>,
+}
+
+impl MatrixResponse {
+    pub fn nb_of_origins(&self) -> usize {
+        self.nb_of_origins
+    }
+
+    pub fn nb_of_destinations(&self) -> usize {
+        self.nb_of_destinations
+    }
+
+    /// Returns `None` if the destination cannot be reached from the origin.
+    ///
+    /// Panics if `origin_idx >= nb_of_origins()` or `destination_idx >= nb_of_destinations()`.
+    pub fn cell(&self, origin_idx: usize, destination_idx: usize) -> Option<&MatrixCell> {
+        assert!(origin_idx < self.nb_of_origins);
+        assert!(destination_idx < self.nb_of_destinations);
+        self.cells[origin_idx * self.nb_of_destinations + destination_idx].as_ref()
+    }
+}
+
+/// Computes the earliest arrival at each destination, for each origin, when departing
+/// at `request_input.datetime`.
+///
+/// Only one search is performed for each origin : all stops reachable from the origin
+/// are explored, and the destinations are then read from the labels obtained at their stops.
+/// The other parameters of `request_input` are used for all searches, but its departures
+/// and arrivals are ignored.
+pub fn solve_matrix_request<Data>(
+    engine: &mut MultiCriteriaRaptor<RequestTypes>,
+    data: &Data,
+    model: &ModelRefs<'_>,
+    request_input: &RequestInput,
+    origins: &[InputStops],
+    destinations: &[InputStops],
+) -> Result<MatrixResponse, BadRequest>
+where
+    Data: DataWithIters<
+        Position = generic_request::Position,
+        Mission = generic_request::Mission,
+        Stop = generic_request::Stop,
+        Trip = generic_request::Trip,
+        Transfer = generic_request::Transfer,
+    >,
+{
+    let departure_time = data
+        .calendar()
+        .from_naive_datetime(&request_input.datetime)
+        .ok_or(BadRequest::RequestedDatetime)?;
+
+    let destinations_stops: Vec<Vec<_>> = destinations
+        .iter()
+        .map(|destination| {
+            generic_request::parse_input_stops(destination, model, data, "destination")
+                .into_iter()
+                .map(|(stop, fallback_duration)| (data.stop_id(&stop), fallback_duration))
+                .collect()
+        })
+        .collect();
+
+    // the same for every origin
+    let mut vehicle_penalties = VehiclePenalties::new();
+    vehicle_penalties.fill(&request_input.preferences, model);
+
+    let mut cells = Vec::with_capacity(origins.len() * destinations.len());
+    let mut origin_request_input = request_input.clone();
+    for (origin_idx, origin) in origins.iter().enumerate() {
+        origin_request_input
+            .departures_stop_and_fallback_duration
+            .clone_from(origin);
+        let request = match depart_after::Request::new_one_to_all(
+            model,
+            data,
+            &origin_request_input,
+            &vehicle_penalties,
+        ) {
+            Ok(request) => request,
+            Err(BadRequest::NoValidDepartureStop) => {
+                warn!("The {origin_idx}th origin of the matrix has no valid stop. I ignore it.");
+                cells.extend(std::iter::repeat_n(None, destinations.len()));
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        engine.compute_one_to_all(&request);
+
+        for destination_stops in destinations_stops.iter() {
+            let cell = best_cell(
+                engine,
+                destination_stops,
+                departure_time,
+                request_input.max_journey_duration,
+                data,
+            );
+            cells.push(cell);
+        }
+    }
+
+    Ok(MatrixResponse {
+        nb_of_origins: origins.len(),
+        nb_of_destinations: destinations.len(),
+        cells,
+    })
+}
+
+fn best_cell<Data: DataTrait>(
+    engine: &MultiCriteriaRaptor<RequestTypes>,
+    destination_stops: &[(usize, PositiveDuration)],
+    departure_time: SecondsSinceDatasetUTCStart,
+    max_journey_duration: PositiveDuration,
+    data: &Data,
+) -> Option<MatrixCell> {
+    let mut best: Option<(SecondsSinceDatasetUTCStart, u8)> = None;
+    for (stop_id, fallback_duration) in destination_stops {
+        for criteria in engine.criteria_at_stop(*stop_id) {
+            let arrival_time = criteria.time() + *fallback_duration;
+            let nb_of_legs = criteria.nb_of_legs();
+            let is_better = match best {
+                None => true,
+                Some((best_time, best_nb_of_legs)) => {
+                    arrival_time < best_time
+                        || (arrival_time == best_time && nb_of_legs < best_nb_of_legs)
+                }
+            };
+            if is_better {
+                best = Some((arrival_time, nb_of_legs));
+            }
+        }
+    }
+    let (arrival_time, nb_of_legs) = best?;
+    let duration = arrival_time.duration_since(&departure_time)?;
+    if duration > max_journey_duration {
+        return None;
+    }
+    Some(MatrixCell {
+        arrival_datetime: data.calendar().to_naive_datetime(arrival_time),
+        duration,
+        nb_of_transfers: nb_of_legs.saturating_sub(1),
+    })
+}
@@ -48,6 +48,7 @@ use transit_model::objects::{
 use typed_index_collection::Idx;
 
 use crate::{
+    fares::FareData,
     time::{calendar, SecondsSinceTimezonedDayStart},
     timetables::FlowDirection,
     OccupancyData, PositiveDuration,
@@ -67,6 +68,13 @@ pub const PREFIX_ID_STOP_POINT: &str = "stop_point:";
 pub const PREFIX_ID_COMMERCIAL_MODE: &str = "commercial_mode:";
 pub const PREFIX_ID_PHYSICAL_MODE: &str = "physical_mode:";
 pub const PREFIX_ID_COORD: &str = "coord:";
+pub const PREFIX_ID_COMPANY: &str = "company:";
+pub const PREFIX_ID_DATASET: &str = "dataset:";
+pub const PREFIX_ID_CONTRIBUTOR: &str = "contributor:";
+pub const PREFIX_ID_TRIP_SHORT_NAME: &str = "trip_short_name:";
+pub const PREFIX_ID_HEADSIGN: &str = "headsign:";
+pub const PREFIX_ID_VEHICLE_JOURNEY_PROPERTY: &str = "vehicle_journey_property:";
+pub const PREFIX_ID_EQUIPMENT: &str = "equipment:";
 
 pub fn strip_id_prefix<'a>(id: &'a str, prefix: &str) -> &'a str {
     id.strip_prefix(prefix).unwrap_or(id)
@@ -85,6 +93,7 @@ pub struct BaseModel {
     validity_period: (NaiveDate, NaiveDate),
     default_transfer_duration: PositiveDuration,
     stop_point_to_pathways: StopPointToPathWays,
+    fare_data: FareData,
 }
 
 pub type BaseVehicleJourneyIdx = Idx<transit_model::objects::VehicleJourney>;
@@ -125,6 +134,76 @@ pub enum EquipmentPropertyKey {
     AppropriateSignage,
 }
 
+const VEHICLE_JOURNEY_PROPERTY_KEYS: [VehicleJourneyPropertyKey; 8] = [
+    VehicleJourneyPropertyKey::WheelChairAccessible,
+    VehicleJourneyPropertyKey::BikeAccepted,
+    VehicleJourneyPropertyKey::AirConditioned,
+    VehicleJourneyPropertyKey::VisualAnnouncement,
+    VehicleJourneyPropertyKey::AudibleAnnouncement,
+    VehicleJourneyPropertyKey::AppropriateEscort,
+    VehicleJourneyPropertyKey::AppropriateSignage,
+    VehicleJourneyPropertyKey::SchoolVehicle,
+];
+
+impl VehicleJourneyPropertyKey {
+    /// The key of the property in the data, like `air_conditioned`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            VehicleJourneyPropertyKey::WheelChairAccessible => "wheelchair_accessible",
+            VehicleJourneyPropertyKey::BikeAccepted => "bike_accepted",
+            VehicleJourneyPropertyKey::AirConditioned => "air_conditioned",
+            VehicleJourneyPropertyKey::VisualAnnouncement => "visual_announcement",
+            VehicleJourneyPropertyKey::AudibleAnnouncement => "audible_announcement",
+            VehicleJourneyPropertyKey::AppropriateEscort => "appropriate_escort",
+            VehicleJourneyPropertyKey::AppropriateSignage => "appropriate_signage",
+            VehicleJourneyPropertyKey::SchoolVehicle => "school_vehicle_type",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        VEHICLE_JOURNEY_PROPERTY_KEYS
+            .into_iter()
+            .find(|key| key.name() == name)
+    }
+}
+
+const EQUIPMENT_PROPERTY_KEYS: [EquipmentPropertyKey; 10] = [
+    EquipmentPropertyKey::WheelChairBoarding,
+    EquipmentPropertyKey::Sheltered,
+    EquipmentPropertyKey::Elevator,
+    EquipmentPropertyKey::Escalator,
+    EquipmentPropertyKey::BikeAccepted,
+    EquipmentPropertyKey::BikeDepot,
+    EquipmentPropertyKey::VisualAnnouncement,
+    EquipmentPropertyKey::AudibleAnnouncement,
+    EquipmentPropertyKey::AppropriateEscort,
+    EquipmentPropertyKey::AppropriateSignage,
+];
+
+impl EquipmentPropertyKey {
+    /// The name of the equipment, like `elevator`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EquipmentPropertyKey::WheelChairBoarding => "wheelchair_boarding",
+            EquipmentPropertyKey::Sheltered => "sheltered",
+            EquipmentPropertyKey::Elevator => "elevator",
+            EquipmentPropertyKey::Escalator => "escalator",
+            EquipmentPropertyKey::BikeAccepted => "bike_accepted",
+            EquipmentPropertyKey::BikeDepot => "bike_depot",
+            EquipmentPropertyKey::VisualAnnouncement => "visual_announcement",
+            EquipmentPropertyKey::AudibleAnnouncement => "audible_announcement",
+            EquipmentPropertyKey::AppropriateEscort => "appropriate_escort",
+            EquipmentPropertyKey::AppropriateSignage => "appropriate_signage",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        EQUIPMENT_PROPERTY_KEYS
+            .into_iter()
+            .find(|key| key.name() == name)
+    }
+}
+
 impl BaseModel {
     pub fn from_transit_model(
         model: transit_model::Model,
@@ -219,12 +298,16 @@ impl BaseModel {
         // Associate stop_points with path way
         let stop_point_to_pathways = Self::associate_stop_points_with_pathway(&model);
 
+        let fare_data = FareData::new(&model);
+        info!("{} ticket uses loaded", fare_data.nb_of_ticket_uses());
+
         Ok(Self {
             model,
             occupancy_data,
             validity_period,
             default_transfer_duration,
             stop_point_to_pathways,
+            fare_data,
         })
     }
 
@@ -236,6 +319,10 @@ impl BaseModel {
         self.validity_period
     }
 
+    pub fn fare_data(&self) -> &FareData {
+        &self.fare_data
+    }
+
     pub fn time_period(&self) -> TimePeriod {
         let start_datetime = self.validity_period.0.and_hms_opt(0, 0, 0).unwrap(); // unwrap is safe since 00:00:00 is a valid NaiveTime
         let end_datetime = self.validity_period.1.and_hms_opt(0, 0, 0).unwrap() // unwrap is safe since 00:00:00 is a valid NaiveTime
@@ -514,6 +601,32 @@ impl BaseModel {
         &self.model.vehicle_journeys[vehicle_journey_idx].id
     }
 
+    pub fn company_name(&self, vehicle_journey_idx: BaseVehicleJourneyIdx) -> &str {
+        &self.model.vehicle_journeys[vehicle_journey_idx].company_id
+    }
+
+    pub fn dataset_name(&self, vehicle_journey_idx: BaseVehicleJourneyIdx) -> &str {
+        &self.model.vehicle_journeys[vehicle_journey_idx].dataset_id
+    }
+
+    pub fn contributor_name(&self, vehicle_journey_idx: BaseVehicleJourneyIdx) -> Option<&str> {
+        let dataset_id = &self.model.vehicle_journeys[vehicle_journey_idx].dataset_id;
+        self.model
+            .datasets
+            .get(dataset_id)
+            .map(|dataset| dataset.contributor_id.as_str())
+    }
+
+    pub fn stop_points_of_vehicle_journey(
+        &self,
+        vehicle_journey_idx: BaseVehicleJourneyIdx,
+    ) -> impl Iterator<Item = BaseStopPointIdx> + '_ {
+        self.model.vehicle_journeys[vehicle_journey_idx]
+            .stop_times
+            .iter()
+            .map(|stop_time| stop_time.stop_point_idx)
+    }
+
     pub fn timezone(&self, idx: BaseVehicleJourneyIdx) -> Option<chrono_tz::Tz> {
         let line = self.vehicle_journey_line(idx)?;
         let network = self.model.networks.get(&line.network_id)?;
@@ -557,18 +670,8 @@ impl BaseModel {
         idx: BaseVehicleJourneyIdx,
         property_key: VehicleJourneyPropertyKey,
     ) -> bool {
-        let string_key = match property_key {
-            VehicleJourneyPropertyKey::WheelChairAccessible => "wheelchair_accessible",
-            VehicleJourneyPropertyKey::BikeAccepted => "bike_accepted",
-            VehicleJourneyPropertyKey::AirConditioned => "air_conditioned",
-            VehicleJourneyPropertyKey::VisualAnnouncement => "visual_announcement",
-            VehicleJourneyPropertyKey::AudibleAnnouncement => "audible_announcement",
-            VehicleJourneyPropertyKey::AppropriateEscort => "appropriate_escort",
-            VehicleJourneyPropertyKey::AppropriateSignage => "appropriate_signage",
-            VehicleJourneyPropertyKey::SchoolVehicle => "school_vehicle_type",
-        };
         let properties = &self.model.vehicle_journeys[idx].properties();
-        let value = properties.get(string_key);
+        let value = properties.get(property_key.name());
         match value {
             Some(value) => matches!(value.as_str(), "1"), // return true only if value == "1"
             None => false,
@@ -740,6 +843,22 @@ impl BaseModel {
     pub fn contains_stop_area_id(&self, id: &str) -> bool {
         self.model.stop_areas.contains_id(id)
     }
+
+    pub fn contains_vehicle_journey_id(&self, id: &str) -> bool {
+        self.model.vehicle_journeys.contains_id(id)
+    }
+
+    pub fn contains_company_id(&self, id: &str) -> bool {
+        self.model.companies.contains_id(id)
+    }
+
+    pub fn contains_dataset_id(&self, id: &str) -> bool {
+        self.model.datasets.contains_id(id)
+    }
+
+    pub fn contains_contributor_id(&self, id: &str) -> bool {
+        self.model.contributors.contains_id(id)
+    }
 }
 
 // stop_times
@@ -36,6 +36,7 @@
 
 use crate::{
     chrono::NaiveDate,
+    geometry::distance_coord_to_coord,
     models::{
         base_model::{
             BaseTransferIdx, EquipmentPropertyKey, PathwayByIter, VehicleJourneyPropertyKey,
@@ -45,6 +46,7 @@ use crate::{
     },
     RealTimeLevel,
 };
+use std::collections::BTreeSet;
 use transit_model::objects::{
     CommercialMode, Equipment, Line, Network, PhysicalMode, Route, StopArea, VehicleJourney,
 };
@@ -198,6 +200,47 @@ impl<'model> ModelRefs<'model> {
         }
     }
 
+    pub fn company_name(&self, vehicle_journey_idx: &VehicleJourneyIdx) -> &str {
+        match vehicle_journey_idx {
+            VehicleJourneyIdx::Base(idx) => self.base.company_name(*idx),
+            VehicleJourneyIdx::New(_idx) => "unknown_company",
+        }
+    }
+
+    pub fn dataset_name(&self, vehicle_journey_idx: &VehicleJourneyIdx) -> &str {
+        match vehicle_journey_idx {
+            VehicleJourneyIdx::Base(idx) => self.base.dataset_name(*idx),
+            VehicleJourneyIdx::New(_idx) => "unknown_dataset",
+        }
+    }
+
+    pub fn contributor_name(&self, vehicle_journey_idx: &VehicleJourneyIdx) -> &str {
+        let unknown_contributor = "unknown_contributor";
+        match vehicle_journey_idx {
+            VehicleJourneyIdx::Base(idx) => self
+                .base
+                .contributor_name(*idx)
+                .unwrap_or(unknown_contributor),
+            VehicleJourneyIdx::New(_idx) => unknown_contributor,
+        }
+    }
+
+    /// The trip short name (or headsign) of the vehicle journey in the base schedule.
+    pub fn base_trip_short_name(&self, vehicle_journey_idx: &VehicleJourneyIdx) -> Option<&str> {
+        match vehicle_journey_idx {
+            VehicleJourneyIdx::Base(idx) => self.base.trip_short_name(*idx),
+            VehicleJourneyIdx::New(_idx) => None,
+        }
+    }
+
+    /// The headsign of the vehicle journey in the base schedule.
+    pub fn base_headsign(&self, vehicle_journey_idx: &VehicleJourneyIdx) -> Option<&str> {
+        match vehicle_journey_idx {
+            VehicleJourneyIdx::Base(idx) => self.base.headsign(*idx),
+            VehicleJourneyIdx::New(_idx) => None,
+        }
+    }
+
     pub fn co2_emission(&self, vehicle_journey_idx: &VehicleJourneyIdx) -> Option<f32> {
         match vehicle_journey_idx {
             VehicleJourneyIdx::Base(idx) => self.base.co2_emission(*idx),
@@ -205,6 +248,33 @@ impl<'model> ModelRefs<'model> {
         }
     }
 
+    /// Grams of CO2 emitted by the vehicle journey between each pair of consecutive `stops`,
+    /// computed from the emission rate (in grams per kilometer) of its physical mode.
+    /// Returns an empty vector if this emission rate is unknown.
+    pub fn co2_emissions_between_stops<'a, Stops>(
+        &self,
+        vehicle_journey_idx: &VehicleJourneyIdx,
+        stops: Stops,
+    ) -> Vec<f32>
+    where
+        Stops: Iterator<Item = &'a StopPointIdx> + Clone,
+    {
+        let Some(co2_emission_rate) = self.co2_emission(vehicle_journey_idx) else {
+            return Vec::new();
+        };
+        stops
+            .clone()
+            .zip(stops.skip(1))
+            .map(|(from, to)| match (self.coord(from), self.coord(to)) {
+                (Some(from), Some(to)) => {
+                    let distance_in_km = distance_coord_to_coord(&from, &to) * 1e-3_f64;
+                    co2_emission_rate * distance_in_km as f32
+                }
+                _ => 0.0,
+            })
+            .collect()
+    }
+
     pub fn stop_point_at(
         &self,
         vehicle_journey_idx: &VehicleJourneyIdx,
@@ -365,6 +435,22 @@ impl<'model> ModelRefs<'model> {
         self.base.contains_stop_area_id(id)
     }
 
+    pub fn contains_vehicle_journey_id(&self, id: &str) -> bool {
+        self.vehicle_journey_idx(id).is_some()
+    }
+
+    pub fn contains_company_id(&self, id: &str) -> bool {
+        self.base.contains_company_id(id)
+    }
+
+    pub fn contains_dataset_id(&self, id: &str) -> bool {
+        self.base.contains_dataset_id(id)
+    }
+
+    pub fn contains_contributor_id(&self, id: &str) -> bool {
+        self.base.contains_contributor_id(id)
+    }
+
     pub fn physical_mode_id(&self, physical_mode_idx: Idx<PhysicalMode>) -> &str {
         self.base.physical_mode_id(physical_mode_idx)
     }
@@ -426,6 +512,35 @@ impl<'model> ModelRefs<'model> {
             .collect()
     }
 
+    /// The stop points served by the base vehicle journeys on which `predicate` is `true`.
+    pub fn stop_points_of_vehicle_journeys<Predicate>(
+        &self,
+        predicate: Predicate,
+    ) -> Vec<StopPointIdx>
+    where
+        Predicate: Fn(&VehicleJourneyIdx) -> bool,
+    {
+        let stop_points: BTreeSet<BaseStopPointIdx> = self
+            .base
+            .vehicle_journeys()
+            .filter(|idx| predicate(&VehicleJourneyIdx::Base(*idx)))
+            .flat_map(|idx| self.base.stop_points_of_vehicle_journey(idx))
+            .collect();
+        stop_points.into_iter().map(StopPointIdx::Base).collect()
+    }
+
+    /// The base stop points that have `property_key` in their equipments.
+    pub fn stop_points_with_equipment(
+        &self,
+        property_key: EquipmentPropertyKey,
+    ) -> Vec<StopPointIdx> {
+        self.base
+            .stop_points()
+            .filter(|idx| self.base.stop_point_property(*idx, property_key))
+            .map(StopPointIdx::Base)
+            .collect()
+    }
+
     pub fn stop_point_pathways(&self, stop_point_idx: &BaseStopPointIdx) -> PathwayByIter {
         self.base.stop_point_pathways(stop_point_idx)
     }
@@ -111,6 +111,10 @@ pub(super) fn add_trip(
     );
     let physical_mode = model_ref.physical_mode_name(&vehicle_journey_idx);
     let regularity = Regularity::new(physical_mode);
+    let co2_emissions = model_ref.co2_emissions_between_stops(
+        &vehicle_journey_idx,
+        stop_times.iter().map(|stop_time| &stop_time.stop),
+    );
 
     let dates = std::iter::once(date);
     let stops = stop_times.iter().map(|stop_time| stop_time.stop.clone());
@@ -128,6 +132,7 @@ pub(super) fn add_trip(
         chrono_tz::UTC,
         vehicle_journey_idx,
         regularity,
+        &co2_emissions,
     );
     let model_ref = ModelRefs {
         base: base_model,
@@ -162,6 +167,10 @@ pub fn modify_trip(
     );
     let physical_mode = model_ref.physical_mode_name(vehicle_journey_idx);
     let regularity = Regularity::new(physical_mode);
+    let co2_emissions = model_ref.co2_emissions_between_stops(
+        vehicle_journey_idx,
+        stop_times.iter().map(|stop_time| &stop_time.stop),
+    );
     let dates = std::iter::once(*date);
     let stops = stop_times.iter().map(|stop_time| stop_time.stop.clone());
     let flows = stop_times.iter().map(|stop_time| stop_time.flow_direction);
@@ -178,6 +187,7 @@ pub fn modify_trip(
         chrono_tz::UTC,
         vehicle_journey_idx,
         regularity,
+        &co2_emissions,
     );
     if let Err(err) = modify_result {
         let model_ref = ModelRefs {
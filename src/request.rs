@@ -35,5 +35,7 @@
 // www.navitia.io
 
 pub mod arrive_before;
+pub mod comparator;
 pub mod depart_after;
 pub mod generic_request;
+pub mod lower_bounds;
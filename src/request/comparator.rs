@@ -0,0 +1,279 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use std::fmt;
+
+use super::generic_request::Criteria;
+use crate::time::PositiveDuration;
+
+/// A dimension along which the `Criteria` of two journeys can be compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criterion {
+    /// The arrival time (departure time for an arrive before request),
+    /// penalized by `leg_arrival_penalty` for each vehicle leg.
+    Time,
+    /// The number of vehicle legs.
+    NbOfLegs,
+    /// The duration of the fallbacks and transfers,
+    /// penalized by `leg_walking_penalty` for each vehicle leg.
+    Walking,
+    /// The duration of the fallbacks and transfers, without any penalty.
+    WalkingDuration,
+    /// The highest occupancy of the vehicles.
+    Occupancy,
+    /// The uncertainty of the connections.
+    Uncertainty,
+    /// The CO2 emitted by the vehicles.
+    Co2,
+}
+
+/// Compares `Criteria` along a list of `Criterion`.
+///
+/// A journey is lower than another one if it is lower or equal on every `Criterion`.
+/// `Criterion::Time` is always part of the list, since the engine relies on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparator {
+    criteria: Vec<Criterion>,
+}
+
+impl Comparator {
+    /// Returns an error if `criteria` does not contain `Criterion::Time`.
+    /// Duplicated criteria are ignored.
+    pub fn new(criteria: &[Criterion]) -> Result<Self, ComparatorError> {
+        if !criteria.contains(&Criterion::Time) {
+            return Err(ComparatorError::NoTimeCriterion);
+        }
+        let mut unique_criteria = Vec::with_capacity(criteria.len());
+        for criterion in criteria {
+            if !unique_criteria.contains(criterion) {
+                unique_criteria.push(*criterion);
+            }
+        }
+        Ok(Self {
+            criteria: unique_criteria,
+        })
+    }
+
+    /// Compares arrival time and walking.
+    pub fn basic() -> Self {
+        Self {
+            criteria: vec![Criterion::Time, Criterion::Walking],
+        }
+    }
+
+    /// Compares arrival time, walking and occupancy.
+    pub fn occupancy() -> Self {
+        Self {
+            criteria: vec![Criterion::Time, Criterion::Walking, Criterion::Occupancy],
+        }
+    }
+
+    /// Compares arrival time, walking and uncertainty.
+    pub fn robustness() -> Self {
+        Self {
+            criteria: vec![Criterion::Time, Criterion::Uncertainty, Criterion::Walking],
+        }
+    }
+
+    /// Compares arrival time, walking duration and number of legs.
+    pub fn least_walking() -> Self {
+        Self {
+            criteria: vec![
+                Criterion::Time,
+                Criterion::WalkingDuration,
+                Criterion::NbOfLegs,
+            ],
+        }
+    }
+
+    /// Compares arrival time, walking and CO2 emission.
+    pub fn co2() -> Self {
+        Self {
+            criteria: vec![Criterion::Time, Criterion::Walking, Criterion::Co2],
+        }
+    }
+
+    pub fn criteria(&self) -> &[Criterion] {
+        &self.criteria
+    }
+
+    /// Adds `criterion` to the compared criteria, if it is not already there.
+    pub fn with_criterion(mut self, criterion: Criterion) -> Self {
+        if !self.criteria.contains(&criterion) {
+            self.criteria.push(criterion);
+        }
+        self
+    }
+
+    // `time_is_lower` compares `Criterion::Time`, which depends
+    // on the direction of the request
+    pub(super) fn is_lower<TimeIsLower>(
+        &self,
+        lower: &Criteria,
+        upper: &Criteria,
+        leg_walking_penalty: PositiveDuration,
+        time_is_lower: TimeIsLower,
+    ) -> bool
+    where
+        TimeIsLower: Fn(&Criteria, &Criteria) -> bool,
+    {
+        self.criteria.iter().all(|criterion| match criterion {
+            Criterion::Time => time_is_lower(lower, upper),
+            Criterion::NbOfLegs => lower.nb_of_legs <= upper.nb_of_legs,
+            Criterion::Walking => {
+                walking_cost(lower, leg_walking_penalty) <= walking_cost(upper, leg_walking_penalty)
+            }
+            Criterion::WalkingDuration => {
+                lower.fallback_duration + lower.transfers_duration
+                    <= upper.fallback_duration + upper.transfers_duration
+            }
+            Criterion::Occupancy => lower.occupancies_count.max() <= upper.occupancies_count.max(),
+            Criterion::Uncertainty => lower.uncertainty <= upper.uncertainty,
+            Criterion::Co2 => lower.co2_emission <= upper.co2_emission,
+        })
+    }
+}
+
+impl Default for Comparator {
+    fn default() -> Self {
+        Self::basic()
+    }
+}
+
+fn walking_cost(criteria: &Criteria, leg_walking_penalty: PositiveDuration) -> PositiveDuration {
+    criteria.fallback_duration
+        + criteria.transfers_duration
+        + leg_walking_penalty * u32::from(criteria.nb_of_legs)
+}
+
+#[derive(Debug)]
+pub enum ComparatorError {
+    UnknownCriterion(String),
+    NoTimeCriterion,
+}
+
+impl fmt::Display for ComparatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComparatorError::UnknownCriterion(criterion) => write!(
+                f,
+                "Unable to parse {} as a criterion. Valid values are : \
+                time, nb_of_legs, walking, walking_duration, occupancy, uncertainty, co2",
+                criterion
+            ),
+            ComparatorError::NoTimeCriterion => {
+                write!(f, "The criterion `time` must always be used.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ComparatorError {}
+
+impl std::str::FromStr for Criterion {
+    type Err = ComparatorError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "time" => Ok(Criterion::Time),
+            "nb_of_legs" => Ok(Criterion::NbOfLegs),
+            "walking" => Ok(Criterion::Walking),
+            "walking_duration" => Ok(Criterion::WalkingDuration),
+            "occupancy" => Ok(Criterion::Occupancy),
+            "uncertainty" => Ok(Criterion::Uncertainty),
+            "co2" => Ok(Criterion::Co2),
+            _ => Err(ComparatorError::UnknownCriterion(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Criterion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Criterion::Time => "time",
+            Criterion::NbOfLegs => "nb_of_legs",
+            Criterion::Walking => "walking",
+            Criterion::WalkingDuration => "walking_duration",
+            Criterion::Occupancy => "occupancy",
+            Criterion::Uncertainty => "uncertainty",
+            Criterion::Co2 => "co2",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Parses a comma separated list of criteria, like `time,walking,nb_of_legs`
+impl std::str::FromStr for Comparator {
+    type Err = ComparatorError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let criteria = s
+            .split(',')
+            .map(|criterion| Criterion::from_str(criterion.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(&criteria)
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let criteria: Vec<String> = self
+            .criteria
+            .iter()
+            .map(|criterion| criterion.to_string())
+            .collect();
+        write!(f, "{}", criteria.join(","))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Comparator {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use std::str::FromStr;
+        let s = String::deserialize(deserializer)?;
+
+        Self::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl serde::Serialize for Comparator {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
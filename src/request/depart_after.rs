@@ -34,25 +34,29 @@
 // https://groups.google.com/d/forum/navitia
 // www.navitia.io
 
-pub mod basic_comparator;
-pub mod occupancy_comparator;
-pub mod robustness_comparator;
+mod request;
+pub use request::Request;
 
 use crate::{
     models::ModelRefs,
     occupancy_data::OccupanciesCount,
     robustness::Uncertainty,
     time::{PositiveDuration, SecondsSinceDatasetUTCStart},
-    transit_data::data_interface::DataIters,
+    transit_data::data_interface::{DataIters, DataWithIters},
     RealTimeLevel,
 };
 
 use crate::{
     engine::engine_interface::{BadRequest, RequestInput, RequestTypes},
+    filters::VehiclePenalties,
     transit_data::data_interface::Data as DataTrait,
 };
 
-use super::generic_request::{Arrival, Arrivals, Criteria, Departure, Departures};
+use super::{
+    comparator::Comparator,
+    generic_request::{Arrival, Arrivals, Criteria, Departure, Departures, ParetoSlack, Via},
+    lower_bounds::LowerBounds,
+};
 
 use crate::{
     engine::engine_interface::Journey as PTJourney,
@@ -69,26 +73,107 @@ pub struct GenericDepartAfterRequest<'data, 'model, Data: DataTrait> {
     pub(super) departure_datetime: SecondsSinceDatasetUTCStart,
     pub(super) departures_stop_point_and_fallback_duration: Vec<(Data::Stop, PositiveDuration)>,
     pub(super) arrivals_stop_point_and_fallbrack_duration: Vec<(Data::Stop, PositiveDuration)>,
+    pub(super) vias: Vec<Via<Data::Stop>>,
     pub(super) leg_arrival_penalty: PositiveDuration,
     pub(super) leg_walking_penalty: PositiveDuration,
     pub(super) max_arrival_time: SecondsSinceDatasetUTCStart,
+    // the maximum penalty of boarding a vehicle penalized by a `Preference`
+    pub(super) max_journey_duration: PositiveDuration,
     pub(super) max_nb_legs: u8,
+    pub(super) max_walking_duration: PositiveDuration,
+    pub(super) max_transfer_walking_duration: PositiveDuration,
+    pub(super) walking_speed_factor: f64,
+    pub(super) max_waiting_duration: PositiveDuration,
+    pub(super) max_initial_waiting_duration: Option<PositiveDuration>,
     pub(super) too_late_threshold: PositiveDuration,
     pub(super) real_time_level: RealTimeLevel,
+    pub(super) lower_bounds: Option<LowerBounds>,
+    pub(super) pareto_slack: ParetoSlack,
+    pub(super) comparator: Comparator,
+    pub(super) vehicle_penalties: &'data VehiclePenalties,
 }
 
 impl<'data, 'model, Data> GenericDepartAfterRequest<'data, 'model, Data>
 where
-    Data: DataTrait,
+    Data: DataWithIters,
 {
+    /// When `request_input.lower_bound_pruning` is set, a backward search
+    /// from the arrivals is performed to compute the `LowerBounds` used to
+    /// prune the multicriteria search.
     pub fn new(
         model: &'model ModelRefs<'model>,
         transit_data: &'data Data,
         request_input: &RequestInput,
+        vehicle_penalties: &'data VehiclePenalties,
     ) -> Result<Self, BadRequest>
     where
         Self: Sized,
     {
+        let arrivals: Vec<_> = super::generic_request::parse_arrivals(
+            &request_input.arrivals_stop_and_fallback_duration,
+            model,
+            transit_data,
+        )?;
+
+        let mut result = Self::new_with_arrivals(
+            model,
+            transit_data,
+            request_input,
+            vehicle_penalties,
+            arrivals,
+        )?;
+        result.vias = super::generic_request::parse_vias(
+            &request_input.via_stops_and_stay_duration,
+            model,
+            transit_data,
+        )?;
+        if request_input.lower_bound_pruning {
+            let lower_bounds = LowerBounds::to_arrivals(
+                transit_data,
+                &result.arrivals_stop_point_and_fallbrack_duration,
+                request_input.max_journey_duration,
+                result.walking_speed_factor,
+            );
+            result.lower_bounds = Some(lower_bounds);
+        }
+        Ok(result)
+    }
+}
+
+impl<'data, 'model, Data> GenericDepartAfterRequest<'data, 'model, Data>
+where
+    Data: DataTrait,
+{
+    /// Creates a request with no arrival stop, in order to explore
+    /// every stop that can be reached from the departures.
+    ///
+    /// `request_input.arrivals_stop_and_fallback_duration` and
+    /// `request_input.via_stops_and_stay_duration` are ignored.
+    pub fn new_one_to_all(
+        model: &'model ModelRefs<'model>,
+        transit_data: &'data Data,
+        request_input: &RequestInput,
+        vehicle_penalties: &'data VehiclePenalties,
+    ) -> Result<Self, BadRequest>
+    where
+        Self: Sized,
+    {
+        Self::new_with_arrivals(
+            model,
+            transit_data,
+            request_input,
+            vehicle_penalties,
+            Vec::new(),
+        )
+    }
+
+    fn new_with_arrivals(
+        model: &'model ModelRefs<'model>,
+        transit_data: &'data Data,
+        request_input: &RequestInput,
+        vehicle_penalties: &'data VehiclePenalties,
+        arrivals: Vec<(Data::Stop, PositiveDuration)>,
+    ) -> Result<Self, BadRequest> {
         let departure_datetime = super::generic_request::parse_datetime(
             &request_input.datetime,
             transit_data.calendar(),
@@ -100,11 +185,8 @@ where
             transit_data,
         )?;
 
-        let arrivals: Vec<_> = super::generic_request::parse_arrivals(
-            &request_input.arrivals_stop_and_fallback_duration,
-            model,
-            transit_data,
-        )?;
+        let walking_speed_factor =
+            super::generic_request::parse_walking_speed_factor(request_input.walking_speed_factor)?;
 
         let result = Self {
             transit_data,
@@ -112,12 +194,23 @@ where
             departure_datetime,
             departures_stop_point_and_fallback_duration: departures,
             arrivals_stop_point_and_fallbrack_duration: arrivals,
+            vias: Vec::new(),
             leg_arrival_penalty: request_input.leg_arrival_penalty,
             leg_walking_penalty: request_input.leg_walking_penalty,
             max_arrival_time: departure_datetime + request_input.max_journey_duration,
+            max_journey_duration: request_input.max_journey_duration,
             max_nb_legs: request_input.max_nb_of_legs,
+            max_walking_duration: request_input.max_walking_duration,
+            max_transfer_walking_duration: request_input.max_transfer_walking_duration,
+            walking_speed_factor,
+            max_waiting_duration: request_input.max_waiting_duration,
+            max_initial_waiting_duration: request_input.max_initial_waiting_duration,
             too_late_threshold: request_input.too_late_threshold,
             real_time_level: request_input.real_time_level,
+            lower_bounds: None,
+            pareto_slack: ParetoSlack::new(request_input),
+            comparator: request_input.comparator.clone(),
+            vehicle_penalties,
         };
 
         Ok(result)
@@ -169,12 +262,18 @@ where
             *arrival_fallback_duration,
             pt_journey.criteria_at_arrival.occupancies_count.clone(),
             pt_journey.criteria_at_arrival.uncertainty,
+            self.walking_speed_factor,
             self.transit_data,
             self.real_time_level,
         )
         .map_err(response::JourneyError::BadJourney)?;
+        let stay_durations = super::generic_request::stay_durations_at_connections(
+            &journey,
+            &self.vias,
+            self.transit_data,
+        );
         let new_journey = self
-            .maximize_departure_time(journey)
+            .maximize_departure_time(journey, &stay_durations)
             .map_err(response::JourneyError::MaximizeDepartureTimeError)?;
         Ok(new_journey)
     }
@@ -204,7 +303,50 @@ where
     }
 
     fn is_valid(&self, criteria: &Criteria) -> bool {
-        criteria.time <= self.max_arrival_time && criteria.nb_of_legs <= self.max_nb_legs
+        criteria.time <= self.max_arrival_time
+            && criteria.nb_of_legs <= self.max_nb_legs
+            && criteria.fallback_duration + criteria.transfers_duration <= self.max_walking_duration
+    }
+
+    fn is_lower(&self, lower: &Criteria, upper: &Criteria) -> bool {
+        if lower.nb_of_visited_vias < upper.nb_of_visited_vias {
+            return false;
+        }
+        self.comparator
+            .is_lower(lower, upper, self.leg_walking_penalty, |lower, upper| {
+                self.penalized_seconds(lower) <= self.penalized_seconds(upper)
+            })
+    }
+
+    fn is_within_slack(&self, lower: &Criteria, upper: &Criteria) -> bool {
+        if self.pareto_slack.is_zero() {
+            return false;
+        }
+        let extra_seconds = self.penalized_seconds(upper) - self.penalized_seconds(lower);
+        let extra_duration = u32::try_from(extra_seconds)
+            .ok()
+            .map(|seconds| PositiveDuration::from_hms(0, 0, seconds));
+        let lower_duration = lower.time.duration_since(&self.departure_datetime);
+        match (extra_duration, lower_duration) {
+            (Some(extra_duration), Some(lower_duration)) => {
+                self.pareto_slack.keeps(extra_duration, lower_duration)
+            }
+            _ => false,
+        }
+    }
+
+    // the arrival time penalized by the number of legs and the preferences,
+    // as compared in `is_lower()`, computed on i64 since the penalties may be large
+    fn penalized_seconds(&self, criteria: &Criteria) -> i64 {
+        let legs_penalty = self.leg_arrival_penalty * u32::from(criteria.nb_of_legs);
+        i64::from(criteria.time.total_seconds())
+            + i64::from(legs_penalty.total_seconds_u32())
+            + i64::from(criteria.preference_penalty.total_seconds_u32())
+            - i64::from(criteria.preference_bonus.total_seconds_u32())
+    }
+
+    fn front_key(&self, criteria: &Criteria) -> i64 {
+        self.penalized_seconds(criteria)
     }
 
     fn can_be_discarded(
@@ -215,6 +357,15 @@ where
         partial_journey_criteria.time >= complete_journey_criteria.time + self.too_late_threshold
     }
 
+    fn lower_bound_at_arrival(&self, stop: &Data::Stop, criteria: &Criteria) -> Option<Criteria> {
+        let lower_bounds = self.lower_bounds.as_ref()?;
+        let duration_to_arrival = lower_bounds.duration_at(self.transit_data.stop_id(stop));
+        Some(Criteria {
+            time: criteria.time + duration_to_arrival,
+            ..criteria.clone()
+        })
+    }
+
     fn board_and_ride(
         &self,
         position: &Data::Position,
@@ -223,7 +374,9 @@ where
     ) -> Option<Criteria> {
         let has_board = self.transit_data.board_time_of(trip, position);
         if let Some(board_time) = has_board {
-            if waiting_criteria.time > board_time {
+            if waiting_criteria.time > board_time
+                || !self.can_wait_until(waiting_criteria, board_time)
+            {
                 return None;
             }
         } else {
@@ -233,7 +386,9 @@ where
         let next_position = self.transit_data.next_on_mission(position, &mission)?;
         let arrival_time_at_next_stop = self.transit_data.arrival_time_of(trip, &next_position);
         let occupancy = self.transit_data.occupancy_before(trip, &next_position);
+        let co2_emission = self.transit_data.co2_emission_before(trip, &next_position);
         let regularity = self.transit_data.regularity(trip);
+        let (preference_penalty, preference_bonus) = self.preference_penalty_and_bonus(trip);
         let new_criteria = Criteria {
             time: arrival_time_at_next_stop,
             nb_of_legs: waiting_criteria.nb_of_legs + 1,
@@ -241,6 +396,10 @@ where
             transfers_duration: waiting_criteria.transfers_duration,
             occupancies_count: waiting_criteria.occupancies_count.add(occupancy),
             uncertainty: waiting_criteria.uncertainty.extend(regularity),
+            nb_of_visited_vias: waiting_criteria.nb_of_visited_vias,
+            co2_emission: waiting_criteria.co2_emission + co2_emission,
+            preference_penalty: waiting_criteria.preference_penalty + preference_penalty,
+            preference_bonus: waiting_criteria.preference_bonus + preference_bonus,
         };
         Some(new_criteria)
     }
@@ -260,6 +419,10 @@ where
             transfers_duration: criteria.transfers_duration,
             occupancies_count: criteria.occupancies_count.clone(),
             uncertainty: criteria.uncertainty.extend(regularity),
+            nb_of_visited_vias: criteria.nb_of_visited_vias,
+            co2_emission: criteria.co2_emission,
+            preference_penalty: criteria.preference_penalty,
+            preference_bonus: criteria.preference_bonus,
         };
         Some((next_trip, new_criteria))
     }
@@ -279,8 +442,22 @@ where
                 self.real_time_level,
                 |_| true,
             )
+            .filter(|(trip, _, _)| {
+                self.transit_data
+                    .board_time_of(trip, position)
+                    .is_some_and(|board_time| self.can_wait_until(waiting_criteria, board_time))
+            })
             .map(|(trip, arrival_time, occupancy)| {
                 let regularity = self.transit_data.regularity(&trip);
+                let (preference_penalty, preference_bonus) =
+                    self.preference_penalty_and_bonus(&trip);
+                let co2_emission = self
+                    .transit_data
+                    .next_on_mission(position, mission)
+                    .map(|next_position| {
+                        self.transit_data.co2_emission_before(&trip, &next_position)
+                    })
+                    .unwrap_or(0.0);
                 let new_criteria = Criteria {
                     time: arrival_time,
                     nb_of_legs: waiting_criteria.nb_of_legs + 1,
@@ -288,6 +465,10 @@ where
                     transfers_duration: waiting_criteria.transfers_duration,
                     occupancies_count: waiting_criteria.occupancies_count.add(occupancy),
                     uncertainty: waiting_criteria.uncertainty.extend(regularity),
+                    nb_of_visited_vias: waiting_criteria.nb_of_visited_vias,
+                    co2_emission: waiting_criteria.co2_emission + co2_emission,
+                    preference_penalty: waiting_criteria.preference_penalty + preference_penalty,
+                    preference_bonus: waiting_criteria.preference_bonus + preference_bonus,
                 };
                 (trip, new_criteria)
             })
@@ -303,16 +484,70 @@ where
             let arrival_time = &onboard_criteria.time;
             self.transit_data.arrival_time_of(trip, position) == *arrival_time
         });
-        self.transit_data
-            .debark_time_of(trip, position)
-            .map(|debark_time| Criteria {
-                time: debark_time,
-                nb_of_legs: onboard_criteria.nb_of_legs,
-                fallback_duration: onboard_criteria.fallback_duration,
-                transfers_duration: onboard_criteria.transfers_duration,
-                occupancies_count: onboard_criteria.occupancies_count.clone(),
-                uncertainty: onboard_criteria.uncertainty,
-            })
+        let debark_time = self.transit_data.debark_time_of(trip, position)?;
+        let mut criteria = Criteria {
+            time: debark_time,
+            nb_of_legs: onboard_criteria.nb_of_legs,
+            fallback_duration: onboard_criteria.fallback_duration,
+            transfers_duration: onboard_criteria.transfers_duration,
+            occupancies_count: onboard_criteria.occupancies_count.clone(),
+            uncertainty: onboard_criteria.uncertainty,
+            nb_of_visited_vias: onboard_criteria.nb_of_visited_vias,
+            co2_emission: onboard_criteria.co2_emission,
+            preference_penalty: onboard_criteria.preference_penalty,
+            preference_bonus: onboard_criteria.preference_bonus,
+        };
+        if let Some(via) = self.next_via(&criteria) {
+            let mission = self.transit_data.mission_of(trip);
+            let stop = self.transit_data.stop_of(position, &mission);
+            stay_at_via(via, &stop, &mut criteria);
+        }
+        Some(criteria)
+    }
+
+    // the via that a journey with `criteria` should visit next
+    fn next_via(&self, criteria: &Criteria) -> Option<&Via<Data::Stop>> {
+        self.vias.get(usize::from(criteria.nb_of_visited_vias))
+    }
+
+    // a journey with `waiting_criteria` can wait until `board_time` to board a vehicle
+    // when it does not exceed the max waiting duration, or the max initial waiting duration
+    // when no vehicle has been boarded yet.
+    // The wait starts at `waiting_criteria.time`, so the fallback to the first stop
+    // is not counted as waiting
+    fn can_wait_until(
+        &self,
+        waiting_criteria: &Criteria,
+        board_time: SecondsSinceDatasetUTCStart,
+    ) -> bool {
+        let max_waiting_duration = if waiting_criteria.nb_of_legs == 0 {
+            match self.max_initial_waiting_duration {
+                Some(max_initial_waiting_duration) => max_initial_waiting_duration,
+                None => return true,
+            }
+        } else {
+            self.max_waiting_duration
+        };
+        board_time
+            .duration_since(&waiting_criteria.time)
+            .is_none_or(|waiting_duration| waiting_duration <= max_waiting_duration)
+    }
+
+    // the penalty and the bonus of boarding `trip`, given by the `Preference`s of the request.
+    // The penalty is at most the max journey duration, and the bonus at most the leg arrival penalty
+    fn preference_penalty_and_bonus(
+        &self,
+        trip: &Data::Trip,
+    ) -> (PositiveDuration, PositiveDuration) {
+        if self.vehicle_penalties.is_empty() {
+            return (PositiveDuration::zero(), PositiveDuration::zero());
+        }
+        let vehicle_journey_idx = self.transit_data.vehicle_journey_idx(trip);
+        self.vehicle_penalties.penalty_and_bonus(
+            &vehicle_journey_idx,
+            self.max_journey_duration,
+            self.leg_arrival_penalty,
+        )
     }
 
     fn ride(&self, trip: &Data::Trip, position: &Data::Position, criteria: &Criteria) -> Criteria {
@@ -323,6 +558,7 @@ where
             .unwrap();
         let arrival_time_at_next_position = self.transit_data.arrival_time_of(trip, &next_position);
         let occupancy = self.transit_data.occupancy_before(trip, &next_position);
+        let co2_emission = self.transit_data.co2_emission_before(trip, &next_position);
         Criteria {
             time: arrival_time_at_next_position,
             nb_of_legs: criteria.nb_of_legs,
@@ -330,6 +566,10 @@ where
             transfers_duration: criteria.transfers_duration,
             occupancies_count: criteria.occupancies_count.add(occupancy),
             uncertainty: criteria.uncertainty,
+            nb_of_visited_vias: criteria.nb_of_visited_vias,
+            co2_emission: criteria.co2_emission + co2_emission,
+            preference_penalty: criteria.preference_penalty,
+            preference_bonus: criteria.preference_bonus,
         }
     }
 
@@ -344,6 +584,10 @@ where
             transfers_duration: PositiveDuration::zero(),
             occupancies_count: OccupanciesCount::zero(),
             uncertainty: Uncertainty::zero(),
+            nb_of_visited_vias: 0,
+            co2_emission: 0.0,
+            preference_penalty: PositiveDuration::zero(),
+            preference_bonus: PositiveDuration::zero(),
         };
         (stop.clone(), criteria)
     }
@@ -354,16 +598,23 @@ where
             .clone()
     }
 
-    fn arrive(&self, arrival: &Arrival, criteria: &Criteria) -> Criteria {
+    fn arrive(&self, arrival: &Arrival, criteria: &Criteria) -> Option<Criteria> {
+        if self.next_via(criteria).is_some() {
+            return None;
+        }
         let arrival_duration = &self.arrivals_stop_point_and_fallbrack_duration[arrival.idx].1;
-        Criteria {
+        Some(Criteria {
             time: criteria.time + *arrival_duration,
             nb_of_legs: criteria.nb_of_legs,
             fallback_duration: criteria.fallback_duration + *arrival_duration,
             transfers_duration: criteria.transfers_duration,
             occupancies_count: criteria.occupancies_count.clone(),
             uncertainty: criteria.uncertainty,
-        }
+            nb_of_visited_vias: criteria.nb_of_visited_vias,
+            co2_emission: criteria.co2_emission,
+            preference_penalty: criteria.preference_penalty,
+            preference_bonus: criteria.preference_bonus,
+        })
     }
 
     fn is_upstream(
@@ -438,10 +689,12 @@ where
     }
 
     // Given a 'journey' (ie arrival_time + list of tranfers and vehicle_leg)
-    // return a journey with the same path and the latest possible departure
+    // return a journey with the same path and the latest possible departure,
+    // that still stays `stay_durations[i]` at the vias during the i-th connection
     fn maximize_departure_time(
         &self,
         mut journey: response::Journey<Data>,
+        stay_durations: &[PositiveDuration],
     ) -> Result<response::Journey<Data>, MaximizeDepartureTimeError<Data>> {
         let last_vehicle_leg = journey
             .connections
@@ -459,15 +712,21 @@ where
                 )
             })?;
 
-        for (transfer, vehicle) in journey.connections.iter_mut().rev() {
+        for ((transfer, vehicle), stay_duration) in journey
+            .connections
+            .iter_mut()
+            .zip(stay_durations.iter())
+            .rev()
+        {
             let new_board_time = self._maximize_leg_board_time(vehicle, current_time)?;
             current_time = new_board_time;
 
             let transfer_duration = self
                 .transit_data
                 .transfer_durations(transfer)
+                .with_walking_speed_factor(self.walking_speed_factor)
                 .total_duration;
-            current_time = current_time - transfer_duration;
+            current_time = current_time - transfer_duration - *stay_duration;
         }
 
         let vehicle = &mut journey.first_vehicle;
@@ -510,6 +769,9 @@ where
         TransferAtStop {
             inner: outgoing_transfers,
             criteria: criteria.clone(),
+            next_via: self.next_via(criteria),
+            max_walking_duration: self.max_transfer_walking_duration,
+            walking_speed_factor: self.walking_speed_factor,
         }
     }
 
@@ -524,6 +786,10 @@ where
 {
     inner: Data::OutgoingTransfersAtStop,
     criteria: Criteria,
+    next_via: Option<&'outer Via<Data::Stop>>,
+    // transfers that walk longer than this are skipped
+    max_walking_duration: PositiveDuration,
+    walking_speed_factor: f64,
 }
 
 impl<'outer, Data> Iterator for TransferAtStop<'outer, Data>
@@ -533,16 +799,39 @@ where
     type Item = (Data::Stop, Criteria, Data::Transfer);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|(stop, durations, transfer)| {
-            let new_criteria = Criteria {
-                time: self.criteria.time + durations.total_duration,
-                nb_of_legs: self.criteria.nb_of_legs,
-                fallback_duration: self.criteria.fallback_duration,
-                transfers_duration: self.criteria.transfers_duration + durations.walking_duration,
-                occupancies_count: self.criteria.occupancies_count.clone(),
-                uncertainty: self.criteria.uncertainty,
-            };
-            (stop.clone(), new_criteria, transfer.clone())
-        })
+        let max_walking_duration = self.max_walking_duration;
+        let walking_speed_factor = self.walking_speed_factor;
+        let (stop, durations, transfer) =
+            self.inner
+                .by_ref()
+                .find_map(|(stop, durations, transfer)| {
+                    let durations = durations.with_walking_speed_factor(walking_speed_factor);
+                    (durations.walking_duration <= max_walking_duration)
+                        .then_some((stop, durations, transfer))
+                })?;
+        let mut new_criteria = Criteria {
+            time: self.criteria.time + durations.total_duration,
+            nb_of_legs: self.criteria.nb_of_legs,
+            fallback_duration: self.criteria.fallback_duration,
+            transfers_duration: self.criteria.transfers_duration + durations.walking_duration,
+            occupancies_count: self.criteria.occupancies_count.clone(),
+            uncertainty: self.criteria.uncertainty,
+            nb_of_visited_vias: self.criteria.nb_of_visited_vias,
+            co2_emission: self.criteria.co2_emission,
+            preference_penalty: self.criteria.preference_penalty,
+            preference_bonus: self.criteria.preference_bonus,
+        };
+        if let Some(via) = self.next_via {
+            stay_at_via(via, stop, &mut new_criteria);
+        }
+        Some((stop.clone(), new_criteria, transfer.clone()))
+    }
+}
+
+// when `stop` belongs to `via`, stays there after the time of `criteria`
+fn stay_at_via<Stop: PartialEq>(via: &Via<Stop>, stop: &Stop, criteria: &mut Criteria) {
+    if via.contains(stop) {
+        criteria.time = criteria.time + via.stay_duration();
+        criteria.nb_of_visited_vias += 1;
     }
 }
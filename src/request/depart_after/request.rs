@@ -39,6 +39,7 @@ use crate::{
         BadRequest, Request as RequestTrait, RequestDebug, RequestIO, RequestInput, RequestIters,
         RequestTypes, RequestWithIters,
     },
+    filters::VehiclePenalties,
     models::ModelRefs,
     transit_data::data_interface::{Data as DataTrait, DataIters, DataWithIters, TransitTypes},
 };
@@ -49,6 +50,23 @@ pub struct Request<'data, 'model, Data: DataTrait> {
     generic: GenericDepartAfterRequest<'data, 'model, Data>,
 }
 
+impl<'data, 'model, Data: DataTrait> Request<'data, 'model, Data> {
+    pub fn new_one_to_all(
+        model: &'model ModelRefs<'model>,
+        transit_data: &'data Data,
+        request_input: &RequestInput,
+        vehicle_penalties: &'data VehiclePenalties,
+    ) -> Result<Self, BadRequest> {
+        let generic_result = GenericDepartAfterRequest::new_one_to_all(
+            model,
+            transit_data,
+            request_input,
+            vehicle_penalties,
+        );
+        generic_result.map(|generic| Self { generic })
+    }
+}
+
 impl<'data, 'model, Data: DataTrait> TransitTypes for Request<'data, 'model, Data> {
     type Stop = Data::Stop;
     type Mission = Data::Mission;
@@ -65,21 +83,15 @@ impl<'data, 'model, Data: DataTrait> RequestTypes for Request<'data, 'model, Dat
 
 impl<'data, 'model, Data: DataTrait> RequestTrait for Request<'data, 'model, Data> {
     fn is_lower(&self, lower: &Self::Criteria, upper: &Self::Criteria) -> bool {
-        let arrival_penalty = self.generic.leg_arrival_penalty();
-        let walking_penalty = self.generic.leg_walking_penalty();
+        self.generic.is_lower(lower, upper)
+    }
 
-        let lower_nb_of_legs = u32::from(lower.nb_of_legs);
-        let upper_nb_of_legs = u32::from(upper.nb_of_legs);
+    fn is_within_slack(&self, lower: &Self::Criteria, upper: &Self::Criteria) -> bool {
+        self.generic.is_within_slack(lower, upper)
+    }
 
-        lower.time + arrival_penalty * lower_nb_of_legs
-            <= upper.time + arrival_penalty * upper_nb_of_legs
-            && lower.uncertainty <= upper.uncertainty
-            && lower.fallback_duration
-                + lower.transfers_duration
-                + walking_penalty * lower_nb_of_legs
-                <= upper.fallback_duration
-                    + upper.transfers_duration
-                    + walking_penalty * upper_nb_of_legs
+    fn front_key(&self, criteria: &Self::Criteria) -> i64 {
+        self.generic.front_key(criteria)
     }
 
     fn can_be_discarded(
@@ -95,6 +107,14 @@ impl<'data, 'model, Data: DataTrait> RequestTrait for Request<'data, 'model, Dat
         self.generic.is_valid(criteria)
     }
 
+    fn lower_bound_at_arrival(
+        &self,
+        stop: &Self::Stop,
+        criteria: &Self::Criteria,
+    ) -> Option<Self::Criteria> {
+        self.generic.lower_bound_at_arrival(stop, criteria)
+    }
+
     fn board_and_ride(
         &self,
         position: &Self::Position,
@@ -137,7 +157,7 @@ impl<'data, 'model, Data: DataTrait> RequestTrait for Request<'data, 'model, Dat
         self.generic.depart(departure)
     }
 
-    fn arrive(&self, arrival: &Self::Arrival, criteria: &Self::Criteria) -> Self::Criteria {
+    fn arrive(&self, arrival: &Self::Arrival, criteria: &Self::Criteria) -> Option<Self::Criteria> {
         self.generic.arrive(arrival, criteria)
     }
 
@@ -238,17 +258,19 @@ use crate::{engine::engine_interface::Journey as PTJourney, response};
 
 impl<'data, 'model, Data> RequestIO<'data, 'model, Data> for Request<'data, 'model, Data>
 where
-    Data: DataTrait,
+    Data: DataWithIters,
 {
     fn new(
         model: &'model ModelRefs<'model>,
         transit_data: &'data Data,
         request_input: &RequestInput,
+        vehicle_penalties: &'data VehiclePenalties,
     ) -> Result<Self, BadRequest>
     where
         Self: Sized,
     {
-        let generic_result = GenericDepartAfterRequest::new(model, transit_data, request_input);
+        let generic_result =
+            GenericDepartAfterRequest::new(model, transit_data, request_input, vehicle_penalties);
         generic_result.map(|generic| Self { generic })
     }
 
@@ -35,9 +35,10 @@
 // www.navitia.io
 
 use crate::{
-    engine::engine_interface::InputStop,
+    engine::engine_interface::{InputStop, RequestInput},
     models::ModelRefs,
     occupancy_data::OccupanciesCount,
+    response,
     robustness::Uncertainty,
     time::{Calendar, PositiveDuration, SecondsSinceDatasetUTCStart},
     timetables::generic_timetables,
@@ -46,7 +47,9 @@ use crate::{
 };
 
 use crate::{
-    engine::engine_interface::BadRequest, transit_data::data_interface::Data as DataTrait,
+    engine::engine_interface::BadRequest,
+    transit_data::data_interface::{Data as DataTrait, DataWithIters},
+    RealTimeLevel,
 };
 use chrono::NaiveDateTime;
 use std::fmt::Debug;
@@ -106,6 +109,89 @@ pub struct Criteria {
     pub(super) transfers_duration: PositiveDuration,
     pub(super) occupancies_count: OccupanciesCount,
     pub(super) uncertainty: Uncertainty,
+    pub(super) nb_of_visited_vias: u8,
+    // grams of CO2 emitted by the vehicles
+    pub(super) co2_emission: f32,
+    // sum of the penalties of the boarded vehicles, given by the `Preference`s of the request
+    pub(super) preference_penalty: PositiveDuration,
+    // sum of the bonuses of the boarded vehicles, given by the `Preference`s of the request.
+    // Each bonus is at most the leg arrival penalty, so that
+    // `leg_arrival_penalty * nb_of_legs >= preference_bonus`
+    pub(super) preference_bonus: PositiveDuration,
+}
+
+impl Criteria {
+    pub fn time(&self) -> SecondsSinceDatasetUTCStart {
+        self.time
+    }
+
+    pub fn nb_of_legs(&self) -> u8 {
+        self.nb_of_legs
+    }
+
+    pub fn nb_of_visited_vias(&self) -> u8 {
+        self.nb_of_visited_vias
+    }
+
+    pub fn co2_emission(&self) -> f32 {
+        self.co2_emission
+    }
+}
+
+/// A stop point, or all the stop points of a stop area,
+/// where a journey must stay for at least `stay_duration`.
+pub(super) struct Via<Stop> {
+    stops: Vec<Stop>,
+    stay_duration: PositiveDuration,
+}
+
+impl<Stop: PartialEq> Via<Stop> {
+    pub(super) fn stay_duration(&self) -> PositiveDuration {
+        self.stay_duration
+    }
+
+    pub(super) fn contains(&self, stop: &Stop) -> bool {
+        self.stops.contains(stop)
+    }
+}
+
+/// Allows a pareto front to keep a journey that is dominated by another one,
+/// when its arrival time (departure time for an arrive before request) is later (resp. earlier)
+/// than the one of the dominating journey by at most `duration`,
+/// or by at most `percentage`% of the duration of the dominating journey.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ParetoSlack {
+    duration: PositiveDuration,
+    percentage: u32,
+}
+
+impl ParetoSlack {
+    pub(super) fn new(request_input: &RequestInput) -> Self {
+        Self {
+            duration: request_input.pareto_slack_duration,
+            percentage: request_input.pareto_slack_percentage,
+        }
+    }
+
+    pub(super) fn is_zero(&self) -> bool {
+        self.duration == PositiveDuration::zero() && self.percentage == 0
+    }
+
+    /// Returns `true` if a journey that is `extra_duration` slower
+    /// than a dominating journey of duration `dominating_duration` should be kept.
+    pub(super) fn keeps(
+        &self,
+        extra_duration: PositiveDuration,
+        dominating_duration: PositiveDuration,
+    ) -> bool {
+        if extra_duration == PositiveDuration::zero() {
+            return false;
+        }
+        let percentage_slack_seconds =
+            dominating_duration.total_seconds() * u64::from(self.percentage) / 100;
+        extra_duration <= self.duration
+            || extra_duration.total_seconds() <= percentage_slack_seconds
+    }
 }
 
 pub struct RequestTypes {}
@@ -188,7 +274,81 @@ where
     Ok(result)
 }
 
-fn parse_input_stops<Data>(
+pub(super) fn parse_vias<Data>(
+    via_stops_and_stay_duration: &[(InputStop, PositiveDuration)],
+    model: &ModelRefs<'_>,
+    transit_data: &Data,
+) -> Result<Vec<Via<Data::Stop>>, BadRequest>
+where
+    Data: DataTrait,
+{
+    via_stops_and_stay_duration
+        .iter()
+        .map(|(via_stop, stay_duration)| {
+            let stops: Vec<_> = parse_input_stops(
+                std::slice::from_ref(&(via_stop.clone(), *stay_duration)),
+                model,
+                transit_data,
+                "via",
+            )
+            .into_iter()
+            .map(|(stop, _)| stop)
+            .collect();
+            if stops.is_empty() {
+                return Err(BadRequest::NoValidViaStop);
+            }
+            Ok(Via {
+                stops,
+                stay_duration: *stay_duration,
+            })
+        })
+        .collect()
+}
+
+pub(super) fn parse_walking_speed_factor(walking_speed_factor: f64) -> Result<f64, BadRequest> {
+    if walking_speed_factor.is_finite() && walking_speed_factor > 0.0 {
+        Ok(walking_speed_factor)
+    } else {
+        Err(BadRequest::WalkingSpeedFactor)
+    }
+}
+
+/// Returns, for each connection of `journey`, the total duration of the stays
+/// at `vias` that happen during this connection.
+///
+/// A via is visited when debarking at one of its stops, or when walking to one of its stops,
+/// provided that all the previous vias have been visited.
+pub(super) fn stay_durations_at_connections<Data>(
+    journey: &response::Journey<Data>,
+    vias: &[Via<Data::Stop>],
+    transit_data: &Data,
+) -> Vec<PositiveDuration>
+where
+    Data: DataTrait,
+{
+    let mut nb_of_visited_vias = 0;
+    let mut previous_vehicle = &journey.first_vehicle;
+    let mut result = Vec::with_capacity(journey.connections.len());
+    for (transfer, vehicle) in journey.connections.iter() {
+        let mission = transit_data.mission_of(&previous_vehicle.trip);
+        let debark_stop = transit_data.stop_of(&previous_vehicle.debark_position, &mission);
+        let (_, transfer_stop) = transit_data.transfer_from_to_stop(transfer);
+        let mut stay_duration = PositiveDuration::zero();
+        for stop in [debark_stop, transfer_stop] {
+            if let Some(via) = vias.get(nb_of_visited_vias) {
+                if via.contains(&stop) {
+                    stay_duration = stay_duration + via.stay_duration();
+                    nb_of_visited_vias += 1;
+                }
+            }
+        }
+        result.push(stay_duration);
+        previous_vehicle = vehicle;
+    }
+    result
+}
+
+pub fn parse_input_stops<Data>(
     stops_and_fallback_duration: &[(InputStop, PositiveDuration)],
     model: &ModelRefs<'_>,
     transit_data: &Data,
@@ -201,7 +361,7 @@ where
     for (idx, (stop, fallback_duration)) in stops_and_fallback_duration.iter().enumerate() {
         match stop {
             InputStop::StopPoint(stop_point_uri) => {
-                let Some(stop_idx) =  model.stop_point_idx(stop_point_uri) else {
+                let Some(stop_idx) = model.stop_point_idx(stop_point_uri) else {
                     warn!(
                         "The {idx}th {input_stop_type} : stop point {stop_point_uri} is not found in model. \
                                 I ignore it."
@@ -243,6 +403,133 @@ where
     result
 }
 
+/// Returns all the datetimes in `[from_datetime, until_datetime]` at which a traveler leaving
+/// from one of `departures_stop_and_fallback_duration` can board a trip
+/// right after its fallback, sorted by decreasing datetime.
+///
+/// These are the datetimes to be used for a range search over a departure window
+/// with `MultiCriteriaRaptor::compute_range_iteration()`.
+pub fn range_departure_datetimes<Data>(
+    departures_stop_and_fallback_duration: &[(InputStop, PositiveDuration)],
+    from_datetime: &NaiveDateTime,
+    until_datetime: &NaiveDateTime,
+    real_time_level: RealTimeLevel,
+    model: &ModelRefs<'_>,
+    transit_data: &Data,
+) -> Result<Vec<NaiveDateTime>, BadRequest>
+where
+    Data: DataWithIters,
+{
+    let calendar = transit_data.calendar();
+    let from_time = parse_datetime(from_datetime, calendar)?;
+    let until_time = parse_datetime(
+        &std::cmp::min(*until_datetime, calendar.last_datetime()),
+        calendar,
+    )?;
+    let departures = parse_departures(departures_stop_and_fallback_duration, model, transit_data)?;
+
+    let one_second = PositiveDuration::from_hms(0, 0, 1);
+    let mut result = Vec::new();
+    for (stop, fallback_duration) in departures.iter() {
+        for (mission, position) in transit_data.missions_at(stop) {
+            let mut waiting_time = from_time + *fallback_duration;
+            while let Some((trip, _, _)) = transit_data.earliest_trip_to_board(
+                waiting_time,
+                &mission,
+                &position,
+                real_time_level,
+                |_| true,
+            ) {
+                let Some(board_time) = transit_data.board_time_of(&trip, &position) else {
+                    break;
+                };
+                if board_time < waiting_time {
+                    break;
+                }
+                // board_time >= from_time + fallback_duration so this cannot underflow
+                let departure_time = board_time - *fallback_duration;
+                if departure_time > until_time {
+                    break;
+                }
+                result.push(departure_time);
+                waiting_time = board_time + one_second;
+            }
+        }
+    }
+    result.sort_unstable_by(|a, b| b.cmp(a));
+    result.dedup();
+    Ok(result
+        .into_iter()
+        .map(|time| calendar.to_naive_datetime(time))
+        .collect())
+}
+
+/// Returns all the datetimes in `[from_datetime, until_datetime]` at which a traveler
+/// can reach one of `arrivals_stop_and_fallback_duration` right after debarking from a trip,
+/// sorted by increasing datetime.
+///
+/// These are the datetimes to be used for a range search over an arrival window
+/// with `MultiCriteriaRaptor::compute_range_iteration()`.
+pub fn range_arrival_datetimes<Data>(
+    arrivals_stop_and_fallback_duration: &[(InputStop, PositiveDuration)],
+    from_datetime: &NaiveDateTime,
+    until_datetime: &NaiveDateTime,
+    real_time_level: RealTimeLevel,
+    model: &ModelRefs<'_>,
+    transit_data: &Data,
+) -> Result<Vec<NaiveDateTime>, BadRequest>
+where
+    Data: DataWithIters,
+{
+    let calendar = transit_data.calendar();
+    let from_time = parse_datetime(
+        &std::cmp::max(*from_datetime, calendar.first_datetime()),
+        calendar,
+    )?;
+    let until_time = parse_datetime(until_datetime, calendar)?;
+    let arrivals = parse_arrivals(arrivals_stop_and_fallback_duration, model, transit_data)?;
+
+    let one_second = PositiveDuration::from_hms(0, 0, 1);
+    let mut result = Vec::new();
+    for (stop, fallback_duration) in arrivals.iter() {
+        let Some(max_debark_time) = until_time.checked_sub(fallback_duration) else {
+            continue;
+        };
+        for (mission, position) in transit_data.missions_at(stop) {
+            let mut waiting_time = max_debark_time;
+            while let Some((trip, _, _)) = transit_data.latest_trip_that_debark(
+                waiting_time,
+                &mission,
+                &position,
+                real_time_level,
+                |_| true,
+            ) {
+                let Some(debark_time) = transit_data.debark_time_of(&trip, &position) else {
+                    break;
+                };
+                if debark_time > waiting_time {
+                    break;
+                }
+                let arrival_time = debark_time + *fallback_duration;
+                if arrival_time < from_time {
+                    break;
+                }
+                result.push(arrival_time);
+                match debark_time.checked_sub(&one_second) {
+                    Some(time) => waiting_time = time,
+                    None => break,
+                }
+            }
+        }
+    }
+    result.sort_unstable();
+    result.dedup();
+    Ok(result
+        .into_iter()
+        .map(|time| calendar.to_naive_datetime(time))
+        .collect())
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Departure {
     pub(super) idx: usize,
@@ -0,0 +1,213 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{time::PositiveDuration, transit_data::data_interface::DataWithIters};
+
+/// Lower bounds on the duration needed to travel between the stops of
+/// a request and every other stop.
+///
+/// They are obtained by a single criterion search on the graph of stops,
+/// where each ride takes the smallest duration among all trips of its mission,
+/// and where no time is spent waiting for a vehicle.
+/// Hence no journey can be faster than these bounds.
+///
+/// Stops farther than `max_duration` are not explored, and are given
+/// a bound greater than `max_duration`.
+#[derive(Debug, Clone)]
+pub struct LowerBounds {
+    durations: Vec<Option<PositiveDuration>>,
+    unreached_duration: PositiveDuration,
+}
+
+impl LowerBounds {
+    /// Computes, for each stop, a lower bound on the duration needed
+    /// to go from this stop to one of `arrivals`, fallback included.
+    /// Transfers are walked at `walking_speed_factor` times the speed of the data.
+    pub fn to_arrivals<Data>(
+        transit_data: &Data,
+        arrivals: &[(Data::Stop, PositiveDuration)],
+        max_duration: PositiveDuration,
+        walking_speed_factor: f64,
+    ) -> Self
+    where
+        Data: DataWithIters,
+    {
+        let mut search = Search::new(transit_data.nb_of_stops(), max_duration);
+        for (stop, fallback_duration) in arrivals {
+            search.relax(transit_data.stop_id(stop), stop, *fallback_duration);
+        }
+
+        while let Some((stop, duration)) = search.pop() {
+            for (from_stop, transfer_durations, _) in transit_data.incoming_transfers_at(&stop) {
+                let transfer_durations =
+                    transfer_durations.with_walking_speed_factor(walking_speed_factor);
+                let new_duration = duration + transfer_durations.total_duration;
+                search.relax(transit_data.stop_id(from_stop), from_stop, new_duration);
+            }
+            for (mission, position) in transit_data.missions_at(&stop) {
+                let Some(previous_position) = transit_data.previous_on_mission(&position, &mission)
+                else {
+                    continue;
+                };
+                let Some(ride_duration) =
+                    transit_data.min_ride_duration_to_next(&previous_position, &mission)
+                else {
+                    continue;
+                };
+                let previous_stop = transit_data.stop_of(&previous_position, &mission);
+                search.relax(
+                    transit_data.stop_id(&previous_stop),
+                    &previous_stop,
+                    duration + ride_duration,
+                );
+            }
+        }
+
+        search.into_lower_bounds()
+    }
+
+    /// Computes, for each stop, a lower bound on the duration needed
+    /// to go from one of `departures` to this stop, fallback included.
+    /// Transfers are walked at `walking_speed_factor` times the speed of the data.
+    pub fn from_departures<Data>(
+        transit_data: &Data,
+        departures: &[(Data::Stop, PositiveDuration)],
+        max_duration: PositiveDuration,
+        walking_speed_factor: f64,
+    ) -> Self
+    where
+        Data: DataWithIters,
+    {
+        let mut search = Search::new(transit_data.nb_of_stops(), max_duration);
+        for (stop, fallback_duration) in departures {
+            search.relax(transit_data.stop_id(stop), stop, *fallback_duration);
+        }
+
+        while let Some((stop, duration)) = search.pop() {
+            for (to_stop, transfer_durations, _) in transit_data.outgoing_transfers_at(&stop) {
+                let transfer_durations =
+                    transfer_durations.with_walking_speed_factor(walking_speed_factor);
+                let new_duration = duration + transfer_durations.total_duration;
+                search.relax(transit_data.stop_id(to_stop), to_stop, new_duration);
+            }
+            for (mission, position) in transit_data.missions_at(&stop) {
+                let Some(ride_duration) =
+                    transit_data.min_ride_duration_to_next(&position, &mission)
+                else {
+                    continue;
+                };
+                let Some(next_position) = transit_data.next_on_mission(&position, &mission) else {
+                    continue;
+                };
+                let next_stop = transit_data.stop_of(&next_position, &mission);
+                search.relax(
+                    transit_data.stop_id(&next_stop),
+                    &next_stop,
+                    duration + ride_duration,
+                );
+            }
+        }
+
+        search.into_lower_bounds()
+    }
+
+    /// The lower bound for the stop with id `stop_id`.
+    pub fn duration_at(&self, stop_id: usize) -> PositiveDuration {
+        self.durations
+            .get(stop_id)
+            .copied()
+            .flatten()
+            .unwrap_or(self.unreached_duration)
+    }
+}
+
+// A Dijkstra search on the graph of stops
+struct Search<Stop> {
+    durations: Vec<Option<PositiveDuration>>,
+    // the stop of each stop_id that has been reached
+    stops: Vec<Option<Stop>>,
+    is_settled: Vec<bool>,
+    queue: BinaryHeap<Reverse<(PositiveDuration, usize)>>,
+    max_duration: PositiveDuration,
+}
+
+impl<Stop: Clone> Search<Stop> {
+    fn new(nb_of_stops: usize, max_duration: PositiveDuration) -> Self {
+        Self {
+            durations: vec![None; nb_of_stops],
+            stops: vec![None; nb_of_stops],
+            is_settled: vec![false; nb_of_stops],
+            queue: BinaryHeap::new(),
+            max_duration,
+        }
+    }
+
+    fn relax(&mut self, stop_id: usize, stop: &Stop, duration: PositiveDuration) {
+        if duration > self.max_duration || self.is_settled[stop_id] {
+            return;
+        }
+        let current = &mut self.durations[stop_id];
+        if matches!(current, Some(current_duration) if *current_duration <= duration) {
+            return;
+        }
+        *current = Some(duration);
+        self.stops[stop_id] = Some(stop.clone());
+        self.queue.push(Reverse((duration, stop_id)));
+    }
+
+    // Returns the unsettled stop with the smallest duration, and settles it.
+    fn pop(&mut self) -> Option<(Stop, PositiveDuration)> {
+        while let Some(Reverse((duration, stop_id))) = self.queue.pop() {
+            if self.is_settled[stop_id] {
+                continue;
+            }
+            self.is_settled[stop_id] = true;
+            if let Some(stop) = &self.stops[stop_id] {
+                return Some((stop.clone(), duration));
+            }
+        }
+        None
+    }
+
+    fn into_lower_bounds(self) -> LowerBounds {
+        LowerBounds {
+            durations: self.durations,
+            unreached_duration: self.max_duration + PositiveDuration::from_hms(0, 0, 1),
+        }
+    }
+}
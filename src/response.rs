@@ -43,7 +43,7 @@ use crate::{
 };
 use chrono::{NaiveDate, NaiveDateTime};
 
-use crate::transit_data::data_interface::Data as DataTrait;
+use crate::transit_data::{data_interface::Data as DataTrait, TransferDurations};
 
 use std::fmt::Debug;
 
@@ -131,6 +131,7 @@ pub struct Journey<Data: DataTrait> {
     pub(crate) occupancies_count: OccupanciesCount,
     pub(crate) uncertainty: Uncertainty,
     pub(crate) real_time_level: RealTimeLevel,
+    pub(crate) walking_speed_factor: f64,
 }
 #[derive(Debug, Clone)]
 pub enum VehicleLegIdx {
@@ -208,6 +209,7 @@ where
         arrival_fallback_duration: PositiveDuration,
         occupancies_count: OccupanciesCount,
         uncertainty: Uncertainty,
+        walking_speed_factor: f64,
         data: &Data,
         real_time_level: RealTimeLevel,
     ) -> Result<Self, BadJourney<Data>> {
@@ -220,6 +222,7 @@ where
             occupancies_count,
             uncertainty,
             real_time_level,
+            walking_speed_factor,
         };
 
         result.is_valid(data)?;
@@ -261,7 +264,7 @@ where
 
         for (idx, (transfer, vehicle_leg)) in self.connections.iter().enumerate() {
             let (transfer_from_stop, transfer_to_stop) = data.transfer_from_to_stop(transfer);
-            let transfer_duration = data.transfer_durations(transfer).total_duration;
+            let transfer_duration = self.transfer_durations(transfer, data).total_duration;
 
             if !data.is_same_stop(&prev_debark_stop, &transfer_from_stop) {
                 return Err(BadJourney::BadTransferStartStop(
@@ -351,10 +354,16 @@ where
         last_debark_time + self.arrival_fallback_duration
     }
 
+    // the durations of `transfer` at the walking speed of the request
+    fn transfer_durations(&self, transfer: &Data::Transfer, data: &Data) -> TransferDurations {
+        data.transfer_durations(transfer)
+            .with_walking_speed_factor(self.walking_speed_factor)
+    }
+
     pub fn total_transfer_walking_duration(&self, data: &Data) -> PositiveDuration {
         let mut result = PositiveDuration::zero();
         for (transfer, _) in &self.connections {
-            let transfer_duration = data.transfer_durations(transfer).walking_duration;
+            let transfer_duration = self.transfer_durations(transfer, data).walking_duration;
             result = result + transfer_duration;
         }
         result
@@ -588,7 +597,7 @@ impl<Data: DataTrait> Journey<Data> {
 
         let (transfer, _) = &self.connections[connection_idx];
         let (transfer_from_stop, transfer_to_stop) = data.transfer_from_to_stop(transfer);
-        let transfer_duration = data.transfer_durations(transfer).walking_duration;
+        let transfer_duration = self.transfer_durations(transfer, data).walking_duration;
         let end_transfer_time = prev_debark_time + transfer_duration;
         let to_datetime = data.to_naive_datetime(end_transfer_time);
         let to_stop_point = data.stop_point_idx(&transfer_to_stop);
@@ -646,6 +655,13 @@ impl<'journey, 'data, Data: DataTrait> Iterator for ConnectionIter<'journey, 'da
 }
 
 impl VehicleSection {
+    fn is_same_vehicle_section(&self, other: &VehicleSection) -> bool {
+        self.vehicle_journey == other.vehicle_journey
+            && self.day_for_vehicle_journey == other.day_for_vehicle_journey
+            && self.from_stoptime_idx == other.from_stoptime_idx
+            && self.to_stoptime_idx == other.to_stoptime_idx
+    }
+
     fn duration_in_seconds(&self) -> i64 {
         let duration = self.to_datetime - self.from_datetime;
         duration.num_seconds()
@@ -803,6 +819,28 @@ impl Response {
         self.connections.len() + 1
     }
 
+    /// Returns true if both responses leave and arrive at the same datetimes
+    /// and use the same sequence of vehicles
+    pub fn is_same_journey(&self, other: &Response) -> bool {
+        self.departure.from_datetime == other.departure.from_datetime
+            && self.arrival.to_datetime == other.arrival.to_datetime
+            && self.has_same_vehicles(other)
+    }
+
+    /// Returns true if both responses use the same sequence of vehicles,
+    /// whatever the datetimes of their departures and arrivals
+    pub fn has_same_vehicles(&self, other: &Response) -> bool {
+        self.connections.len() == other.connections.len()
+            && self
+                .first_vehicle
+                .is_same_vehicle_section(&other.first_vehicle)
+            && self.connections.iter().zip(other.connections.iter()).all(
+                |((_, _, vehicle), (_, _, other_vehicle))| {
+                    vehicle.is_same_vehicle_section(other_vehicle)
+                },
+            )
+    }
+
     pub fn print(&self, model: &ModelRefs<'_>) -> Result<String, std::fmt::Error> {
         let mut result = String::new();
         self.write(model, &mut result)?;
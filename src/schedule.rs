@@ -115,6 +115,12 @@ where
             Filter::Vehicle(VehicleFilter::CommercialMode(id)) => {
                 model.stop_points_of_commercial_mode(id)
             }
+            Filter::Vehicle(vehicle_filter) => {
+                model.stop_points_of_vehicle_journeys(|idx| vehicle_filter.applies_on(idx, model))
+            }
+            Filter::Stop(StopFilter::Equipment(property_key)) => {
+                model.stop_points_with_equipment(property_key)
+            }
         };
 
         for forbidden_uri in forbidden_uris {
@@ -131,6 +137,10 @@ where
                 Some(Filter::Stop(StopFilter::StopArea(stop_area_id))) => {
                     stop_points.retain(|stop_idx| model.stop_area_id(stop_idx) != stop_area_id);
                 }
+                Some(Filter::Stop(StopFilter::Equipment(property_key))) => {
+                    stop_points
+                        .retain(|stop_idx| !model.stop_point_property(stop_idx, property_key));
+                }
                 Some(_) => {
                     warn!("Unexpected forbidden_uri {} provided in a next_stop_time request. I'm gonna ignore it.", forbidden_uri.as_ref());
                 }
@@ -320,6 +320,17 @@ impl SecondsSinceUTCDayStart {
             seconds: -MAX_SECONDS_IN_UTC_DAY,
         }
     }
+
+    pub fn total_seconds(&self) -> i32 {
+        self.seconds
+    }
+
+    pub fn duration_since(&self, start_time: &SecondsSinceUTCDayStart) -> Option<PositiveDuration> {
+        let seconds_i32 = self.seconds.checked_sub(start_time.seconds)?;
+        u32::try_from(seconds_i32)
+            .ok()
+            .map(|seconds| PositiveDuration { seconds })
+    }
 }
 
 impl Display for SecondsSinceTimezonedDayStart {
@@ -347,6 +358,10 @@ impl Debug for SecondsSinceTimezonedDayStart {
 }
 
 impl SecondsSinceDatasetUTCStart {
+    pub fn total_seconds(&self) -> u32 {
+        self.seconds
+    }
+
     pub fn duration_since(
         &self,
         start_datetime: &SecondsSinceDatasetUTCStart,
@@ -355,6 +370,12 @@ impl SecondsSinceDatasetUTCStart {
             .checked_sub(start_datetime.seconds)
             .map(|seconds| PositiveDuration { seconds })
     }
+
+    pub fn checked_sub(&self, duration: &PositiveDuration) -> Option<Self> {
+        self.seconds
+            .checked_sub(duration.seconds)
+            .map(|seconds| Self { seconds })
+    }
 }
 
 impl Display for SecondsSinceUTCDayStart {
@@ -165,6 +165,20 @@ impl Calendar {
         }
     }
 
+    /// Returns the day that comes `nb_of_days` after `day` (or before, when negative),
+    /// if it belongs to the calendar.
+    pub fn shift_day(
+        &self,
+        day: DaysSinceDatasetStart,
+        nb_of_days: i32,
+    ) -> Option<DaysSinceDatasetStart> {
+        let days = i32::from(day.days) + nb_of_days;
+        u16::try_from(days)
+            .ok()
+            .filter(|days| *days <= self.last_day_offset)
+            .map(|days| DaysSinceDatasetStart { days })
+    }
+
     pub fn decompose_utc(
         &self,
         datetime_to_decompose: SecondsSinceDatasetUTCStart,
@@ -98,18 +98,18 @@ pub struct Position {
     pub(super) idx: PositionIdx,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
 pub(super) struct VehicleIdx {
     pub(super) idx: usize,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct Vehicle {
     pub(super) timetable: Timetable,
     pub(super) idx: VehicleIdx,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Trip {
     pub(super) vehicle: Vehicle,
     pub(super) day: DaysSinceDatasetStart,
@@ -134,7 +134,22 @@ where
         if !self.can_board(position) {
             return None;
         }
+        self.earliest_vehicle_departing(waiting_time, position, filter)
+    }
 
+    // Returns `Some(best_vehicle_idx)`
+    // where `best_vehicle_idx` is the idx of the vehicle with the earliest departure time, among those on which `filter` returns true,
+    // that departs from `position` after or at `waiting_time`, whether boarding is allowed at `position` or not.
+    // Returns None if no vehicle departs from `position` after or at `waiting_time`.
+    pub(super) fn earliest_vehicle_departing<Filter>(
+        &self,
+        waiting_time: &Time,
+        position: PositionIdx,
+        filter: Filter,
+    ) -> Option<VehicleIdx>
+    where
+        Filter: Fn(&VehicleData) -> bool,
+    {
         let nb_of_vehicles = self.board_times_by_position[position.idx].len();
         if nb_of_vehicles == 0 {
             return None;
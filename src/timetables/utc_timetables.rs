@@ -49,12 +49,12 @@ use crate::{
 
 use super::{
     day_to_timetable::LocalZone,
-    generic_timetables::{GenericTimetables, PositionIdx, Vehicle, VehicleIdx},
+    generic_timetables::{GenericTimetables, PositionIdx, TimetableData, Vehicle, VehicleIdx},
     timetable_iters::{PositionsIter, TimetableIter},
 };
 use crate::time::{
-    Calendar, DaysSinceDatasetStart, SecondsSinceDatasetUTCStart, SecondsSinceTimezonedDayStart,
-    SecondsSinceUTCDayStart, TimezonesPatterns,
+    Calendar, DaysSinceDatasetStart, PositiveDuration, SecondsSinceDatasetUTCStart,
+    SecondsSinceTimezonedDayStart, SecondsSinceUTCDayStart, TimezonesPatterns,
 };
 use chrono::NaiveDate;
 use std::collections::{BTreeMap, HashMap};
@@ -63,6 +63,10 @@ use tracing::error;
 use crate::timetables::FlowDirection;
 
 pub use super::generic_timetables::{Position, Timetable as Mission, Trip};
+pub use super::timetable_iters::TimetableIter as MissionsIter;
+
+mod trip_transfers;
+pub use trip_transfers::{Board, TripTransfers, TripTransfersAt};
 
 pub struct UTCTimetables {
     timetables: GenericTimetables<SecondsSinceUTCDayStart, Occupancy, VehicleData>,
@@ -76,6 +80,9 @@ pub struct VehicleData {
     real_time_days_pattern: DaysPattern,
     local_zone: LocalZone,
     regularity: Regularity,
+    // grams of CO2 emitted between each pair of consecutive stops,
+    // empty if unknown
+    co2_emissions: Vec<f32>,
 }
 
 impl UTCTimetables {
@@ -105,6 +112,18 @@ impl UTCTimetables {
         self.timetables.vehicle_data(&trip.vehicle).regularity
     }
 
+    // Returns the grams of CO2 emitted by `trip` between the stop before `position` and `position`,
+    // or 0 if `position` is the first position of the mission of `trip`.
+    pub fn co2_emission_before(&self, trip: &Trip, position: &Position) -> f32 {
+        let co2_emissions = &self.timetables.vehicle_data(&trip.vehicle).co2_emissions;
+        let position_idx = self.timetables.stoptime_idx(position).idx;
+        position_idx
+            .checked_sub(1)
+            .and_then(|previous_idx| co2_emissions.get(previous_idx))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
     pub fn stoptime_idx(&self, position: &Position, _trip: &Trip) -> StopTimeIdx {
         self.timetables.stoptime_idx(position)
     }
@@ -150,6 +169,25 @@ impl UTCTimetables {
         self.timetables.previous_position(position, mission)
     }
 
+    // Returns the smallest duration, among all vehicles of `mission`,
+    // between the departure from `position` and the arrival at the next position.
+    // Returns None if `position` is the last of `mission` or if `mission` has no vehicle.
+    pub fn min_ride_duration_to_next(
+        &self,
+        position: &Position,
+        mission: &Mission,
+    ) -> Option<PositiveDuration> {
+        let next_position = self.timetables.next_position(position, mission)?;
+        self.timetables
+            .vehicles(mission)
+            .filter_map(|vehicle| {
+                let departure_time = self.timetables.departure_time(&vehicle, position);
+                let arrival_time = self.timetables.arrival_time(&vehicle, &next_position);
+                arrival_time.duration_since(departure_time)
+            })
+            .min()
+    }
+
     pub fn arrival_time_of(
         &self,
         trip: &Trip,
@@ -371,6 +409,7 @@ impl UTCTimetables {
         local_zone: LocalZone,
         real_time_level: RealTimeLevel,
         regularity: Regularity,
+        co2_emissions: &[f32],
     ) -> Result<HashMap<Mission, DaysPattern>, (VehicleTimesError, Vec<NaiveDate>)>
     where
         Stops: Iterator<Item = Stop> + ExactSizeIterator + Clone,
@@ -453,6 +492,7 @@ impl UTCTimetables {
                     real_time_days_pattern,
                     local_zone,
                     regularity,
+                    co2_emissions: co2_emissions.to_vec(),
                 };
 
                 let apply_offset = |time_in_timezoned_day: SecondsSinceTimezonedDayStart| -> SecondsSinceUTCDayStart {
@@ -605,6 +645,32 @@ impl UTCTimetables {
         )
     }
 
+    /// Same as `trips_boardable_between()`, but the trips are returned
+    /// even if boarding is not allowed at `position`.
+    pub fn trips_departing_between<'a>(
+        &'a self,
+        from_time: SecondsSinceDatasetUTCStart,
+        until_time: SecondsSinceDatasetUTCStart,
+        mission: &Mission,
+        position: &Position,
+        real_time_level: RealTimeLevel,
+        days_patterns: &'a DaysPatterns,
+        calendar: &'a Calendar,
+    ) -> TripsDepartingBetween<'a> {
+        debug_assert!(position.timetable == *mission);
+
+        TripsDepartingBetween::new(
+            self,
+            real_time_level,
+            days_patterns,
+            calendar,
+            mission.clone(),
+            position.idx,
+            from_time,
+            until_time,
+        )
+    }
+
     pub fn trips_debarkable_between<'a>(
         &'a self,
         from_time: SecondsSinceDatasetUTCStart,
@@ -721,7 +787,11 @@ impl<'a> Iterator for TripsIter<'a> {
 
 pub type TripsBoardableBetween<'a> = TripsBetween<'a, true>;
 pub type TripsDebarkableBetween<'a> = TripsBetween<'a, false>;
-pub struct TripsBetween<'a, const BOARD_TIMES: bool> {
+pub type TripsDepartingBetween<'a> = TripsBetween<'a, true, false>;
+
+// When CHECK_FLOWS is false, trips are returned even if boarding (or debarking)
+// is not allowed at position
+pub struct TripsBetween<'a, const BOARD_TIMES: bool, const CHECK_FLOWS: bool = true> {
     // first iterate on days, and then iterate on NextBoardableVehicle on this day
     utc_timetables: &'a UTCTimetables,
     real_time_level: RealTimeLevel,
@@ -738,7 +808,9 @@ pub struct TripsBetween<'a, const BOARD_TIMES: bool> {
     current_until_time_in_day: SecondsSinceUTCDayStart,
 }
 
-impl<'a, const BOARD_TIMES: bool> TripsBetween<'a, BOARD_TIMES> {
+impl<'a, const BOARD_TIMES: bool, const CHECK_FLOWS: bool>
+    TripsBetween<'a, BOARD_TIMES, CHECK_FLOWS>
+{
     fn new(
         utc_timetables: &'a UTCTimetables,
         real_time_level: RealTimeLevel,
@@ -777,15 +849,9 @@ impl<'a, const BOARD_TIMES: bool> TripsBetween<'a, BOARD_TIMES> {
             .min_by(|(day_a, _), (day_b, _)| day_a.days.cmp(&day_b.days));
         if let Some((from_day, from_time_in_day)) = has_first_day {
             // find first vehicle that depart after from_time_in_day
-            let current_vehicle_idx = if BOARD_TIMES {
-                timetable_data
-                    .earliest_vehicle_to_board(&from_time_in_day, position_idx, |_| true)
-                    .unwrap_or(after_last_vehicle_idx)
-            } else {
-                timetable_data
-                    .earliest_vehicle_that_debark(&from_time_in_day, position_idx, |_| true)
-                    .unwrap_or(after_last_vehicle_idx)
-            };
+            let current_vehicle_idx =
+                Self::earliest_vehicle(timetable_data, &from_time_in_day, position_idx)
+                    .unwrap_or(after_last_vehicle_idx);
 
             let until_time_in_day = match calendar.decompose_utc(until_time, from_day) {
                 DecomposeUTCResult::BelowMin => {
@@ -817,9 +883,29 @@ impl<'a, const BOARD_TIMES: bool> TripsBetween<'a, BOARD_TIMES> {
             empty_iterator
         }
     }
+
+    fn earliest_vehicle(
+        timetable_data: &TimetableData<SecondsSinceUTCDayStart, Occupancy, VehicleData>,
+        time_in_day: &SecondsSinceUTCDayStart,
+        position_idx: PositionIdx,
+    ) -> Option<VehicleIdx> {
+        match (BOARD_TIMES, CHECK_FLOWS) {
+            (true, true) => {
+                timetable_data.earliest_vehicle_to_board(time_in_day, position_idx, |_| true)
+            }
+            (true, false) => {
+                timetable_data.earliest_vehicle_departing(time_in_day, position_idx, |_| true)
+            }
+            (false, _) => {
+                timetable_data.earliest_vehicle_that_debark(time_in_day, position_idx, |_| true)
+            }
+        }
+    }
 }
 
-impl<'a, const BOARD_TIMES: bool> Iterator for TripsBetween<'a, BOARD_TIMES> {
+impl<'a, const BOARD_TIMES: bool, const CHECK_FLOWS: bool> Iterator
+    for TripsBetween<'a, BOARD_TIMES, CHECK_FLOWS>
+{
     type Item = Trip;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -897,21 +983,12 @@ impl<'a, const BOARD_TIMES: bool> Iterator for TripsBetween<'a, BOARD_TIMES> {
                         };
 
                     // find first vehicle that depart after from_time_in_day
-                    if BOARD_TIMES {
-                        self.current_vehicle_idx = timetable_data
-                            .earliest_vehicle_to_board(&from_time_in_day, self.position_idx, |_| {
-                                true
-                            })
-                            .unwrap_or(after_last_vehicle_idx);
-                    } else {
-                        self.current_vehicle_idx = timetable_data
-                            .earliest_vehicle_that_debark(
-                                &from_time_in_day,
-                                self.position_idx,
-                                |_| true,
-                            )
-                            .unwrap_or(after_last_vehicle_idx);
-                    }
+                    self.current_vehicle_idx = Self::earliest_vehicle(
+                        timetable_data,
+                        &from_time_in_day,
+                        self.position_idx,
+                    )
+                    .unwrap_or(after_last_vehicle_idx);
 
                     self.current_until_time_in_day = until_time_in_day;
                 }
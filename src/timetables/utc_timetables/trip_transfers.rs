@@ -0,0 +1,579 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use crate::{
+    occupancy_data::Occupancy,
+    time::{
+        days_patterns::{DaysPattern, DaysPatterns},
+        Calendar, DaysSinceDatasetStart, SecondsSinceUTCDayStart,
+    },
+    timetables::generic_timetables::{PositionIdx, TimetableData, Vehicle, VehicleIdx},
+    transit_data::{StopData, Transfer},
+    RealTimeLevel,
+};
+
+use super::{Mission, Position, Trip, UTCTimetables, VehicleData};
+
+const SECONDS_IN_A_DAY: i64 = 24 * 60 * 60;
+
+/// A transfer that requires to wait more than this duration
+/// before boarding is not stored.
+const MAX_WAITING_SECONDS: i64 = 2 * SECONDS_IN_A_DAY;
+
+/// Trip-to-trip transfers, precomputed for the `TripBased` engine.
+///
+/// For each vehicle, each position at which it can be debarked
+/// and each transfer that can be taken there, we store the (mission, position)
+/// that can be boarded at the end of the transfer, along with the vehicles
+/// that may be the earliest one to board, depending on the day of the trip debarked.
+///
+/// A vehicle to board is marked as useless when it does not reach any stop
+/// earlier than what can already be obtained by staying in the trip debarked,
+/// or by debarking it later, on all its days.
+/// Such transfers are skipped when answering a query on `TransitData`.
+pub struct TripTransfers {
+    // `missions[mission][vehicle][position]` holds the transfers
+    // that can be taken after debarking `vehicle` at `position`
+    missions: Vec<Vec<Vec<Vec<TransferBoards>>>>,
+}
+
+struct TransferBoards {
+    transfer: Transfer,
+    boards: Vec<Board>,
+}
+
+/// A (mission, position) that can be boarded at the end of a transfer.
+pub struct Board {
+    mission: Mission,
+    position: Position,
+    // ordered by increasing arrival time at the position following `position`
+    candidates: Vec<Candidate>,
+}
+
+struct Candidate {
+    vehicle: VehicleIdx,
+    // the vehicle to board runs `day_offset` days after the day of the trip debarked
+    day_offset: i32,
+    is_useful: bool,
+}
+
+impl Board {
+    pub fn mission(&self) -> &Mission {
+        &self.mission
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+}
+
+impl Default for TripTransfers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TripTransfers {
+    pub fn new() -> Self {
+        Self {
+            missions: Vec::new(),
+        }
+    }
+
+    /// Computes the transfers from the vehicles of all missions.
+    pub fn compute(
+        &mut self,
+        timetables: &UTCTimetables,
+        stops_data: &[StopData],
+        calendar: &Calendar,
+        days_patterns: &DaysPatterns,
+    ) {
+        self.missions.clear();
+        self.update(
+            timetables.missions(),
+            timetables,
+            stops_data,
+            calendar,
+            days_patterns,
+        );
+    }
+
+    /// Recomputes the transfers from the vehicles of `missions`.
+    pub fn update<Missions>(
+        &mut self,
+        missions: Missions,
+        timetables: &UTCTimetables,
+        stops_data: &[StopData],
+        calendar: &Calendar,
+        days_patterns: &DaysPatterns,
+    ) where
+        Missions: Iterator<Item = Mission>,
+    {
+        self.missions
+            .resize_with(timetables.nb_of_missions(), Vec::new);
+        let mut best_times = BestTimes::new(stops_data.len());
+        for mission in missions {
+            let transfers = timetables.trip_transfers_of(
+                &mission,
+                stops_data,
+                calendar,
+                days_patterns,
+                &mut best_times,
+            );
+            self.missions[mission.idx] = transfers;
+        }
+    }
+
+    pub fn nb_of_transfers(&self) -> usize {
+        self.missions
+            .iter()
+            .flatten()
+            .flatten()
+            .flatten()
+            .map(|transfer_boards| transfer_boards.boards.len())
+            .sum()
+    }
+
+    /// The (mission, position) that can be boarded after debarking `trip`
+    /// at `position` and taking `transfer`.
+    pub fn boards_at(&self, trip: &Trip, position: &Position, transfer: &Transfer) -> &[Board] {
+        self.missions
+            .get(trip.vehicle.timetable.idx)
+            .and_then(|vehicles| vehicles.get(trip.vehicle.idx.idx))
+            .and_then(|positions| positions.get(position.idx.idx))
+            .and_then(|transfers| {
+                transfers
+                    .iter()
+                    .find(|transfer_boards| transfer_boards.transfer == *transfer)
+            })
+            .map(|transfer_boards| transfer_boards.boards.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+/// The trips to board after debarking a trip and taking a transfer,
+/// as precomputed in `TripTransfers`.
+pub struct TripTransfersAt<'a> {
+    boards: std::slice::Iter<'a, Board>,
+    day: DaysSinceDatasetStart,
+    real_time_level: RealTimeLevel,
+    timetables: &'a UTCTimetables,
+    calendar: &'a Calendar,
+    days_patterns: &'a DaysPatterns,
+}
+
+impl<'a> TripTransfersAt<'a> {
+    pub fn new(
+        boards: &'a [Board],
+        day: DaysSinceDatasetStart,
+        real_time_level: RealTimeLevel,
+        timetables: &'a UTCTimetables,
+        calendar: &'a Calendar,
+        days_patterns: &'a DaysPatterns,
+    ) -> Self {
+        Self {
+            boards: boards.iter(),
+            day,
+            real_time_level,
+            timetables,
+            calendar,
+            days_patterns,
+        }
+    }
+}
+
+impl<'a> Iterator for TripTransfersAt<'a> {
+    type Item = (Mission, Position, Trip);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for board in self.boards.by_ref() {
+            let timetable_data = self.timetables.timetables.timetable_data(&board.mission);
+            // the first candidate that runs is the earliest vehicle to board
+            let has_candidate = board.candidates.iter().find_map(|candidate| {
+                let vehicle_data = timetable_data.vehicle_data(candidate.vehicle);
+                let days_pattern = days_pattern_of(vehicle_data, self.real_time_level);
+                let day = self.calendar.shift_day(self.day, candidate.day_offset)?;
+                self.days_patterns
+                    .is_allowed(&days_pattern, day)
+                    .then_some((candidate, day))
+            });
+            if let Some((candidate, day)) = has_candidate {
+                if candidate.is_useful {
+                    let trip = Trip {
+                        vehicle: Vehicle {
+                            timetable: board.mission.clone(),
+                            idx: candidate.vehicle,
+                        },
+                        day,
+                    };
+                    return Some((board.mission.clone(), board.position.clone(), trip));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn days_pattern_of(vehicle_data: &VehicleData, real_time_level: RealTimeLevel) -> DaysPattern {
+    match real_time_level {
+        RealTimeLevel::Base => vehicle_data.base_days_pattern,
+        RealTimeLevel::RealTime => vehicle_data.real_time_days_pattern,
+    }
+}
+
+type UTCTimetableData = TimetableData<SecondsSinceUTCDayStart, Occupancy, VehicleData>;
+
+impl UTCTimetables {
+    fn trip_transfers_of(
+        &self,
+        mission: &Mission,
+        stops_data: &[StopData],
+        calendar: &Calendar,
+        days_patterns: &DaysPatterns,
+        best_times: &mut BestTimes,
+    ) -> Vec<Vec<Vec<TransferBoards>>> {
+        let timetable_data = self.timetables.timetable_data(mission);
+        (0..timetable_data.nb_of_vehicle())
+            .map(|idx| {
+                self.trip_transfers_of_vehicle(
+                    timetable_data,
+                    VehicleIdx { idx },
+                    stops_data,
+                    calendar,
+                    days_patterns,
+                    best_times,
+                )
+            })
+            .collect()
+    }
+
+    fn trip_transfers_of_vehicle(
+        &self,
+        timetable_data: &UTCTimetableData,
+        vehicle: VehicleIdx,
+        stops_data: &[StopData],
+        calendar: &Calendar,
+        days_patterns: &DaysPatterns,
+        best_times: &mut BestTimes,
+    ) -> Vec<Vec<TransferBoards>> {
+        let nb_of_positions = timetable_data.nb_of_positions();
+        let vehicle_days = RemainingDays::new(timetable_data.vehicle_data(vehicle), days_patterns);
+
+        let mut result = Vec::new();
+        result.resize_with(nb_of_positions, Vec::new);
+        best_times.clear();
+
+        // positions are explored backward, so that `best_times` holds
+        // what can be obtained by debarking the vehicle later
+        for idx in (1..nb_of_positions).rev() {
+            let position = PositionIdx { idx };
+            let Some(debark_time) = timetable_data.debark_time(vehicle, position) else {
+                continue;
+            };
+            let debark_time = i64::from(debark_time.total_seconds());
+            let stop = timetable_data.stop_at(position);
+            best_times.improve_after_debark(stop.idx, debark_time, stops_data, true);
+
+            for (to_stop, durations, transfer) in &stops_data[stop.idx].outgoing_transfers {
+                let ready_time =
+                    debark_time + i64::from(durations.total_duration.total_seconds_u32());
+                let mut boards = Vec::new();
+                for (mission, position) in &stops_data[to_stop.idx].position_in_timetables {
+                    let has_board = self.board_after_transfer(
+                        ready_time,
+                        mission,
+                        position,
+                        &vehicle_days,
+                        stops_data,
+                        calendar,
+                        days_patterns,
+                        best_times,
+                    );
+                    boards.extend(has_board);
+                }
+                if !boards.is_empty() {
+                    result[idx].push(TransferBoards {
+                        transfer: *transfer,
+                        boards,
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    // The vehicles of `mission` that may be the earliest one to board at `position`
+    // when waiting there from `ready_time`, depending on the day of the trip debarked.
+    #[allow(clippy::too_many_arguments)]
+    fn board_after_transfer(
+        &self,
+        ready_time: i64,
+        mission: &Mission,
+        position: &Position,
+        vehicle_days: &RemainingDays,
+        stops_data: &[StopData],
+        calendar: &Calendar,
+        days_patterns: &DaysPatterns,
+        best_times: &mut BestTimes,
+    ) -> Option<Board> {
+        let timetable_data = self.timetables.timetable_data(mission);
+        let position_idx = position.idx;
+        if !timetable_data.can_board(position_idx)
+            || position_idx.idx + 1 >= timetable_data.nb_of_positions()
+        {
+            return None;
+        }
+        let next_position = PositionIdx {
+            idx: position_idx.idx + 1,
+        };
+        let board_times = &timetable_data.board_times_by_position[position_idx.idx];
+        let first_board_time = i64::from(board_times.first()?.total_seconds());
+        let last_board_time = i64::from(board_times.last()?.total_seconds());
+        let latest_board_time = ready_time + MAX_WAITING_SECONDS;
+
+        // the vehicles that can be boarded on each day offset,
+        // as (offset, index of the next vehicle to consider)
+        let min_day_offset = -(last_board_time - ready_time).div_euclid(SECONDS_IN_A_DAY);
+        let max_day_offset = (latest_board_time - first_board_time).div_euclid(SECONDS_IN_A_DAY);
+        let mut next_vehicles: Vec<(i64, usize)> = (min_day_offset..=max_day_offset)
+            .map(|day_offset| {
+                let shift = day_offset * SECONDS_IN_A_DAY;
+                let first_vehicle = board_times
+                    .partition_point(|time| i64::from(time.total_seconds()) + shift < ready_time);
+                (day_offset, first_vehicle)
+            })
+            .collect();
+
+        let mut remaining_days = vehicle_days.clone();
+        let mut candidates = Vec::new();
+        while !remaining_days.is_empty() {
+            // the vehicle with the earliest arrival at the next position, among all day offsets
+            let has_earliest = next_vehicles
+                .iter()
+                .enumerate()
+                .filter_map(|(offset_idx, (day_offset, vehicle_idx))| {
+                    let shift = day_offset * SECONDS_IN_A_DAY;
+                    let board_time = i64::from(board_times.get(*vehicle_idx)?.total_seconds());
+                    if board_time + shift > latest_board_time {
+                        return None;
+                    }
+                    let vehicle = VehicleIdx { idx: *vehicle_idx };
+                    let arrival_time = timetable_data.arrival_time(vehicle, next_position);
+                    Some((i64::from(arrival_time.total_seconds()) + shift, offset_idx))
+                })
+                .min();
+            let Some((_, offset_idx)) = has_earliest else {
+                break;
+            };
+            let (day_offset, vehicle_idx) = next_vehicles[offset_idx];
+            next_vehicles[offset_idx].1 += 1;
+
+            let vehicle = VehicleIdx { idx: vehicle_idx };
+            // offsets are bounded by a few days, so the cast is safe
+            let day_offset = day_offset as i32;
+            let was_full = remaining_days.len() == vehicle_days.len();
+            let nb_of_days_covered = remaining_days.remove_days_of(
+                timetable_data.vehicle_data(vehicle),
+                day_offset,
+                calendar,
+                days_patterns,
+            );
+            if nb_of_days_covered == 0 {
+                // this vehicle is never the earliest one
+                continue;
+            }
+            // `best_times` can be improved only by a vehicle that
+            // is the earliest one to board on all days of the vehicle debarked
+            let runs_on_all_days = was_full && remaining_days.is_empty();
+            let is_useful = best_times.improve_with_vehicle(
+                timetable_data,
+                vehicle,
+                position_idx,
+                i64::from(day_offset) * SECONDS_IN_A_DAY,
+                stops_data,
+                runs_on_all_days,
+            );
+            candidates.push(Candidate {
+                vehicle,
+                day_offset,
+                is_useful,
+            });
+        }
+
+        if candidates.is_empty() {
+            None
+        } else {
+            Some(Board {
+                mission: mission.clone(),
+                position: position.clone(),
+                candidates,
+            })
+        }
+    }
+}
+
+// The days of a vehicle, on each real time level.
+#[derive(Clone)]
+struct RemainingDays {
+    base: Vec<DaysSinceDatasetStart>,
+    real_time: Vec<DaysSinceDatasetStart>,
+}
+
+impl RemainingDays {
+    fn new(vehicle_data: &VehicleData, days_patterns: &DaysPatterns) -> Self {
+        Self {
+            base: days_patterns
+                .days_in_pattern(&vehicle_data.base_days_pattern)
+                .collect(),
+            real_time: days_patterns
+                .days_in_pattern(&vehicle_data.real_time_days_pattern)
+                .collect(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.base.len() + self.real_time.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.base.is_empty() && self.real_time.is_empty()
+    }
+
+    // Removes the days `day` such that the vehicle of `vehicle_data`
+    // runs on `day + day_offset`, and returns the number of days removed.
+    fn remove_days_of(
+        &mut self,
+        vehicle_data: &VehicleData,
+        day_offset: i32,
+        calendar: &Calendar,
+        days_patterns: &DaysPatterns,
+    ) -> usize {
+        let nb_of_days = self.len();
+        let runs = |day: &DaysSinceDatasetStart, days_pattern: &DaysPattern| {
+            calendar
+                .shift_day(*day, day_offset)
+                .is_some_and(|day| days_patterns.is_allowed(days_pattern, day))
+        };
+        self.base
+            .retain(|day| !runs(day, &vehicle_data.base_days_pattern));
+        self.real_time
+            .retain(|day| !runs(day, &vehicle_data.real_time_days_pattern));
+        nb_of_days - self.len()
+    }
+}
+
+// The earliest times, in seconds since the start of the day of the vehicle debarked,
+// at which each stop can be reached from this vehicle.
+struct BestTimes {
+    // by debarking at the stop
+    arrivals: Vec<i64>,
+    // by debarking and taking a transfer to the stop
+    transfers: Vec<i64>,
+    touched_stops: Vec<usize>,
+}
+
+impl BestTimes {
+    fn new(nb_of_stops: usize) -> Self {
+        Self {
+            arrivals: vec![i64::MAX; nb_of_stops],
+            transfers: vec![i64::MAX; nb_of_stops],
+            touched_stops: Vec::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        for stop_idx in self.touched_stops.drain(..) {
+            self.arrivals[stop_idx] = i64::MAX;
+            self.transfers[stop_idx] = i64::MAX;
+        }
+    }
+
+    // Returns true if debarking at `stop_idx` at `debark_time` improves the best times.
+    // The best times are updated only when `update` is true.
+    fn improve_after_debark(
+        &mut self,
+        stop_idx: usize,
+        debark_time: i64,
+        stops_data: &[StopData],
+        update: bool,
+    ) -> bool {
+        let mut is_improved = false;
+        if debark_time < self.arrivals[stop_idx] {
+            is_improved = true;
+            if update {
+                self.arrivals[stop_idx] = debark_time;
+                self.touched_stops.push(stop_idx);
+            }
+        }
+        for (to_stop, durations, _) in &stops_data[stop_idx].outgoing_transfers {
+            let time = debark_time + i64::from(durations.total_duration.total_seconds_u32());
+            if time < self.transfers[to_stop.idx] {
+                is_improved = true;
+                if update {
+                    self.transfers[to_stop.idx] = time;
+                    self.touched_stops.push(to_stop.idx);
+                }
+            }
+        }
+        is_improved
+    }
+
+    // Returns true if boarding `vehicle` at `board_position` improves the best times.
+    fn improve_with_vehicle(
+        &mut self,
+        timetable_data: &UTCTimetableData,
+        vehicle: VehicleIdx,
+        board_position: PositionIdx,
+        shift: i64,
+        stops_data: &[StopData],
+        update: bool,
+    ) -> bool {
+        let mut is_improved = false;
+        for idx in board_position.idx + 1..timetable_data.nb_of_positions() {
+            let position = PositionIdx { idx };
+            let Some(debark_time) = timetable_data.debark_time(vehicle, position) else {
+                continue;
+            };
+            let debark_time = i64::from(debark_time.total_seconds()) + shift;
+            let stop = timetable_data.stop_at(position);
+            is_improved |= self.improve_after_debark(stop.idx, debark_time, stops_data, update);
+            if is_improved && !update {
+                return true;
+            }
+        }
+        is_improved
+    }
+}
@@ -48,7 +48,9 @@ use crate::{
     timetables::{
         day_to_timetable::VehicleJourneyToTimetable,
         generic_timetables::{PositionPair, VehicleTimesError},
-        utc_timetables::{self, TripsIter},
+        utc_timetables::{
+            self, MissionsIter, TripTransfers, TripTransfersAt, TripsDepartingBetween, TripsIter,
+        },
         InsertionError, ModifyError,
     },
     RealTimeLevel,
@@ -81,6 +83,9 @@ pub struct TransitData {
 
     pub(super) vehicle_journey_to_next_stay_in: HashMap<VehicleJourneyIdx, VehicleJourneyIdx>,
     pub(super) vehicle_journey_to_prev_stay_in: HashMap<VehicleJourneyIdx, VehicleJourneyIdx>,
+
+    // used by the trip based engine, computed on demand
+    pub(super) trip_transfers: Option<TripTransfers>,
 }
 
 pub struct StopData {
@@ -96,6 +101,34 @@ pub struct TransferDurations {
     pub total_duration: PositiveDuration, // = walking_duration + some waiting time
 }
 
+impl TransferDurations {
+    /// The durations of this transfer for a traveler that walks
+    /// `walking_speed_factor` times as fast as the one assumed by `walking_duration`.
+    ///
+    /// Only the walking part of `total_duration` is scaled.
+    pub fn with_walking_speed_factor(&self, walking_speed_factor: f64) -> Self {
+        if walking_speed_factor == 1.0 {
+            return self.clone();
+        }
+        let walking_seconds = self.walking_duration.total_seconds_u32();
+        let waiting_seconds = self
+            .total_duration
+            .total_seconds_u32()
+            .saturating_sub(walking_seconds);
+        // float to int casts saturate
+        let scaled_walking_seconds =
+            (f64::from(walking_seconds) / walking_speed_factor).ceil() as u32;
+        Self {
+            walking_duration: PositiveDuration::from_hms(0, 0, scaled_walking_seconds),
+            total_duration: PositiveDuration::from_hms(
+                0,
+                0,
+                scaled_walking_seconds.saturating_add(waiting_seconds),
+            ),
+        }
+    }
+}
+
 pub struct TransferData {
     pub from_stop: Stop,
     pub to_stop: Stop,
@@ -121,6 +154,31 @@ impl TransitData {
     pub fn stop_point_idx_to_stop(&self, stop_point_idx: &StopPointIdx) -> Option<&Stop> {
         self.stop_point_idx_to_stop.get(stop_point_idx)
     }
+
+    /// Precomputes the trip-to-trip transfers needed by the trip based engine.
+    ///
+    /// Once computed, they are kept up to date when vehicles are
+    /// inserted, modified or removed.
+    pub fn compute_trip_transfers(&mut self) {
+        let mut trip_transfers = TripTransfers::new();
+        trip_transfers.compute(
+            &self.timetables,
+            &self.stops_data,
+            &self.calendar,
+            &self.days_patterns,
+        );
+        self.trip_transfers = Some(trip_transfers);
+    }
+
+    pub fn has_trip_transfers(&self) -> bool {
+        self.trip_transfers.is_some()
+    }
+
+    pub fn nb_of_trip_transfers(&self) -> usize {
+        self.trip_transfers
+            .as_ref()
+            .map_or(0, TripTransfers::nb_of_transfers)
+    }
 }
 
 impl data_interface::TransitTypes for TransitData {
@@ -166,6 +224,14 @@ impl data_interface::Data for TransitData {
         self.timetables.previous_position(position, mission)
     }
 
+    fn min_ride_duration_to_next(
+        &self,
+        position: &Self::Position,
+        mission: &Self::Mission,
+    ) -> Option<PositiveDuration> {
+        self.timetables.min_ride_duration_to_next(position, mission)
+    }
+
     fn mission_of(&self, trip: &Self::Trip) -> Self::Mission {
         self.timetables.mission_of(trip)
     }
@@ -205,6 +271,10 @@ impl data_interface::Data for TransitData {
         self.timetables.occupancy_before(trip, position)
     }
 
+    fn co2_emission_before(&self, trip: &Self::Trip, position: &Self::Position) -> f32 {
+        self.timetables.co2_emission_before(trip, position)
+    }
+
     fn departure_time_of(
         &self,
         trip: &Self::Trip,
@@ -449,6 +519,54 @@ impl<'a> data_interface::DataIters<'a> for TransitData {
         self.timetables
             .trips_of(mission, real_time_level, &self.days_patterns)
     }
+
+    type Missions = MissionsIter;
+    fn missions(&'a self) -> Self::Missions {
+        self.timetables.missions()
+    }
+
+    type TripsDepartingBetween = TripsDepartingBetween<'a>;
+    fn trips_departing_between(
+        &'a self,
+        from_time: SecondsSinceDatasetUTCStart,
+        until_time: SecondsSinceDatasetUTCStart,
+        mission: &Self::Mission,
+        position: &Self::Position,
+        real_time_level: RealTimeLevel,
+    ) -> Self::TripsDepartingBetween {
+        self.timetables.trips_departing_between(
+            from_time,
+            until_time,
+            mission,
+            position,
+            real_time_level,
+            &self.days_patterns,
+            &self.calendar,
+        )
+    }
+
+    type TripTransfersAt = TripTransfersAt<'a>;
+    fn trip_transfers_at(
+        &'a self,
+        trip: &Self::Trip,
+        position: &Self::Position,
+        transfer: &Self::Transfer,
+        real_time_level: RealTimeLevel,
+    ) -> Self::TripTransfersAt {
+        let boards = self
+            .trip_transfers
+            .as_ref()
+            .map(|trip_transfers| trip_transfers.boards_at(trip, position, transfer))
+            .unwrap_or(&[]);
+        TripTransfersAt::new(
+            boards,
+            self.timetables.day_of(trip),
+            real_time_level,
+            &self.timetables,
+            &self.calendar,
+            &self.days_patterns,
+        )
+    }
 }
 
 impl data_interface::DataWithIters for TransitData {}
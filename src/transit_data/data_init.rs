@@ -187,6 +187,7 @@ impl TransitData {
             days_patterns: DaysPatterns::new(usize::from(nb_of_days)),
             vehicle_journey_to_next_stay_in: std::collections::HashMap::new(),
             vehicle_journey_to_prev_stay_in: std::collections::HashMap::new(),
+            trip_transfers: None,
         };
 
         data.init(base_model);
@@ -416,6 +417,15 @@ impl TransitData {
         let physical_mode_name = base_model.physical_mode_name(vehicle_journey_idx);
         let regularity = Regularity::new(physical_mode_name);
         let vehicle_journey_idx = VehicleJourneyIdx::Base(vehicle_journey_idx);
+        let co2_emissions = {
+            let real_time_model = RealTimeModel::new();
+            let model = ModelRefs {
+                base: base_model,
+                real_time: &real_time_model,
+            };
+            let stop_points: Vec<_> = stop_times.clone().map(|(_, s)| s.stop).collect();
+            model.co2_emissions_between_stops(&vehicle_journey_idx, stop_points.iter())
+        };
 
         let mut local_zones: Vec<_> = stop_times.clone().map(|(_, s)| s.local_zone_id).collect();
         local_zones.sort_unstable();
@@ -435,6 +445,7 @@ impl TransitData {
                 local_zones[0],
                 RealTimeLevel::Base,
                 regularity,
+                &co2_emissions,
             );
             let real_time_model = RealTimeModel::new();
             let model = ModelRefs {
@@ -480,6 +491,7 @@ impl TransitData {
                     local_zone,
                     RealTimeLevel::Base,
                     regularity,
+                    &co2_emissions,
                 );
 
                 let real_time_model = RealTimeModel::new();
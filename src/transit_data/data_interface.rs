@@ -2,7 +2,7 @@ use crate::{
     models::{StopPointIdx, StopTimeIdx, TransferIdx, VehicleJourneyIdx},
     occupancy_data::Occupancy,
     robustness::Regularity,
-    time::SecondsSinceDatasetUTCStart,
+    time::{PositiveDuration, SecondsSinceDatasetUTCStart},
 };
 use chrono::{NaiveDate, NaiveDateTime};
 pub use typed_index_collection::Idx;
@@ -13,7 +13,7 @@ use super::TransferDurations;
 
 pub trait TransitTypes {
     /// A location where a vehicle can be boarded into or debarked from
-    type Stop: Debug + Clone + 'static;
+    type Stop: Debug + Clone + PartialEq + 'static;
 
     /// A `Mission` is an ordered sequence of `Position`
     type Mission: Debug + Clone;
@@ -72,6 +72,18 @@ pub trait Data: TransitTypes {
         mission: &Self::Mission,
     ) -> Option<Self::Position>;
 
+    /// Returns a lower bound on the duration of a ride on any trip of `mission`,
+    /// from the departure at `position` to the arrival at the next position on `mission`.
+    ///
+    /// Returns `None` if `position` is the last on `mission`, or if `mission` has no trip.
+    ///
+    /// Panics if `position` does not belong to `mission`.
+    fn min_ride_duration_to_next(
+        &self,
+        position: &Self::Position,
+        mission: &Self::Mission,
+    ) -> Option<PositiveDuration>;
+
     /// Returns the `Mission` that `trip` belongs to.
     fn mission_of(&self, trip: &Self::Trip) -> Self::Mission;
 
@@ -105,6 +117,11 @@ pub trait Data: TransitTypes {
     // Panics if `trip` does not go through `position`
     fn occupancy_before(&self, trip: &Self::Trip, position: &Self::Position) -> Occupancy;
 
+    // Grams of CO2 emitted by `trip` between the stop before `position` and `position`,
+    // or 0 if `position` is the first of its mission.
+    // Panics if `trip` does not go through `position`
+    fn co2_emission_before(&self, trip: &Self::Trip, position: &Self::Position) -> f32;
+
     // Panics if `trip` does not go through `position`
     fn departure_time_of(
         &self,
@@ -228,6 +245,43 @@ where
         mission: &Self::Mission,
         real_time_level: RealTimeLevel,
     ) -> Self::TripsOfMission;
+
+    /// Iterator for all `Mission`s.
+    type Missions: Iterator<Item = Self::Mission>;
+    /// Returns all `Mission`s
+    fn missions(&'a self) -> Self::Missions;
+
+    /// Iterator for the `Trip`s of a `Mission` that depart from a `Position` in a time window.
+    type TripsDepartingBetween: Iterator<Item = Self::Trip>;
+    /// Returns the `Trip`s of `mission` that depart from `position` between
+    /// `from_time` and `until_time` (both included), whether boarding is allowed
+    /// at `position` or not.
+    ///
+    /// Panics if `position` does not belong to `mission`.
+    fn trips_departing_between(
+        &'a self,
+        from_time: SecondsSinceDatasetUTCStart,
+        until_time: SecondsSinceDatasetUTCStart,
+        mission: &Self::Mission,
+        position: &Self::Position,
+        real_time_level: RealTimeLevel,
+    ) -> Self::TripsDepartingBetween;
+
+    /// Iterator for the `Trip`s that can be boarded after a transfer.
+    type TripTransfersAt: Iterator<Item = (Self::Mission, Self::Position, Self::Trip)>;
+    /// Returns, for each `Mission` that can be boarded after debarking `trip`
+    /// at `position` and taking `transfer`, the earliest `Trip` to board
+    /// along with the `Position` where it is boarded.
+    ///
+    /// Uses the trip transfers precomputed in the data, and returns nothing
+    /// when they have not been computed.
+    fn trip_transfers_at(
+        &'a self,
+        trip: &Self::Trip,
+        position: &Self::Position,
+        transfer: &Self::Transfer,
+        real_time_level: RealTimeLevel,
+    ) -> Self::TripTransfersAt;
 }
 
 pub trait DataWithIters: Data + for<'a> DataIters<'a> {}
@@ -34,6 +34,7 @@
 // https://groups.google.com/d/forum/navitia
 // www.navitia.io
 
+use std::collections::HashSet;
 use tracing::error;
 
 use crate::{
@@ -63,6 +64,7 @@ impl TransitData {
             .vehicle_journey_to_timetable
             .get_vehicle_local_zones(vehicle_journey_idx);
 
+        let mut updated_missions = Vec::new();
         for local_zone in local_zones {
             let has_timetable = self.vehicle_journey_to_timetable.remove_real_time_vehicle(
                 vehicle_journey_idx,
@@ -87,11 +89,14 @@ impl TransitData {
                 &self.calendar,
                 &mut self.days_patterns,
             );
+            updated_missions.push(timetable);
         }
+        self.update_trip_transfers(updated_missions);
 
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn insert_real_time_vehicle<Stops, Flows, Dates, BoardTimes, DebarkTimes>(
         &mut self,
         stop_points: Stops,
@@ -103,6 +108,7 @@ impl TransitData {
         timezone: chrono_tz::Tz,
         vehicle_journey_idx: VehicleJourneyIdx,
         regularity: Regularity,
+        co2_emissions: &[f32],
     ) -> Result<(), InsertionError>
     where
         Stops: Iterator<Item = StopPointIdx> + ExactSizeIterator + Clone,
@@ -123,9 +129,11 @@ impl TransitData {
             None,
             RealTimeLevel::RealTime,
             regularity,
+            co2_emissions,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn modify_real_time_vehicle<Stops, Flows, Dates, BoardTimes, DebarkTimes>(
         &mut self,
         stops: Stops,
@@ -137,6 +145,7 @@ impl TransitData {
         timezone: chrono_tz::Tz,
         vehicle_journey_idx: &VehicleJourneyIdx,
         regularity: Regularity,
+        co2_emissions: &[f32],
     ) -> Result<(), ModifyError>
     where
         Stops: Iterator<Item = StopPointIdx> + ExactSizeIterator + Clone,
@@ -160,6 +169,7 @@ impl TransitData {
             .vehicle_journey_to_timetable
             .get_vehicle_local_zones(vehicle_journey_idx);
 
+        let mut updated_missions = Vec::new();
         for date in valid_dates.clone() {
             // unwrap is safe, because we checked above the validity of all dates
             let day = self.calendar.date_to_days_since_start(date).unwrap();
@@ -189,6 +199,7 @@ impl TransitData {
                     &self.calendar,
                     &mut self.days_patterns,
                 );
+                updated_missions.push(timetable);
             }
         }
 
@@ -212,6 +223,7 @@ impl TransitData {
                 local_zone,
                 RealTimeLevel::RealTime,
                 regularity,
+                co2_emissions,
             );
             let timetables = match timetables {
                 Err(err) => {
@@ -242,7 +254,9 @@ impl TransitData {
             for mission in missions {
                 self.add_mission_to_stops(mission);
             }
+            updated_missions.extend(timetables.into_keys());
         }
+        self.update_trip_transfers(updated_missions);
 
         Ok(())
     }
@@ -262,6 +276,7 @@ impl TransitData {
         local_zone: LocalZone,
         real_time_level: RealTimeLevel,
         regularity: Regularity,
+        co2_emissions: &[f32],
     ) -> Result<(), InsertionError>
     where
         Stops: Iterator<Item = StopPointIdx> + ExactSizeIterator + Clone,
@@ -327,6 +342,7 @@ impl TransitData {
                 local_zone,
                 real_time_level,
                 regularity,
+                co2_emissions,
             )
             .map_err(|(err, dates)| {
                 InsertionError::Times(vehicle_journey_idx.clone(), real_time_level, err, dates)
@@ -368,10 +384,41 @@ impl TransitData {
         for mission in missions {
             self.add_mission_to_stops(mission);
         }
+        self.update_trip_transfers(timetables.into_keys().collect());
 
         Ok(())
     }
 
+    // Recomputes the trip transfers from the vehicles of `missions`,
+    // and from the vehicles that can transfer to them,
+    // if the trip transfers have been computed.
+    fn update_trip_transfers(&mut self, missions: Vec<Mission>) {
+        let Some(mut trip_transfers) = self.trip_transfers.take() else {
+            return;
+        };
+        let mut missions_to_update = HashSet::new();
+        for mission in missions {
+            for position in self.timetables.positions(&mission) {
+                let stop = self.timetables.stop_at(&position, &mission);
+                for (from_stop, _, _) in &self.stops_data[stop.idx].incoming_transfers {
+                    let from_stop_data = &self.stops_data[from_stop.idx];
+                    for (from_mission, _) in &from_stop_data.position_in_timetables {
+                        missions_to_update.insert(from_mission.clone());
+                    }
+                }
+            }
+            missions_to_update.insert(mission);
+        }
+        trip_transfers.update(
+            missions_to_update.into_iter(),
+            &self.timetables,
+            &self.stops_data,
+            &self.calendar,
+            &self.days_patterns,
+        );
+        self.trip_transfers = Some(trip_transfers);
+    }
+
     pub(super) fn add_mission_to_stops(&mut self, mission: &Mission) {
         for position in self.timetables.positions(mission) {
             let stop = self.timetables.stop_at(&position, mission);
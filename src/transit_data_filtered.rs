@@ -39,7 +39,7 @@ use crate::{
     models::{ModelRefs, StopPointIdx, StopTimeIdx, TransferIdx, VehicleJourneyIdx},
     occupancy_data::Occupancy,
     robustness::Regularity,
-    time::{Calendar, SecondsSinceDatasetUTCStart},
+    time::{Calendar, PositiveDuration, SecondsSinceDatasetUTCStart},
     timetables::utc_timetables,
     transit_data::{self, data_interface, data_iters, TransferDurations},
     RealTimeLevel, TransitData,
@@ -186,6 +186,15 @@ impl data_interface::Data for TransitDataFiltered<'_, '_> {
         self.transit_data.previous_on_mission(position, mission)
     }
 
+    fn min_ride_duration_to_next(
+        &self,
+        position: &Self::Position,
+        mission: &Self::Mission,
+    ) -> Option<PositiveDuration> {
+        self.transit_data
+            .min_ride_duration_to_next(position, mission)
+    }
+
     fn mission_of(&self, trip: &Self::Trip) -> Self::Mission {
         self.transit_data.mission_of(trip)
     }
@@ -236,6 +245,10 @@ impl data_interface::Data for TransitDataFiltered<'_, '_> {
         self.transit_data.occupancy_before(trip, position)
     }
 
+    fn co2_emission_before(&self, trip: &Self::Trip, position: &Self::Position) -> f32 {
+        self.transit_data.co2_emission_before(trip, position)
+    }
+
     fn departure_time_of(
         &self,
         trip: &Self::Trip,
@@ -418,6 +431,137 @@ impl<'data> data_interface::DataIters<'data> for TransitDataFiltered<'_, '_> {
     ) -> Self::TripsOfMission {
         self.transit_data.trips_of(mission, real_time_level)
     }
+
+    type Missions = utc_timetables::MissionsIter;
+    fn missions(&'data self) -> Self::Missions {
+        self.transit_data.missions()
+    }
+
+    type TripsDepartingBetween = TripsDepartingBetweenFiltered<'data>;
+    fn trips_departing_between(
+        &'data self,
+        from_time: SecondsSinceDatasetUTCStart,
+        until_time: SecondsSinceDatasetUTCStart,
+        mission: &Self::Mission,
+        position: &Self::Position,
+        real_time_level: RealTimeLevel,
+    ) -> Self::TripsDepartingBetween {
+        TripsDepartingBetweenFiltered {
+            inner: self.transit_data.trips_departing_between(
+                from_time,
+                until_time,
+                mission,
+                position,
+                real_time_level,
+            ),
+            transit_data: self.transit_data,
+            memory: self.memory,
+        }
+    }
+
+    type TripTransfersAt = TripTransfersAtFiltered<'data>;
+    fn trip_transfers_at(
+        &'data self,
+        trip: &Self::Trip,
+        position: &Self::Position,
+        transfer: &Self::Transfer,
+        real_time_level: RealTimeLevel,
+    ) -> Self::TripTransfersAt {
+        use data_interface::Data;
+        let boards = self
+            .transit_data
+            .trip_transfers
+            .as_ref()
+            .map(|trip_transfers| trip_transfers.boards_at(trip, position, transfer))
+            .unwrap_or(&[]);
+        let waiting_time = self
+            .transit_data
+            .debark_time_of(trip, position)
+            .map(|debark_time| {
+                debark_time
+                    + self
+                        .transit_data
+                        .transfer_durations(transfer)
+                        .total_duration
+            });
+        TripTransfersAtFiltered {
+            boards: boards.iter(),
+            waiting_time,
+            real_time_level,
+            transit_data: self.transit_data,
+            memory: self.memory,
+        }
+    }
 }
 
 impl data_interface::DataWithIters for TransitDataFiltered<'_, '_> {}
+
+/// The trips that can be boarded after a transfer, among the ones allowed by the filters.
+///
+/// The trips precomputed in `TransitData` may be forbidden by the filters,
+/// and so may be those that make them useless.
+/// So only the missions to board are taken from the precomputation, and
+/// the earliest allowed trip of each mission is looked up in the timetables.
+pub struct TripTransfersAtFiltered<'data> {
+    boards: std::slice::Iter<'data, utc_timetables::Board>,
+    waiting_time: Option<SecondsSinceDatasetUTCStart>,
+    real_time_level: RealTimeLevel,
+    transit_data: &'data TransitData,
+    memory: &'data FilterMemory,
+}
+
+impl<'data> Iterator for TripTransfersAtFiltered<'data> {
+    type Item = (
+        utc_timetables::Mission,
+        utc_timetables::Position,
+        utc_timetables::Trip,
+    );
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use data_interface::Data;
+        let waiting_time = self.waiting_time?;
+        for board in self.boards.by_ref() {
+            let (mission, position) = (board.mission(), board.position());
+            let stop = self.transit_data.stop_of(position, mission);
+            if !self
+                .memory
+                .is_stop_allowed(&self.transit_data.stop_point_idx(&stop))
+            {
+                continue;
+            }
+            let has_trip = self.transit_data.earliest_trip_to_board(
+                waiting_time,
+                mission,
+                position,
+                self.real_time_level,
+                |vehicle_journey_idx| self.memory.is_vehicle_journey_allowed(vehicle_journey_idx),
+            );
+            if let Some((trip, _, _)) = has_trip {
+                return Some((mission.clone(), position.clone(), trip));
+            }
+        }
+        None
+    }
+}
+
+/// The trips of `TransitData::trips_departing_between()` whose
+/// vehicle journey is allowed by the filters.
+pub struct TripsDepartingBetweenFiltered<'data> {
+    inner: utc_timetables::TripsDepartingBetween<'data>,
+    transit_data: &'data TransitData,
+    memory: &'data FilterMemory,
+}
+
+impl<'data> Iterator for TripsDepartingBetweenFiltered<'data> {
+    type Item = utc_timetables::Trip;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use data_interface::Data;
+        let transit_data = self.transit_data;
+        let memory = self.memory;
+        self.inner.find(|trip| {
+            let vehicle_journey_idx = transit_data.vehicle_journey_idx(trip);
+            memory.is_vehicle_journey_allowed(&vehicle_journey_idx)
+        })
+    }
+}
@@ -34,12 +34,16 @@
 // https://groups.google.com/d/forum/navitia
 // www.navitia.io
 
+pub mod matrix;
+
 use loki_launch::{
     config,
     datetime::DateTimeRepresent,
     loki::{
         self,
+        filters::{parse_filter_expression, Filters, Preference},
         models::{base_model::BaseModel, real_time_model::RealTimeModel, ModelRefs},
+        Cancellation, InputStop, PositiveDuration,
     },
     solver::Solver,
     timer,
@@ -49,6 +53,7 @@ use loki::tracing::{debug, error, info};
 
 use std::{
     fs,
+    io::BufWriter,
     path::{Path, PathBuf},
     time::SystemTime,
 };
@@ -68,6 +73,10 @@ pub struct Options {
     /// path to the config file
     #[structopt(parse(from_os_str))]
     config_file: PathBuf,
+
+    /// path of a json file in which the trace of the search will be written
+    #[structopt(long, parse(from_os_str))]
+    trace_file: Option<PathBuf>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -93,13 +102,49 @@ pub struct Config {
     pub datetime_represent: DateTimeRepresent,
 
     /// Which comparator to use for the request
-    /// "basic", "robustness" or "occupancy"
+    /// "basic", "robustness", "occupancy", "co2" or "least_walking"
     #[serde(default)]
     pub comparator_type: config::ComparatorType,
 
+    /// Boolean expression over filters that the vehicles (or the stops)
+    /// of the journeys must satisfy, like "network:A AND physical_mode:Bus"
+    /// or "NOT (line:1 OR line:2)"
+    #[serde(default)]
+    pub filter_expression: Option<String>,
+
     pub launch_params: config::LaunchParams,
 
     pub request_params: config::RequestParams,
+
+    /// The stop areas the journeys must go through, in this order
+    #[serde(default)]
+    pub vias: Vec<ViaConfig>,
+
+    /// Penalize or favor the boarding of some vehicles
+    #[serde(default)]
+    pub preferences: Vec<PreferenceConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ViaConfig {
+    /// uri of the stop_area
+    pub stop_area: String,
+
+    /// minimum duration of the stay in the stop area
+    #[serde(default = "PositiveDuration::zero")]
+    pub stay_duration: PositiveDuration,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PreferenceConfig {
+    /// the vehicles concerned, like "line:A" or "physical_mode:Bus"
+    pub uri: String,
+
+    /// number of seconds added at each boarding of a concerned vehicle,
+    /// or removed when negative
+    pub weight: i32,
 }
 
 pub fn run() -> Result<(), Error> {
@@ -107,7 +152,7 @@ pub fn run() -> Result<(), Error> {
 
     let config = read_config(&options.config_file)?;
 
-    launch(config)?;
+    launch_with_trace(config, options.trace_file.as_deref())?;
     Ok(())
 }
 
@@ -119,11 +164,21 @@ pub fn read_config(config_file_path: &Path) -> Result<Config, Error> {
 }
 
 pub fn launch(config: Config) -> Result<(BaseModel, Vec<loki::Response>), Error> {
+    launch_with_trace(config, None)
+}
+
+/// Same as `launch()`, but when `trace_file` is provided,
+/// the trace of the search is written in it as json.
+pub fn launch_with_trace(
+    config: Config,
+    trace_file: Option<&Path>,
+) -> Result<(BaseModel, Vec<loki::Response>), Error> {
     use loki::DataTrait;
 
     let (data, base_model) = loki_launch::read(&config.launch_params)?;
 
     let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+    solver.set_trace_recording(trace_file.is_some());
 
     let real_time_model = RealTimeModel::new();
     let model_refs = ModelRefs::new(&base_model, &real_time_model);
@@ -143,20 +198,54 @@ pub fn launch(config: Config) -> Result<(BaseModel, Vec<loki::Response>), Error>
     let start_stop_area_uri = &config.start_stop_area;
     let end_stop_area_uri = &config.end_stop_area;
 
-    let request_input = loki_launch::stop_areas::make_query_stop_areas(
+    let mut request_input = loki_launch::stop_areas::make_query_stop_areas(
         &base_model,
         &datetime,
         start_stop_area_uri,
         end_stop_area_uri,
         &config.request_params,
+        config.comparator_type,
     )?;
-    let solve_result = solver.solve_journey_request(
+    request_input.via_stops_and_stay_duration = config
+        .vias
+        .iter()
+        .map(|via| {
+            (
+                InputStop::StopArea(via.stop_area.clone()),
+                via.stay_duration,
+            )
+        })
+        .collect();
+    request_input.preferences = config
+        .preferences
+        .iter()
+        .map(|preference| Preference {
+            uri: preference.uri.clone(),
+            weight: preference.weight,
+        })
+        .collect();
+    let has_filters = match &config.filter_expression {
+        Some(expression_str) => {
+            let expression = parse_filter_expression(&model_refs, expression_str, "config")
+                .with_context(|| format!("Error parsing filter expression {}", expression_str))?;
+            Filters::new_with_expression(
+                std::iter::empty(),
+                std::iter::empty(),
+                Some(expression),
+                false,
+                false,
+            )
+        }
+        None => None,
+    };
+
+    let solve_result = solver.solve_journey_request_with_cancellation(
         &data,
         &model_refs,
         &request_input,
-        None,
-        config.comparator_type,
+        has_filters,
         datetime_represent,
+        &Cancellation::none(),
     );
 
     let duration = timer::duration_since(start_compute_time);
@@ -166,18 +255,37 @@ pub fn launch(config: Config) -> Result<(BaseModel, Vec<loki::Response>), Error>
         Err(err) => {
             error!("Error while solving request. {}", err);
         }
-        Ok(responses) => {
-            for response in responses.iter() {
+        Ok(solution) => {
+            for response in solution.journeys.iter() {
                 debug!("{}", response.print(&model_refs)?);
             }
         }
     }
 
-    let responses = solve_result?;
+    let solution = solve_result?;
+
+    if let (Some(trace_file), Some(trace)) = (trace_file, &solution.trace) {
+        write_trace(trace_file, trace)?;
+        info!(
+            "Trace with {} events written in {:?}",
+            trace.nb_of_events(),
+            trace_file
+        );
+    }
+
+    let responses = solution.journeys;
 
     Ok((base_model, responses))
 }
 
+fn write_trace(trace_file: &Path, trace: &loki::SearchTrace) -> Result<(), Error> {
+    let file = fs::File::create(trace_file)
+        .with_context(|| format!("Error creating trace file {:?}", trace_file))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), trace)
+        .with_context(|| format!("Error writing trace in {:?}", trace_file))?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -0,0 +1,258 @@
+// Copyright  (C) 2020, Hove and/or its affiliates. All rights reserved.
+//
+// This file is part of Navitia,
+// the software to build cool stuff with public transport.
+//
+// Hope you'll enjoy and contribute to this project,
+// powered by Hove (www.kisio.com).
+// Help us simplify mobility and open public transport:
+// a non ending quest to the responsive locomotion way of traveling!
+//
+// This contribution is a part of the research and development work of the
+// IVA Project which aims to enhance traveler information and is carried out
+// under the leadership of the Technological Research Institute SystemX,
+// with the partnership and support of the transport organization authority
+// Ile-De-France Mobilités (IDFM), SNCF, and public funds
+// under the scope of the French Program "Investissements d’Avenir".
+//
+// LICENCE: This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Stay tuned using
+// twitter @navitia
+// channel `#navitia` on riot https://riot.im/app/#/room/#navitia:matrix.org
+// https://groups.google.com/d/forum/navitia
+// www.navitia.io
+
+use loki_launch::{
+    config,
+    loki::{
+        self,
+        matrix::MatrixResponse,
+        models::{real_time_model::RealTimeModel, ModelRefs},
+        InputStop, NaiveDateTime, PositiveDuration,
+    },
+    solver::Solver,
+    timer,
+};
+
+use loki::tracing::info;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Error};
+
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "loki_stop_areas_matrix",
+    about = "Compute the travel times between several origin and destination stop areas, \
+    and write them in a csv file.",
+    rename_all = "snake_case"
+)]
+pub struct Options {
+    /// path to the config file
+    #[structopt(parse(from_os_str))]
+    config_file: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// uris of the origin stop_areas
+    pub origin_stop_areas: Vec<String>,
+
+    /// uris of the destination stop_areas
+    pub destination_stop_areas: Vec<String>,
+
+    /// Departure datetime of the queries, formatted like 20190628T163215
+    /// If none is given, all queries will be made at 08:00:00 on the first
+    /// valid day of the dataset
+    pub datetime: Option<String>,
+
+    /// path of the csv file in which the matrix will be written
+    pub output_file: PathBuf,
+
+    pub launch_params: config::LaunchParams,
+
+    pub request_params: config::RequestParams,
+}
+
+const DATETIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+#[derive(Debug, Serialize)]
+struct MatrixRecord<'a> {
+    origin: &'a str,
+    destination: &'a str,
+    departure_datetime: String,
+    arrival_datetime: Option<String>,
+    duration: Option<u32>,
+    nb_of_transfers: Option<u8>,
+}
+
+pub fn run() -> Result<(), Error> {
+    let options = Options::from_args();
+
+    let config = read_config(&options.config_file)?;
+
+    let (datetime, matrix) = launch(&config)?;
+
+    write_csv(&config, &datetime, &matrix)?;
+    Ok(())
+}
+
+pub fn read_config(config_file_path: &Path) -> Result<Config, Error> {
+    let content = fs::read_to_string(config_file_path)
+        .with_context(|| format!("Error opening config file {:?}", &config_file_path))?;
+    let config: Config = toml::from_str(&content)?;
+    Ok(config)
+}
+
+pub fn launch(config: &Config) -> Result<(NaiveDateTime, MatrixResponse), Error> {
+    use loki::DataTrait;
+
+    let (data, base_model) = loki_launch::read(&config.launch_params)?;
+
+    let mut solver = Solver::new(data.nb_of_stops(), data.nb_of_missions());
+
+    let real_time_model = RealTimeModel::new();
+    let model_refs = ModelRefs::new(&base_model, &real_time_model);
+
+    let datetime = departure_datetime(config, &data)?;
+
+    let origins = make_input_stops(&config.origin_stop_areas);
+    let destinations = make_input_stops(&config.destination_stop_areas);
+
+    // departures and arrivals are provided separately to the matrix solver
+    let request_input = loki_launch::loki::RequestInput {
+        datetime,
+        departures_stop_and_fallback_duration: Vec::new(),
+        arrivals_stop_and_fallback_duration: Vec::new(),
+        via_stops_and_stay_duration: Vec::new(),
+        leg_arrival_penalty: config.request_params.leg_arrival_penalty,
+        leg_walking_penalty: config.request_params.leg_walking_penalty,
+        max_nb_of_legs: config.request_params.max_nb_of_legs,
+        max_journey_duration: config.request_params.max_journey_duration,
+        max_walking_duration: config.request_params.max_walking_duration,
+        max_transfer_walking_duration: config.request_params.max_transfer_walking_duration,
+        walking_speed_factor: config.request_params.walking_speed_factor,
+        max_waiting_duration: config.request_params.max_waiting_duration,
+        max_initial_waiting_duration: config.request_params.max_initial_waiting_duration,
+        too_late_threshold: config.request_params.too_late_threshold,
+        real_time_level: config.request_params.real_time_level,
+        lower_bound_pruning: config.request_params.lower_bound_pruning,
+        pareto_slack_duration: config.request_params.pareto_slack_duration,
+        pareto_slack_percentage: config.request_params.pareto_slack_percentage,
+        tighten_journeys: config.request_params.tighten_journeys,
+        comparator: config
+            .request_params
+            .comparator(loki_launch::config::ComparatorType::default()),
+        preferences: Vec::new(),
+    };
+
+    let start_compute_time = SystemTime::now();
+
+    let matrix = solver.solve_matrix(
+        &data,
+        &model_refs,
+        &request_input,
+        &origins,
+        &destinations,
+        None,
+    )?;
+
+    let duration = timer::duration_since(start_compute_time);
+    info!("Duration : {} ms", duration);
+
+    Ok((datetime, matrix))
+}
+
+fn departure_datetime(config: &Config, data: &loki::TransitData) -> Result<NaiveDateTime, Error> {
+    use loki::DataTrait;
+    let datetime = match &config.datetime {
+        Some(string_datetime) => loki_launch::datetime::parse_datetime(string_datetime)?,
+        None => {
+            let naive_date = data.calendar().first_date();
+            naive_date.and_hms_opt(8, 0, 0).unwrap() // 08:00:00 is a valid time
+        }
+    };
+    Ok(datetime)
+}
+
+fn make_input_stops(stop_area_uris: &[String]) -> Vec<Vec<(InputStop, PositiveDuration)>> {
+    stop_area_uris
+        .iter()
+        .map(|stop_area_uri| {
+            vec![(
+                InputStop::StopArea(stop_area_uri.clone()),
+                PositiveDuration::zero(),
+            )]
+        })
+        .collect()
+}
+
+pub fn write_csv(
+    config: &Config,
+    departure_datetime: &NaiveDateTime,
+    matrix: &MatrixResponse,
+) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_path(&config.output_file)
+        .with_context(|| format!("Error creating output file {:?}", &config.output_file))?;
+    let departure_datetime = departure_datetime.format(DATETIME_FORMAT).to_string();
+    for (origin_idx, origin) in config.origin_stop_areas.iter().enumerate() {
+        for (destination_idx, destination) in config.destination_stop_areas.iter().enumerate() {
+            let cell = matrix.cell(origin_idx, destination_idx);
+            let record = MatrixRecord {
+                origin,
+                destination,
+                departure_datetime: departure_datetime.clone(),
+                arrival_datetime: cell
+                    .map(|cell| cell.arrival_datetime.format(DATETIME_FORMAT).to_string()),
+                duration: cell.map(|cell| cell.duration.total_seconds_u32()),
+                nb_of_transfers: cell.map(|cell| cell.nb_of_transfers),
+            };
+            writer.serialize(record)?;
+        }
+    }
+    writer.flush()?;
+    info!("Matrix written in {:?}", &config.output_file);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::read_config;
+    use std::{path::PathBuf, str::FromStr};
+
+    #[test]
+    fn test_config() {
+        let path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR"))
+            .unwrap()
+            .join("matrix_config.toml");
+
+        let read_result = read_config(&path);
+        assert!(
+            read_config(&path).is_ok(),
+            "Error while reading config file {:?} : {:?}",
+            &path,
+            read_result
+        );
+    }
+}